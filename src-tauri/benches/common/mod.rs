@@ -0,0 +1,41 @@
+//! 各基准共用的计时工具
+//!
+//! 每个基准是独立的crate，只用到这里的一部分。
+#![allow(dead_code)]
+
+use std::time::{Duration, Instant};
+
+/// 每项计时的运行次数，报告中位耗时和最快耗时
+pub const RUNS: usize = 3;
+
+/// 一项计时的结果
+pub struct Timing<T> {
+    /// 单次调用的中位耗时
+    pub median: Duration,
+    /// 单次调用的最快耗时
+    pub fastest: Duration,
+    /// 最后一次调用的结果，用于确认不同实现的结果一致
+    pub output: T,
+}
+
+/// 运行`RUNS`次，每次连续调用`iterations`遍，按单次调用计时
+///
+/// 很快的操作应设置较大的`iterations`，使每次运行的耗时远大于计时精度。
+pub fn measure<T>(iterations: usize, mut task: impl FnMut() -> T) -> Timing<T> {
+    let iterations = iterations.max(1);
+    let mut timings = Vec::with_capacity(RUNS);
+    let mut output = None;
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        for _ in 0..iterations {
+            output = Some(std::hint::black_box(task()));
+        }
+        timings.push(start.elapsed() / iterations as u32);
+    }
+    timings.sort();
+    Timing {
+        median: timings[RUNS / 2],
+        fastest: timings[0],
+        output: output.expect("RUNS至少为1"),
+    }
+}
@@ -0,0 +1,128 @@
+//! 密集簇分组的基准：200张几乎相同的图像，几乎所有图像对都是候选对
+//!
+//! 运行：`cargo bench --bench dense_cluster`
+//!
+//! `find_duplicate_groups`分批计算相似度，跳过两端已在同一组中的候选对；
+//! 对照组逐一计算全部候选对的相似度后再合并（跳过之前的做法）。
+//! 两者使用相同的LSH候选对，并确认得到的分组一致。分别用感知哈希和ORB特征测量。
+
+mod common;
+
+use common::{measure, RUNS};
+use delo_lib::{
+    calculate_hash_from_image, calculate_similarity, compute_candidate_pairs, find_duplicate_groups,
+    DuplicateDetectionParams, HashAlgorithm, HashOptions, ImageInfo,
+};
+use image::{DynamicImage, GrayImage, Luma};
+
+const IMAGES: usize = 200;
+const BATCH_SIZE: usize = 10_000;
+
+/// 噪声纹理上叠加随机矩形，每个副本只在少量像素上叠加噪声
+fn near_identical_images() -> Vec<DynamicImage> {
+    let mut rng = fastrand::Rng::with_seed(381);
+    // 低分辨率随机噪声放大后得到不重复的纹理，再叠加矩形产生清晰的角点
+    let noise = GrayImage::from_fn(40, 30, |_, _| Luma([rng.u8(..)]));
+    let mut base = image::imageops::resize(&noise, 320, 240, image::imageops::FilterType::Triangle);
+    for _ in 0..60 {
+        let (x, y) = (rng.u32(..300), rng.u32(..220));
+        let (w, h) = (rng.u32(8..40), rng.u32(8..40));
+        let value = Luma([rng.u8(..)]);
+        for py in y..(y + h).min(240) {
+            for px in x..(x + w).min(320) {
+                base.put_pixel(px, py, value);
+            }
+        }
+    }
+
+    (0..IMAGES)
+        .map(|_| {
+            let mut copy = base.clone();
+            for _ in 0..200 {
+                let (x, y) = (rng.u32(..320), rng.u32(..240));
+                let pixel = copy.get_pixel_mut(x, y);
+                pixel[0] = pixel[0].saturating_add_signed(rng.i8(-6..=6));
+            }
+            DynamicImage::ImageLuma8(copy)
+        })
+        .collect()
+}
+
+fn synthetic_infos(count: usize) -> Vec<ImageInfo> {
+    (0..count)
+        .map(|i| ImageInfo {
+            path: format!("/dense/{:03}.png", i),
+            hash: String::new(),
+            algorithm: HashAlgorithm::Perceptual,
+            width: 320,
+            height: 240,
+            size_bytes: 50_000,
+            created_at: String::new(),
+            modified_at: String::new(),
+            source_root: String::new(),
+            thumbnail: None,
+        })
+        .collect()
+}
+
+/// 对照组：计算全部候选对的相似度，再用并查集合并，返回排好序的各组大小
+fn group_sizes_without_skip(hashes: &[String], algorithm: HashAlgorithm, threshold: f32) -> Vec<usize> {
+    let pairs = compute_candidate_pairs(hashes, algorithm, BATCH_SIZE, None);
+    let similar: Vec<(usize, usize)> = pairs.into_iter()
+        .filter(|&(i, j)| calculate_similarity(&hashes[i], &hashes[j], algorithm) >= threshold)
+        .collect();
+
+    let mut parent: Vec<usize> = (0..hashes.len()).collect();
+    fn find(parent: &mut [usize], x: usize) -> usize {
+        if parent[x] != x {
+            parent[x] = find(parent, parent[x]);
+        }
+        parent[x]
+    }
+    for (i, j) in similar {
+        let (root_i, root_j) = (find(&mut parent, i), find(&mut parent, j));
+        parent[root_i] = root_j;
+    }
+
+    let mut counts = vec![0; hashes.len()];
+    for i in 0..hashes.len() {
+        counts[find(&mut parent, i)] += 1;
+    }
+    let mut sizes: Vec<usize> = counts.into_iter().filter(|&count| count > 1).collect();
+    sizes.sort_unstable_by(|a, b| b.cmp(a));
+    sizes
+}
+
+fn bench_algorithm(images: &[DynamicImage], algorithm: HashAlgorithm) {
+    let options = HashOptions::default();
+    let hashes: Vec<String> = images.iter()
+        .map(|img| calculate_hash_from_image(img, algorithm, &options).expect("哈希计算失败").hash)
+        .collect();
+    let threshold = algorithm.default_threshold();
+    let infos = synthetic_infos(hashes.len());
+    let params = DuplicateDetectionParams::new(Vec::new(), algorithm, threshold, false);
+    let candidates = compute_candidate_pairs(&hashes, algorithm, BATCH_SIZE, None).len();
+
+    let full = measure(1, || group_sizes_without_skip(&hashes, algorithm, threshold));
+    let skip = measure(1, || {
+        let mut sizes: Vec<usize> = find_duplicate_groups(&hashes, &infos, &params)
+            .expect("分组失败")
+            .iter()
+            .map(|group| group.images.len())
+            .collect();
+        sizes.sort_unstable_by(|a, b| b.cmp(a));
+        sizes
+    });
+    assert_eq!(skip.output, full.output, "跳过已连通的候选对不应改变分组");
+
+    println!("{}（阈值 {}，{} 个候选对，组大小 {:?}）", algorithm, threshold, candidates, skip.output);
+    println!("  计算全部候选对: 中位 {:?}", full.median);
+    println!("  跳过已连通的候选对: 中位 {:?}", skip.median);
+}
+
+fn main() {
+    let images = near_identical_images();
+    println!("{} 张几乎相同的图像，{} 次运行", images.len(), RUNS);
+    bench_algorithm(&images, HashAlgorithm::Perceptual);
+    bench_algorithm(&images, HashAlgorithm::ORB);
+}
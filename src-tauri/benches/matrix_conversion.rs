@@ -0,0 +1,67 @@
+//! 灰度图像与浮点矩阵互相转换的基准：256x256的随机灰度图像
+//!
+//! 运行：`cargo bench --bench matrix_conversion`
+//!
+//! 旧的逐像素`get_pixel`/`put_pixel`实现已不在代码中，这里复现后与当前按行顺序处理的实现
+//! 在同一图像上计时，并确认两者结果一致。
+
+mod common;
+
+use common::measure;
+use delo_lib::{gray_image_to_matrix, matrix_to_gray_image};
+use image::{GrayImage, Luma};
+
+const SIZE: u32 = 256;
+const ITERATIONS: usize = 2_000;
+
+fn synthetic_image() -> GrayImage {
+    let mut rng = fastrand::Rng::with_seed(395);
+    GrayImage::from_fn(SIZE, SIZE, |_, _| Luma([rng.u8(..)]))
+}
+
+/// 旧实现：预先分配矩阵后逐像素读取
+#[allow(clippy::needless_range_loop)] // 按原样复现旧的下标循环
+fn to_matrix_per_pixel(img: &GrayImage) -> Vec<Vec<f64>> {
+    let (width, height) = img.dimensions();
+    let mut matrix = vec![vec![0.0f64; width as usize]; height as usize];
+    for y in 0..height {
+        for x in 0..width {
+            matrix[y as usize][x as usize] = img.get_pixel(x, y)[0] as f64;
+        }
+    }
+    matrix
+}
+
+/// 旧实现：逐像素取整、截断后写入
+#[allow(clippy::needless_range_loop)] // 按原样复现旧的下标循环
+fn to_image_per_pixel(matrix: &[Vec<f64>]) -> GrayImage {
+    let (height, width) = (matrix.len(), matrix[0].len());
+    let mut img = GrayImage::new(width as u32, height as u32);
+    for y in 0..height {
+        for x in 0..width {
+            let value = matrix[y][x].round().clamp(0.0, 255.0) as u8;
+            img.put_pixel(x as u32, y as u32, Luma([value]));
+        }
+    }
+    img
+}
+
+fn main() {
+    let img = synthetic_image();
+    println!("{}x{} 灰度图像，每次计时 {} 遍", SIZE, SIZE, ITERATIONS);
+
+    let old_to_matrix = measure(ITERATIONS, || to_matrix_per_pixel(&img));
+    let new_to_matrix = measure(ITERATIONS, || gray_image_to_matrix(&img));
+    assert_eq!(old_to_matrix.output, new_to_matrix.output, "两种实现得到的矩阵应一致");
+    println!("  图像→矩阵: 逐像素 {:?}/次，按行 {:?}/次", old_to_matrix.median, new_to_matrix.median);
+
+    // 带小数和越界值的矩阵，覆盖取整和截断
+    let mut rng = fastrand::Rng::with_seed(256);
+    let matrix: Vec<Vec<f64>> = (0..SIZE)
+        .map(|_| (0..SIZE).map(|_| rng.f64() * 300.0 - 20.0).collect())
+        .collect();
+    let old_to_image = measure(ITERATIONS, || to_image_per_pixel(&matrix));
+    let new_to_image = measure(ITERATIONS, || matrix_to_gray_image(&matrix));
+    assert_eq!(old_to_image.output, new_to_image.output, "两种实现得到的图像应一致");
+    println!("  矩阵→图像: 逐像素 {:?}/次，按行 {:?}/次", old_to_image.median, new_to_image.median);
+}
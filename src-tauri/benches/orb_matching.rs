@@ -0,0 +1,128 @@
+//! ORB描述子匹配的基准：合成纹理图像与其裁剪版本，每张约50个描述子
+//!
+//! 运行：`cargo bench --bench orb_matching`
+//!
+//! 旧的完整距离矩阵实现已不在代码中，这里在同样解码出的描述子上复现两种最近邻搜索
+//! （n*m距离矩阵后再扫描 vs 逐个描述子单次扫描），只比较搜索本身；
+//! 另外计时当前的`calculate_similarity`（包含Base64解码、金字塔层级惩罚和几何验证）。
+
+mod common;
+
+use common::measure;
+use base64::{engine::general_purpose, Engine as _};
+use delo_lib::{calculate_hash_from_image, calculate_similarity, HashAlgorithm, HashOptions};
+use image::{imageops, DynamicImage, Luma, GrayImage};
+
+const ITERATIONS: usize = 20_000;
+const RATIO_THRESHOLD: f32 = 0.8;
+const MAX_DISTANCE: u32 = 80;
+
+/// 版本2序列化格式：4字节文件头、u32描述子数量，每个描述子依次为x、y、角度、层级和32字节描述子
+const HEADER_SIZE: usize = 4 + 4;
+const RECORD_SIZE: usize = 4 + 4 + 4 + 1 + 32;
+
+/// 随机放置的矩形，边缘和交叉处产生足够的角点
+fn synthetic_image() -> GrayImage {
+    let mut rng = fastrand::Rng::with_seed(339);
+    let mut img = GrayImage::from_pixel(640, 480, Luma([128]));
+    for _ in 0..120 {
+        let (x, y) = (rng.u32(..600), rng.u32(..440));
+        let (w, h) = (rng.u32(10..80), rng.u32(10..80));
+        let value = Luma([rng.u8(..)]);
+        for py in y..(y + h).min(480) {
+            for px in x..(x + w).min(640) {
+                img.put_pixel(px, py, value);
+            }
+        }
+    }
+    img
+}
+
+fn orb_features(img: GrayImage) -> String {
+    calculate_hash_from_image(&DynamicImage::ImageLuma8(img), HashAlgorithm::ORB, &HashOptions::default())
+        .expect("ORB特征提取失败")
+        .hash
+}
+
+fn decode_descriptors(features: &str) -> Vec<[u8; 32]> {
+    let data = general_purpose::STANDARD.decode(features).expect("无效的Base64");
+    data[HEADER_SIZE..]
+        .chunks_exact(RECORD_SIZE)
+        .map(|record| record[RECORD_SIZE - 32..].try_into().unwrap())
+        .collect()
+}
+
+fn hamming(a: &[u8; 32], b: &[u8; 32]) -> u32 {
+    a.iter().zip(b).map(|(x, y)| (x ^ y).count_ones()).sum()
+}
+
+/// Lowe比率测试，两种实现共用
+fn accept(best: u32, second: u32) -> bool {
+    let ratio = if second == u32::MAX { 0.0 } else { best as f32 / second as f32 };
+    best < MAX_DISTANCE && ratio < RATIO_THRESHOLD
+}
+
+/// 旧实现：先分配完整的n*m距离矩阵，再逐行查找最近和次近邻
+fn match_with_matrix(descriptors1: &[[u8; 32]], descriptors2: &[[u8; 32]]) -> Vec<(usize, usize)> {
+    let distance_matrix: Vec<Vec<u32>> = descriptors1.iter()
+        .map(|d1| descriptors2.iter().map(|d2| hamming(d1, d2)).collect())
+        .collect();
+
+    let mut matches = Vec::new();
+    for (i, distances) in distance_matrix.iter().enumerate() {
+        let (mut best, mut second, mut best_idx) = (u32::MAX, u32::MAX, 0);
+        for (j, &distance) in distances.iter().enumerate() {
+            if distance < best {
+                second = best;
+                best = distance;
+                best_idx = j;
+            } else if distance < second {
+                second = distance;
+            }
+        }
+        if accept(best, second) {
+            matches.push((i, best_idx));
+        }
+    }
+    matches
+}
+
+/// 新实现：每个描述子单次扫描候选，不保存距离
+fn match_single_pass(descriptors1: &[[u8; 32]], descriptors2: &[[u8; 32]]) -> Vec<(usize, usize)> {
+    descriptors1.iter()
+        .enumerate()
+        .filter_map(|(i, d1)| {
+            let (mut best, mut second, mut best_idx) = (u32::MAX, u32::MAX, 0);
+            for (j, d2) in descriptors2.iter().enumerate() {
+                let distance = hamming(d1, d2);
+                if distance < best {
+                    second = best;
+                    best = distance;
+                    best_idx = j;
+                } else if distance < second {
+                    second = distance;
+                }
+            }
+            accept(best, second).then_some((i, best_idx))
+        })
+        .collect()
+}
+
+fn main() {
+    let original = synthetic_image();
+    let cropped = imageops::crop_imm(&original, 40, 30, 560, 420).to_image();
+    let features1 = orb_features(original);
+    let features2 = orb_features(cropped);
+    let descriptors1 = decode_descriptors(&features1);
+    let descriptors2 = decode_descriptors(&features2);
+    println!("描述子数量: {} 与 {}，每次计时 {} 遍", descriptors1.len(), descriptors2.len(), ITERATIONS);
+
+    let matrix = measure(ITERATIONS, || match_with_matrix(&descriptors1, &descriptors2));
+    let single = measure(ITERATIONS, || match_single_pass(&descriptors1, &descriptors2));
+    assert_eq!(matrix.output, single.output, "两种搜索的匹配结果应一致");
+    println!("  完整距离矩阵: {:?}/次，{} 个匹配", matrix.median, matrix.output.len());
+    println!("  单次扫描: {:?}/次，{} 个匹配", single.median, single.output.len());
+
+    let similarity = measure(ITERATIONS, || calculate_similarity(&features1, &features2, HashAlgorithm::ORB));
+    println!("  calculate_similarity: {:?}/次，相似度 {:.1}", similarity.median, similarity.output);
+}
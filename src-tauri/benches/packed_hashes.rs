@@ -0,0 +1,89 @@
+//! 合成数据上的分组基准：5万张图像的64位感知哈希
+//!
+//! 运行：`cargo bench --bench packed_hashes`
+//!
+//! 哈希由5000个随机基准哈希各派生10个变体（随机翻转0-3位）组成，
+//! 不读取任何文件，只测量候选对生成、预过滤、相似度计算和分组的耗时。
+//! “候选对与相似度”一组在同样的数据上分别计时字符串路径和打包`u64`路径，
+//! 打包路径包含字符串到`u64`的转换。
+
+mod common;
+
+use common::{measure, RUNS};
+use delo_lib::{
+    binary_to_u64, compute_candidate_pairs, compute_packed_candidate_pairs, find_duplicate_groups,
+    hash_similarity, packed_hash_similarity, DuplicateDetectionParams, HashAlgorithm, ImageInfo,
+};
+
+const CLUSTERS: usize = 5_000;
+const VARIANTS_PER_CLUSTER: usize = 10;
+const MAX_FLIPPED_BITS: usize = 3;
+const THRESHOLD: f32 = 90.0;
+const BATCH_SIZE: usize = 10_000;
+
+fn synthetic_hashes(rng: &mut fastrand::Rng) -> Vec<String> {
+    let mut hashes = Vec::with_capacity(CLUSTERS * VARIANTS_PER_CLUSTER);
+    for _ in 0..CLUSTERS {
+        let base = rng.u64(..);
+        for _ in 0..VARIANTS_PER_CLUSTER {
+            let mut variant = base;
+            for _ in 0..rng.usize(..=MAX_FLIPPED_BITS) {
+                variant ^= 1 << rng.u32(..64);
+            }
+            hashes.push(format!("{:064b}", variant));
+        }
+    }
+    rng.shuffle(&mut hashes);
+    hashes
+}
+
+fn synthetic_images(count: usize) -> Vec<ImageInfo> {
+    (0..count)
+        .map(|i| ImageInfo {
+            path: format!("/synthetic/{:05}.jpg", i),
+            hash: String::new(),
+            algorithm: HashAlgorithm::Perceptual,
+            width: 1024,
+            height: 768,
+            size_bytes: 200_000,
+            created_at: String::new(),
+            modified_at: String::new(),
+            source_root: String::new(),
+            thumbnail: None,
+        })
+        .collect()
+}
+
+fn main() {
+    let mut rng = fastrand::Rng::with_seed(42);
+    let hashes = synthetic_hashes(&mut rng);
+    let images = synthetic_images(hashes.len());
+    let algorithm = HashAlgorithm::Perceptual;
+    let params = DuplicateDetectionParams::new(Vec::new(), algorithm, THRESHOLD, false);
+
+    println!("候选对与相似度（{} 张图像，阈值 {}）", hashes.len(), THRESHOLD);
+    let string = measure(1, || {
+        compute_candidate_pairs(&hashes, algorithm, BATCH_SIZE, None)
+            .into_iter()
+            .filter(|&(i, j)| hash_similarity(&hashes[i], &hashes[j]) >= THRESHOLD)
+            .count()
+    });
+    println!("  字符串: 中位 {:?}（最快 {:?}），{} 对超过阈值", string.median, string.fastest, string.output);
+
+    let packed = measure(1, || {
+        let packed: Vec<u64> = hashes.iter().map(|hash| binary_to_u64(hash).expect("64位二值哈希")).collect();
+        compute_packed_candidate_pairs(&packed, algorithm, BATCH_SIZE, None)
+            .into_iter()
+            .filter(|&(i, j)| packed_hash_similarity(packed[i], packed[j]) >= THRESHOLD)
+            .count()
+    });
+    println!("  打包u64: 中位 {:?}（最快 {:?}），{} 对超过阈值", packed.median, packed.fastest, packed.output);
+    assert_eq!(packed.output, string.output, "两种路径的结果应一致");
+    println!("  加速比: {:.2}x", string.median.as_secs_f64() / packed.median.as_secs_f64());
+
+    let grouping = measure(1, || find_duplicate_groups(&hashes, &images, &params).expect("分组失败"));
+    println!(
+        "完整分组: {} 张图像，{} 组重复；{} 次运行的中位耗时 {:?}（最快 {:?}）",
+        hashes.len(), grouping.output.len(), RUNS, grouping.median, grouping.fastest
+    );
+}
@@ -0,0 +1,54 @@
+//! 均值哈希缩放滤波器的基准：3000x2000的合成图像缩放到8x8
+//!
+//! 运行：`cargo bench --bench resize_filter`
+//!
+//! 对每种`ResizeFilter`分别计时单独的8x8缩放，以及包含默认解码尺寸限制、
+//! 灰度转换和阈值化的完整均值哈希（`calculate_hash_from_image`）。
+
+mod common;
+
+use common::{measure, RUNS};
+use delo_lib::{calculate_hash_from_image, HashAlgorithm, HashOptions, ResizeFilter};
+use image::{DynamicImage, Rgb, RgbImage};
+
+const WIDTH: u32 = 3000;
+const HEIGHT: u32 = 2000;
+
+const FILTERS: [ResizeFilter; 5] = [
+    ResizeFilter::Nearest,
+    ResizeFilter::Triangle,
+    ResizeFilter::CatmullRom,
+    ResizeFilter::Gaussian,
+    ResizeFilter::Lanczos3,
+];
+
+/// 渐变叠加随机噪声，避免纯色区域让缩放过于简单
+fn synthetic_image() -> DynamicImage {
+    let mut rng = fastrand::Rng::with_seed(316);
+    DynamicImage::ImageRgb8(RgbImage::from_fn(WIDTH, HEIGHT, |x, y| {
+        let base = ((x * 255 / WIDTH) as u8, (y * 255 / HEIGHT) as u8, ((x + y) % 256) as u8);
+        Rgb([base.0 ^ rng.u8(..32), base.1 ^ rng.u8(..32), base.2 ^ rng.u8(..32)])
+    }))
+}
+
+fn main() {
+    let img = synthetic_image();
+    println!("{}x{} 图像缩放到8x8，{} 次运行", WIDTH, HEIGHT, RUNS);
+
+    for filter in FILTERS {
+        let resize = measure(1, || img.resize_exact(8, 8, filter.filter_type()));
+
+        let options = HashOptions {
+            resize_filter: filter,
+            ..Default::default()
+        };
+        let hash = measure(1, || {
+            calculate_hash_from_image(&img, HashAlgorithm::Average, &options).expect("均值哈希失败")
+        });
+
+        println!(
+            "  {:?}: 缩放 中位 {:?}（最快 {:?}）；完整均值哈希 中位 {:?}（最快 {:?}）",
+            filter, resize.median, resize.fastest, hash.median, hash.fastest
+        );
+    }
+}
@@ -1,39 +1,49 @@
 use std::path::Path;
-use image::GenericImageView;
-use crate::core::types::HashResult;
+use image::DynamicImage;
+use crate::core::types::{HashAlgorithm, HashError, HashOptions, HashResult};
 use crate::core::utils::image_utils;
 
 /// 计算图片的均值哈希 (Average Hash / aHash)
 /// 
 /// 均值哈希算法步骤:
+/// 0. （可选）亮度/对比度归一化，抵消曝光调整
 /// 1. 将图像缩放为8x8大小(去除高频细节与图像大小依赖)
 /// 2. 将图像转换为灰度图
 /// 3. 计算灰度图像的平均值
 /// 4. 根据每个像素与平均值的比较生成64位哈希
 /// 
 /// 这种算法对于缩放和小变化具有一定的鲁棒性。
-pub fn calculate_average_hash(path: &Path) -> Result<HashResult, String> {
+pub fn calculate_average_hash(path: &Path, options: &HashOptions) -> Result<HashResult, HashError> {
     // 打开图像
-    let img = image_utils::open_image(path)?;
-    let (width, height) = img.dimensions();
+    let (img, width, height) = image_utils::open_image_downscaled(
+        path, options.max_pixels, options.max_decode_dimension)?;
+    let color_signature = options.color_signature.then(|| image_utils::color_signature(&img));
+    
+    Ok(HashResult {
+        hash: calculate_average_hash_from_image(img, options),
+        algorithm: HashAlgorithm::Average,
+        color_signature,
+        width, 
+        height,
+    })
+}
+
+/// 对已解码的图像计算均值哈希，步骤与`calculate_average_hash`相同
+pub fn calculate_average_hash_from_image(img: DynamicImage, options: &HashOptions) -> String {
+    let img = image_utils::preprocess_normalize(img, options.normalize, options.grayscale);
+    let img = image_utils::preprocess_equalize(img, options.equalize_histogram, options.grayscale);
     
     // 缩放图像为8x8
-    let small_img = image_utils::resize_image(&img, 8, 8);
+    let small_img = image_utils::resize_image_with(&img, 8, 8, options.resize_filter.filter_type());
     
     // 转换为灰度图
-    let gray_img = image_utils::to_grayscale(&small_img);
+    let gray_img = image_utils::to_grayscale_with(&small_img, options.grayscale);
     
     // 计算平均像素值
     let average = image_utils::average_pixel_value(&gray_img);
     
     // 生成哈希值
-    let hash = image_utils::generate_bits_from_threshold(&gray_img, average);
-    
-    Ok(HashResult {
-        hash,
-        width, 
-        height,
-    })
+    image_utils::generate_bits_from_threshold(&gray_img, average)
 }
 
 /// 计算两个均值哈希的相似度
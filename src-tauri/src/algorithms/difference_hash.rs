@@ -1,27 +1,43 @@
 use std::path::Path;
-use image::GenericImageView;
-use crate::core::types::HashResult;
+use image::DynamicImage;
+use crate::core::types::{HashAlgorithm, HashError, HashOptions, HashResult};
 use crate::core::utils::image_utils;
 
 /// 计算图片的差值哈希 (Difference Hash / dHash)
 /// 
 /// 差值哈希算法步骤:
+/// 0. （可选）亮度/对比度归一化，抵消曝光调整
 /// 1. 将图像缩放为9x8大小(比均值哈希多一列用于计算相邻像素差异)
 /// 2. 将图像转换为灰度图
 /// 3. 计算相邻像素的差值
 /// 4. 根据差值的正负生成64位哈希
 /// 
 /// 相比均值哈希，差值哈希能更好地捕捉图像的纹理特征和边缘信息。
-pub fn calculate_difference_hash(path: &Path) -> Result<HashResult, String> {
+pub fn calculate_difference_hash(path: &Path, options: &HashOptions) -> Result<HashResult, HashError> {
     // 打开图像
-    let img = image_utils::open_image(path)?;
-    let (width, height) = img.dimensions();
+    let (img, width, height) = image_utils::open_image_downscaled(
+        path, options.max_pixels, options.max_decode_dimension)?;
+    let color_signature = options.color_signature.then(|| image_utils::color_signature(&img));
+    
+    Ok(HashResult {
+        hash: calculate_difference_hash_from_image(img, options),
+        algorithm: HashAlgorithm::Difference,
+        color_signature,
+        width,
+        height,
+    })
+}
+
+/// 对已解码的图像计算差值哈希，步骤与`calculate_difference_hash`相同
+pub fn calculate_difference_hash_from_image(img: DynamicImage, options: &HashOptions) -> String {
+    let img = image_utils::preprocess_normalize(img, options.normalize, options.grayscale);
+    let img = image_utils::preprocess_equalize(img, options.equalize_histogram, options.grayscale);
     
     // 缩放图像为9x8 (多一列用于比较差值)
-    let small_img = image_utils::resize_image(&img, 9, 8);
+    let small_img = image_utils::resize_image_with(&img, 9, 8, options.resize_filter.filter_type());
     
     // 转换为灰度图
-    let gray_img = image_utils::to_grayscale(&small_img);
+    let gray_img = image_utils::to_grayscale_with(&small_img, options.grayscale);
     
     // 生成哈希值
     let mut hash = String::with_capacity(64);
@@ -37,11 +53,7 @@ pub fn calculate_difference_hash(path: &Path) -> Result<HashResult, String> {
         }
     }
     
-    Ok(HashResult {
-        hash,
-        width,
-        height,
-    })
+    hash
 }
 
 /// 计算两个差值哈希的相似度
@@ -0,0 +1,56 @@
+use std::path::Path;
+use image::DynamicImage;
+use crate::core::types::{HashAlgorithm, HashError, HashOptions, HashResult};
+use crate::core::utils::{image_utils, math_utils};
+
+/// 计算图片的边缘哈希 (Edge Hash)
+///
+/// 边缘哈希算法步骤:
+/// 1. 将图像缩放为34x34大小并转换为灰度图
+/// 2. 使用Sobel算子计算梯度幅值，得到32x32的边缘强度图
+/// 3. 将边缘强度图按4x4的块求平均，得到8x8的网格
+/// 4. 根据每个网格值与中位数的比较生成64位哈希
+///
+/// 边缘结构不受颜色和整体亮度变化影响，适合线稿、截图等图像。
+pub fn calculate_edge_hash(path: &Path, options: &HashOptions) -> Result<HashResult, HashError> {
+    // 打开图像
+    let (img, width, height) = image_utils::open_image_downscaled(
+        path, options.max_pixels, options.max_decode_dimension)?;
+    let color_signature = options.color_signature.then(|| image_utils::color_signature(&img));
+    
+    Ok(HashResult {
+        hash: calculate_edge_hash_from_image(img, options),
+        algorithm: HashAlgorithm::EdgeHash,
+        color_signature,
+        width,
+        height,
+    })
+}
+
+/// 对已解码的图像计算边缘哈希，步骤与`calculate_edge_hash`相同
+pub fn calculate_edge_hash_from_image(img: DynamicImage, options: &HashOptions) -> String {
+    let img = image_utils::preprocess_equalize(img, options.equalize_histogram, options.grayscale);
+    
+    // 缩放图像为34x34 (Sobel算子不计算边界像素，结果为32x32)
+    let small_img = image_utils::resize_image_with(&img, 34, 34, options.resize_filter.filter_type());
+    
+    // 转换为灰度图
+    let gray_img = image_utils::to_grayscale_with(&small_img, options.grayscale);
+    let matrix = image_utils::gray_image_to_matrix(&gray_img);
+    
+    // 计算梯度幅值
+    let magnitude = image_utils::sobel_magnitude(&matrix);
+    
+    // 按4x4的块求平均，得到8x8网格
+    let mut grid = vec![vec![0.0f64; 8]; 8];
+    for (y, row) in magnitude.iter().enumerate() {
+        for (x, &value) in row.iter().enumerate() {
+            grid[y / 4][x / 4] += value / 16.0;
+        }
+    }
+    
+    // 以中位数为阈值生成哈希值
+    let values: Vec<f64> = grid.iter().flatten().copied().collect();
+    let median = math_utils::median_of(&values);
+    image_utils::threshold_matrix(&grid, median)
+}
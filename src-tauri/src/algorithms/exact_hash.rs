@@ -1,6 +1,6 @@
-use crate::core::types::HashResult;
+use crate::core::types::{HashAlgorithm, HashError, HashOptions, HashResult};
 use crate::core::utils::image_utils;
-use image::GenericImageView;
+use image::{DynamicImage, GenericImageView};
 use sha2::{Digest, Sha256};
 use std::path::Path;
 
@@ -10,27 +10,27 @@ use std::path::Path;
 /// 对于完全相同的图像会生成相同的哈希值，但对图像的任何改变都非常敏感。
 ///
 /// 这种算法适用于寻找完全相同的图像，但不适用于寻找相似的图像。
-pub fn calculate_exact_hash(path: &Path) -> Result<HashResult, String> {
+pub fn calculate_exact_hash(path: &Path, options: &HashOptions) -> Result<HashResult, HashError> {
     // 打开图像
-    let img = image_utils::open_image(path)?;
+    let img = image_utils::open_image_with_limit(path, options.max_pixels)?;
     let (width, height) = img.dimensions();
 
-    // 使用SHA-256计算哈希值
-    let mut hasher = Sha256::new();
-
-    // 将图像数据送入哈希计算器
-    hasher.update(img.into_bytes());
-
-    // 生成最终哈希值并转换为十六进制字符串
-    let hash = format!("{:x}", hasher.finalize());
-
     Ok(HashResult {
-        hash,
+        hash: calculate_exact_hash_from_image(&img),
+        algorithm: HashAlgorithm::Exact,
+        color_signature: None,
         width,
         height,
     })
 }
 
+/// 计算已解码图像像素数据的SHA-256哈希值
+pub fn calculate_exact_hash_from_image(img: &DynamicImage) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(img.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
 /// 比较两个精确哈希的相似度
 /// 对于精确哈希，仅当哈希完全相同时返回100%，否则返回0%
 pub fn compare_exact_hash(hash1: &str, hash2: &str) -> f32 {
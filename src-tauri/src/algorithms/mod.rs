@@ -2,26 +2,83 @@ pub mod exact_hash;
 pub mod average_hash;
 pub mod difference_hash; 
 pub mod perceptual_hash;
+pub mod edge_hash;
+pub mod scaled_duplicate;
 pub mod orb;
+#[cfg(feature = "video")]
+pub mod video;
 // pub mod orb_hash;
 
 use std::path::Path;
-use crate::core::types::{HashAlgorithm, HashResult};
+use image::{DynamicImage, GenericImageView};
+use crate::core::types::{HashAlgorithm, HashError, HashOptions, HashResult, SimilarityExplanation};
+use crate::core::utils::image_utils;
 
 /// 计算图像哈希的统一接口
-pub fn calculate_hash(path: &Path, algorithm: HashAlgorithm) -> Result<HashResult, String> {
+///
+/// 启用`video`功能时，视频文件在精确哈希下计算文件内容的SHA-256，
+/// 其他算法都使用关键帧哈希。
+pub fn calculate_hash(path: &Path, algorithm: HashAlgorithm, options: &HashOptions) -> Result<HashResult, HashError> {
+    #[cfg(feature = "video")]
+    if crate::core::utils::file_utils::is_video_file(path) {
+        // 关键帧哈希只能说明画面相似，不能作为精确哈希
+        if algorithm == HashAlgorithm::Exact {
+            return video::calculate_video_exact_hash(path);
+        }
+        // 视频哈希可以与任意非精确算法的图像哈希比较，记为本次请求的算法
+        return video::calculate_video_hash(path, options)
+            .map(|result| HashResult { algorithm, ..result });
+    }
+
     match algorithm {
-        HashAlgorithm::Exact => exact_hash::calculate_exact_hash(path),
-        HashAlgorithm::Average => average_hash::calculate_average_hash(path),
-        HashAlgorithm::Difference => difference_hash::calculate_difference_hash(path),
-        HashAlgorithm::Perceptual => perceptual_hash::calculate_perceptual_hash(path),
-        HashAlgorithm::ORB => orb::calculate_orb_features(path),
+        HashAlgorithm::Exact => exact_hash::calculate_exact_hash(path, options),
+        HashAlgorithm::Average => average_hash::calculate_average_hash(path, options),
+        HashAlgorithm::Difference => difference_hash::calculate_difference_hash(path, options),
+        HashAlgorithm::Perceptual => perceptual_hash::calculate_perceptual_hash(path, options),
+        HashAlgorithm::EdgeHash => edge_hash::calculate_edge_hash(path, options),
+        HashAlgorithm::ORB => orb::calculate_orb_features(path, options),
+        HashAlgorithm::ScaledDuplicate => scaled_duplicate::calculate_scaled_duplicate_hash(path, options),
         // HashAlgorithm::ORB => orb_hash::calculate_orb_hash(path),
     }
 }
 
+/// 对已解码的图像计算哈希，供不在磁盘上的图像（压缩包、远程来源、视频帧等）使用
+///
+/// 与按路径计算时相同，先把图像缩小到`options.max_decode_dimension`以内（精确哈希除外），
+/// 因此同一张图像从文件或从内存计算得到的哈希一致。
+pub fn calculate_hash_from_image(img: &DynamicImage, algorithm: HashAlgorithm, options: &HashOptions) -> Result<HashResult, HashError> {
+    let (width, height) = img.dimensions();
+    
+    let downscaled = || image_utils::downscale(img, options.max_decode_dimension);
+    // 与按路径计算时相同，只有二值哈希算法计算颜色签名
+    let is_binary = matches!(algorithm, HashAlgorithm::Average | HashAlgorithm::Difference |
+                                        HashAlgorithm::Perceptual | HashAlgorithm::EdgeHash |
+                                        HashAlgorithm::ScaledDuplicate);
+    let color_signature = (options.color_signature && is_binary)
+        .then(|| image_utils::color_signature(&downscaled()));
+    let hash = match algorithm {
+        HashAlgorithm::Exact => exact_hash::calculate_exact_hash_from_image(img),
+        HashAlgorithm::Average => average_hash::calculate_average_hash_from_image(downscaled(), options),
+        HashAlgorithm::Difference => difference_hash::calculate_difference_hash_from_image(downscaled(), options),
+        HashAlgorithm::Perceptual => perceptual_hash::calculate_phash_from_image(downscaled(), options),
+        HashAlgorithm::EdgeHash => edge_hash::calculate_edge_hash_from_image(downscaled(), options),
+        HashAlgorithm::ORB => orb::calculate_orb_features_from_image(downscaled(), options)?,
+        HashAlgorithm::ScaledDuplicate => scaled_duplicate::calculate_scaled_duplicate_hash_from_image(downscaled(), options),
+    };
+    
+    Ok(HashResult { hash, algorithm, color_signature, width, height })
+}
+
 /// 计算两个哈希值之间的相似度 (0-100)
 pub fn calculate_similarity(hash1: &str, hash2: &str, algorithm: HashAlgorithm) -> f32 {
+    // 精确哈希直接比较字符串，对视频同样适用
+    #[cfg(feature = "video")]
+    if algorithm != HashAlgorithm::Exact {
+        if let Some(similarity) = video::compare_if_video(hash1, hash2) {
+            return similarity;
+        }
+    }
+
     match algorithm {
         HashAlgorithm::Exact => {
             // 精确哈希: 相同为100%，不同为0%
@@ -29,7 +86,9 @@ pub fn calculate_similarity(hash1: &str, hash2: &str, algorithm: HashAlgorithm)
         },
         HashAlgorithm::Average |
         HashAlgorithm::Difference |
-        HashAlgorithm::Perceptual => {
+        HashAlgorithm::Perceptual |
+        HashAlgorithm::EdgeHash |
+        HashAlgorithm::ScaledDuplicate => {
             // 感知哈希: 计算汉明距离的相似度
             crate::core::utils::hash_similarity(hash1, hash2)
         },
@@ -39,4 +98,119 @@ pub fn calculate_similarity(hash1: &str, hash2: &str, algorithm: HashAlgorithm)
             // orb_hash::compare_orb_hash(hash1, hash2).unwrap_or(0.0)
         }
     }
-}
\ No newline at end of file
+}
+
+/// 检查哈希字符串是否符合算法的格式
+///
+/// - 精确哈希：64个十六进制字符（SHA-256）；
+/// - 均值、差值、感知、边缘和缩放副本哈希：由"0"/"1"组成，长度等于算法的比特数；
+/// - ORB：完整的Base64特征编码。
+///
+/// 二值哈希之间格式相同，无法据此区分具体算法，这种情况需要比较`HashResult::algorithm`。
+/// 启用`video`功能时，非精确算法也接受视频关键帧哈希。
+pub fn validate_hash(hash: &str, algorithm: HashAlgorithm) -> Result<(), String> {
+    #[cfg(feature = "video")]
+    if algorithm != HashAlgorithm::Exact && video::is_video_hash(hash) {
+        return Ok(());
+    }
+
+    let valid = match algorithm {
+        HashAlgorithm::Exact => {
+            // 64位二值哈希同样是64个十六进制字符，只含0和1时视为二值哈希
+            hash.len() == 64 && hash.bytes().all(|b| b.is_ascii_hexdigit())
+                && !hash.bytes().all(|b| b == b'0' || b == b'1')
+        },
+        HashAlgorithm::Average |
+        HashAlgorithm::Difference |
+        HashAlgorithm::Perceptual |
+        HashAlgorithm::EdgeHash |
+        HashAlgorithm::ScaledDuplicate => {
+            Some(hash.len() as u32) == algorithm.bit_length()
+                && hash.bytes().all(|b| b == b'0' || b == b'1')
+        },
+        HashAlgorithm::ORB => orb::is_valid_orb_features(hash),
+    };
+
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("哈希不是有效的{}格式，可能来自其他算法", algorithm))
+    }
+}
+
+/// 计算两个哈希值之间的相似度，先检查两者都符合算法的格式
+///
+/// 用于外部传入（导入、缓存、用户输入）的哈希；检测流程内部由同一算法计算的哈希
+/// 直接使用`calculate_similarity`。
+pub fn checked_similarity(hash1: &str, hash2: &str, algorithm: HashAlgorithm) -> Result<f32, String> {
+    validate_hash(hash1, algorithm)?;
+    validate_hash(hash2, algorithm)?;
+    Ok(calculate_similarity(hash1, hash2, algorithm))
+}
+
+/// 解释两个二值哈希之间的差异
+///
+/// 只适用于按比特比较的哈希算法（均值、差值、感知、边缘和缩放副本哈希）；
+/// 精确哈希和ORB特征没有逐位的含义，返回错误。
+pub fn explain_similarity(hash1: &str, hash2: &str, algorithm: HashAlgorithm) -> Result<SimilarityExplanation, String> {
+    if !matches!(algorithm, HashAlgorithm::Average | HashAlgorithm::Difference |
+                            HashAlgorithm::Perceptual | HashAlgorithm::EdgeHash |
+                            HashAlgorithm::ScaledDuplicate) {
+        return Err(format!("{}不支持逐位解释", algorithm));
+    }
+    validate_hash(hash1, algorithm)?;
+    validate_hash(hash2, algorithm)?;
+    
+    if hash1.len() != hash2.len() {
+        return Err(format!("哈希长度不一致: {} 与 {}", hash1.len(), hash2.len()));
+    }
+    
+    let differing_bits: Vec<usize> = hash1.chars()
+        .zip(hash2.chars())
+        .enumerate()
+        .filter(|(_, (a, b))| a != b)
+        .map(|(i, _)| i)
+        .collect();
+    
+    // 所有二值哈希都是按行优先展开的8x8网格
+    let differing_cells = differing_bits.iter()
+        .map(|&bit| (bit / 8, bit % 8))
+        .collect();
+    
+    Ok(SimilarityExplanation {
+        algorithm,
+        hash1: hash1.to_string(),
+        hash2: hash2.to_string(),
+        bit_length: hash1.len(),
+        hamming_distance: differing_bits.len(),
+        differing_bits,
+        differing_cells,
+        similarity: calculate_similarity(hash1, hash2, algorithm),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GrayImage, Luma};
+    
+    #[test]
+    fn normalization_cancels_brightness_shift() {
+        // 72x64的随机亮度块：9x8块与差值哈希的缩放尺寸对齐，亮度留出余量使整体提亮时不截断
+        let mut rng = fastrand::Rng::with_seed(5);
+        let levels: Vec<u8> = (0..72).map(|_| rng.u8(50..=150)).collect();
+        let original = GrayImage::from_fn(72, 64, |x, y| Luma([levels[(y / 8 * 9 + x / 8) as usize]]));
+        let brighter = GrayImage::from_fn(72, 64, |x, y| Luma([original.get_pixel(x, y)[0] + 60]));
+        let (original, brighter) = (DynamicImage::ImageLuma8(original), DynamicImage::ImageLuma8(brighter));
+        
+        let options = HashOptions { normalize: true, ..HashOptions::default() };
+        for algorithm in [HashAlgorithm::Average, HashAlgorithm::Difference] {
+            let hash1 = calculate_hash_from_image(&original, algorithm, &options).unwrap().hash;
+            let hash2 = calculate_hash_from_image(&brighter, algorithm, &options).unwrap().hash;
+            // 排除全0或全1这种对任何图像都相同的退化哈希
+            assert!(hash1.contains('0') && hash1.contains('1'), "{}: {}", algorithm, hash1);
+            assert_eq!(hash1, hash2, "{}归一化后亮度不同的副本哈希不一致", algorithm);
+            assert_eq!(calculate_similarity(&hash1, &hash2, algorithm), 100.0);
+        }
+    }
+}
@@ -1,10 +1,11 @@
 use std::path::Path;
 use std::cmp::Ordering;
-use image::{DynamicImage, GenericImageView, GrayImage, Luma};
+use image::{DynamicImage, GrayImage, Luma};
 use base64::{Engine as _, engine::general_purpose};
 use rayon::prelude::*;
-use crate::core::types::HashResult;
-use crate::core::utils::image_utils;
+use serde::{Serialize, Deserialize};
+use crate::core::types::{HashAlgorithm, HashError, HashOptions, HashResult};
+use crate::core::utils::{hash_utils, image_utils};
 use crate::core::utils::math_utils;
 
 /// ORB算法: 定向FAST与旋转BRIEF
@@ -19,36 +20,43 @@ use crate::core::utils::math_utils;
 /// - 对旋转、缩放和亮度变化有良好的鲁棒性
 /// - 计算效率高，适合实时应用
 /// - 可用于寻找物体或场景匹配
-pub fn calculate_orb_features(path: &Path) -> Result<HashResult, String> {
+pub fn calculate_orb_features(path: &Path, options: &HashOptions) -> Result<HashResult, HashError> {
     // 打开图像
-    let img = image_utils::open_image(path)?;
-    let (width, height) = img.dimensions();
+    let (img, width, height) = image_utils::open_image_downscaled(
+        path, options.max_pixels, options.max_decode_dimension)?;
     
-    // 转换为灰度图
-    let gray_img = image_utils::to_grayscale(&img);
+    let hash = calculate_orb_features_from_image(img, options)
+        .map_err(|e| format!("{}: {}", e, path.display()))?;
     
-    // 检测FAST角点，确保返回固定数量的特征点
-    let max_keypoints = 50; // 限制最大特征点数量
-    let mut keypoints = detect_fast_keypoints(&gray_img, 20, max_keypoints)?;
+    Ok(HashResult {
+        hash,
+        algorithm: HashAlgorithm::ORB,
+        color_signature: None,
+        width,
+        height,
+    })
+}
+
+/// 对已解码的图像提取ORB特征，步骤与`calculate_orb_features`相同
+pub fn calculate_orb_features_from_image(img: DynamicImage, options: &HashOptions) -> Result<String, String> {
+    let img = image_utils::preprocess_equalize(img, options.equalize_histogram, options.grayscale);
     
-    // 如果特征点太少，降低阈值重试
-    if keypoints.len() < max_keypoints / 2 {
-        keypoints = detect_fast_keypoints(&gray_img, 15, max_keypoints)?;
-        if keypoints.len() < max_keypoints / 2 {
-            keypoints = detect_fast_keypoints(&gray_img, 10, max_keypoints)?;
-        }
-    }
+    // 转换为灰度图
+    let gray_img = image_utils::to_grayscale_with(&img, options.grayscale);
     
-    // 如果仍然没有足够的特征点，返回错误
-    if keypoints.is_empty() {
-        return Err(format!("在图像中未检测到特征点: {}", path.display()));
-    }
+    // 检测带方向的角点
+    let max_keypoints = options.orb.max_descriptors.max(1); // 限制最大特征点数量
+    let oriented_keypoints = detect_oriented_keypoints(&gray_img, max_keypoints, options.orb.min_corner_score)?;
     
-    // 计算每个角点的方向
-    let oriented_keypoints = compute_keypoint_orientations(&gray_img, &keypoints);
+    // 纯色或非常平坦的图像没有特征点，返回不含描述子的特征集合而不是错误：
+    // 它不会进入任何LSH桶，与任何图像的相似度都是0，因此被视为无法通过ORB匹配的独立图像，
+    // 多张纯色图像也不会因为“同样为空”而被分到一组
+    if oriented_keypoints.is_empty() {
+        return Ok(serialize_features(&[]));
+    }
     
     // 计算BRIEF描述子
-    let descriptors = compute_brief_descriptors(&gray_img, &oriented_keypoints);
+    let descriptors = compute_brief_descriptors(&gray_img, &oriented_keypoints, options.orb.brief_seed);
     
     // 确保描述子数量不超过限制
     let limited_descriptors: Vec<Descriptor> = descriptors.into_iter()
@@ -56,13 +64,68 @@ pub fn calculate_orb_features(path: &Path) -> Result<HashResult, String> {
         .collect();
     
     // 将结果序列化为字符串
-    let features_str = serialize_features(&limited_descriptors);
+    Ok(serialize_features(&limited_descriptors))
+}
+
+/// 检测图像的特征点，用于调试和可视化
+///
+/// 与特征提取使用相同的检测流程，返回的坐标已换算到原始图像尺寸。
+pub fn get_keypoints(path: &Path, options: &HashOptions) -> Result<Vec<KeyPointInfo>, String> {
+    let (img, width, height) = image_utils::open_image_downscaled(
+        path, options.max_pixels, options.max_decode_dimension)?;
+    let img = image_utils::preprocess_equalize(img, options.equalize_histogram, options.grayscale);
+    
+    let gray_img = image_utils::to_grayscale_with(&img, options.grayscale);
+    let keypoints = detect_oriented_keypoints(&gray_img, options.orb.max_descriptors.max(1), options.orb.min_corner_score)?;
+    
+    // 检测在缩小后的图像上进行，换算回原始坐标
+    let scale_x = width as f32 / gray_img.width() as f32;
+    let scale_y = height as f32 / gray_img.height() as f32;
+    
+    Ok(keypoints.into_iter()
+        .map(|kp| KeyPointInfo {
+            x: kp.x as f32 * scale_x,
+            y: kp.y as f32 * scale_y,
+            score: kp.score,
+            angle: kp.angle,
+            octave: kp.octave,
+        })
+        .collect())
+}
+
+/// 检测FAST角点并计算方向
+///
+/// 角点不足`max_keypoints`的一半时依次降低FAST阈值重试；得分低于`min_corner_score`的角点始终被丢弃
+fn detect_oriented_keypoints(gray_img: &GrayImage, max_keypoints: usize, min_corner_score: f32) -> Result<Vec<OrientedKeyPoint>, String> {
+    // 检测FAST角点，确保返回固定数量的特征点
+    let mut keypoints = detect_fast_keypoints(gray_img, 20, max_keypoints, min_corner_score)?;
     
-    Ok(HashResult {
-        hash: features_str,
-        width,
-        height,
-    })
+    // 如果特征点太少，降低阈值重试
+    if keypoints.len() < max_keypoints / 2 {
+        keypoints = detect_fast_keypoints(gray_img, 15, max_keypoints, min_corner_score)?;
+        if keypoints.len() < max_keypoints / 2 {
+            keypoints = detect_fast_keypoints(gray_img, 10, max_keypoints, min_corner_score)?;
+        }
+    }
+    
+    // 计算每个角点的方向
+    Ok(compute_keypoint_orientations(gray_img, &keypoints))
+}
+
+/// 特征点信息（用于调试时在图像上叠加显示）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyPointInfo {
+    /// 原始图像中的x坐标
+    pub x: f32,
+    /// 原始图像中的y坐标
+    pub y: f32,
+    /// FAST角点响应得分
+    pub score: f32,
+    /// 方向（弧度）
+    pub angle: f32,
+    /// 检测到该角点的金字塔层级，0为原始尺寸
+    #[serde(default)]
+    pub octave: u8,
 }
 
 /// FAST角点
@@ -71,6 +134,7 @@ struct KeyPoint {
     x: u32,
     y: u32,
     score: f32,
+    octave: u8, // 金字塔层级
 }
 
 /// 带方向的角点
@@ -80,6 +144,7 @@ struct OrientedKeyPoint {
     y: u32,
     score: f32,
     angle: f32, // 弧度
+    octave: u8,
 }
 
 /// 特征描述子
@@ -88,13 +153,15 @@ struct Descriptor {
     x: u32,
     y: u32,
     angle: f32,
+    octave: u8,
     data: [u8; 32], // 256位描述子
 }
 
 /// 检测FAST角点
 /// 
-/// FAST算法通过比较像素与其周围环形区域的像素值来检测角点
-fn detect_fast_keypoints(img: &GrayImage, threshold: u8, max_points: usize) -> Result<Vec<KeyPoint>, String> {
+/// FAST算法通过比较像素与其周围环形区域的像素值来检测角点，
+/// 响应得分低于`min_score`的角点不参与后续的非极大值抑制
+fn detect_fast_keypoints(img: &GrayImage, threshold: u8, max_points: usize, min_score: f32) -> Result<Vec<KeyPoint>, String> {
     let (width, height) = img.dimensions();
     if width < 12 || height < 12 {
         return Err("图像太小，无法检测特征点".to_string());
@@ -118,6 +185,9 @@ fn detect_fast_keypoints(img: &GrayImage, threshold: u8, max_points: usize) -> R
         for y in radius..level_height - radius {
             for x in radius..level_width - radius {
                 let center_val = current_img.get_pixel(x, y)[0];
+                // 饱和运算：接近0或255的像素不能回绕，否则纯白/纯黑区域会被误判为角点
+                let bright_limit = center_val.saturating_add(threshold);
+                let dark_limit = center_val.saturating_sub(threshold);
                 
                 // 快速连续检查
                 let mut is_corner = false;
@@ -129,15 +199,15 @@ fn detect_fast_keypoints(img: &GrayImage, threshold: u8, max_points: usize) -> R
                 let left = current_img.get_pixel(x - radius, y)[0];
                 
                 // 使用SIMD优化的亮度比较
-                let brighter_count = (top > center_val + threshold) as u8 +
-                                   (right > center_val + threshold) as u8 +
-                                   (bottom > center_val + threshold) as u8 +
-                                   (left > center_val + threshold) as u8;
+                let brighter_count = (top > bright_limit) as u8 +
+                                   (right > bright_limit) as u8 +
+                                   (bottom > bright_limit) as u8 +
+                                   (left > bright_limit) as u8;
                                    
-                let darker_count = (top < center_val - threshold) as u8 +
-                                 (right < center_val - threshold) as u8 +
-                                 (bottom < center_val - threshold) as u8 +
-                                 (left < center_val - threshold) as u8;
+                let darker_count = (top < dark_limit) as u8 +
+                                 (right < dark_limit) as u8 +
+                                 (bottom < dark_limit) as u8 +
+                                 (left < dark_limit) as u8;
                 
                 if brighter_count >= 3 || darker_count >= 3 {
                     // 完整的FAST检测
@@ -151,7 +221,7 @@ fn detect_fast_keypoints(img: &GrayImage, threshold: u8, max_points: usize) -> R
                         let py = (y as i32 + dy) as u32;
                         let point_val = current_img.get_pixel(px, py)[0];
                         
-                        if point_val > center_val + threshold {
+                        if point_val > bright_limit {
                             if !is_brighter {
                                 max_consecutive = max_consecutive.max(consecutive_count);
                                 consecutive_count = 1;
@@ -159,7 +229,7 @@ fn detect_fast_keypoints(img: &GrayImage, threshold: u8, max_points: usize) -> R
                             } else {
                                 consecutive_count += 1;
                             }
-                        } else if point_val < center_val - threshold {
+                        } else if point_val < dark_limit {
                             if is_brighter {
                                 max_consecutive = max_consecutive.max(consecutive_count);
                                 consecutive_count = 1;
@@ -190,12 +260,16 @@ fn detect_fast_keypoints(img: &GrayImage, threshold: u8, max_points: usize) -> R
                             count += 1;
                         }
                         
-                        // 添加考虑尺度的角点
-                        keypoints.push(KeyPoint {
-                            x: (x as f32 * scale) as u32,
-                            y: (y as f32 * scale) as u32,
-                            score: score / count as f32,
-                        });
+                        // 添加考虑尺度的角点，过滤响应太弱的角点
+                        let score = score / count as f32;
+                        if score >= min_score {
+                            keypoints.push(KeyPoint {
+                                x: (x as f32 * scale) as u32,
+                                y: (y as f32 * scale) as u32,
+                                score,
+                                octave: level as u8,
+                            });
+                        }
                     }
                 }
             }
@@ -228,13 +302,57 @@ fn detect_fast_keypoints(img: &GrayImage, threshold: u8, max_points: usize) -> R
         }
     }
     
-    // 非极大值抑制
-    if keypoints.len() > max_points {
-        keypoints.sort_unstable_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
-        keypoints.truncate(max_points);
+    // 基于网格的非极大值抑制，使特征点在图像上分布均匀
+    Ok(grid_non_max_suppression(keypoints, width, height, max_points))
+}
+
+/// 基于网格的非极大值抑制
+///
+/// 全局按得分截断会让特征点全部集中在高对比度区域，大片区域没有特征点，裁剪后难以匹配。
+/// 这里将图像划分为约`max_points`个格子（行列数按宽高比分配），每个格子内按得分排序，
+/// 然后依次取各格子的第1名、第2名……直到取满`max_points`个角点。
+fn grid_non_max_suppression(
+    keypoints: Vec<KeyPoint>,
+    width: u32,
+    height: u32,
+    max_points: usize
+) -> Vec<KeyPoint> {
+    if keypoints.len() <= max_points {
+        return keypoints;
     }
     
-    Ok(keypoints)
+    // 根据图像宽高比确定网格的行列数
+    let aspect = width as f32 / height.max(1) as f32;
+    let cols = ((max_points as f32 * aspect).sqrt().round() as usize).max(1);
+    let rows = max_points.div_ceil(cols).max(1);
+    let cell_width = (width as usize).div_ceil(cols).max(1);
+    let cell_height = (height as usize).div_ceil(rows).max(1);
+    
+    // 将角点分配到所在的格子
+    let mut cells: Vec<Vec<KeyPoint>> = vec![Vec::new(); cols * rows];
+    for kp in keypoints {
+        let col = (kp.x as usize / cell_width).min(cols - 1);
+        let row = (kp.y as usize / cell_height).min(rows - 1);
+        cells[row * cols + col].push(kp);
+    }
+    
+    // 记录每个角点在其格子内的排名
+    let mut ranked: Vec<(usize, KeyPoint)> = Vec::new();
+    for mut cell in cells {
+        cell.sort_unstable_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+        ranked.extend(cell.into_iter().enumerate());
+    }
+    
+    // 排名靠前的优先，同一排名内按得分从高到低
+    ranked.sort_unstable_by(|a, b| {
+        a.0.cmp(&b.0)
+            .then_with(|| b.1.score.partial_cmp(&a.1.score).unwrap_or(Ordering::Equal))
+    });
+    
+    ranked.into_iter()
+        .take(max_points)
+        .map(|(_, kp)| kp)
+        .collect()
 }
 
 /// 获取Bresenham圆的偏移模式（相对于中心点的偏移）
@@ -322,6 +440,7 @@ fn compute_keypoint_orientations(img: &GrayImage, keypoints: &[KeyPoint]) -> Vec
             y,
             score,
             angle,
+            octave: kp.octave,
         });
     }
     
@@ -332,9 +451,9 @@ fn compute_keypoint_orientations(img: &GrayImage, keypoints: &[KeyPoint]) -> Vec
 const BRIEF_PATTERN_SIZE: usize = 256;
 
 /// 计算BRIEF描述子
-fn compute_brief_descriptors(img: &GrayImage, keypoints: &[OrientedKeyPoint]) -> Vec<Descriptor> {
+fn compute_brief_descriptors(img: &GrayImage, keypoints: &[OrientedKeyPoint], seed: u64) -> Vec<Descriptor> {
     let (width, height) = img.dimensions();
-    let pattern = generate_brief_pattern();
+    let pattern = generate_brief_pattern(seed);
     let mut descriptors = Vec::with_capacity(keypoints.len());
     
     // 图像边界
@@ -346,6 +465,7 @@ fn compute_brief_descriptors(img: &GrayImage, keypoints: &[OrientedKeyPoint]) ->
             x: kp.x,
             y: kp.y,
             angle: kp.angle,
+            octave: kp.octave,
             data: [0u8; 32], // 256位 = 32字节
         };
         
@@ -394,14 +514,15 @@ fn compute_brief_descriptors(img: &GrayImage, keypoints: &[OrientedKeyPoint]) ->
 
 /// 生成BRIEF采样模式
 /// 
-/// 返回描述子的采样点对列表，每个点对由两个坐标组成
-fn generate_brief_pattern() -> Vec<(f32, f32, f32, f32)> {
+/// 返回描述子的采样点对列表，每个点对由两个坐标组成。
+/// 相同的种子总是生成相同的模式，不同种子生成的描述子之间不可比较。
+fn generate_brief_pattern(seed: u64) -> Vec<(f32, f32, f32, f32)> {
     let mut pattern = Vec::with_capacity(BRIEF_PATTERN_SIZE);
     
     // 使用预定义的采样模式
     // 基于高斯分布的采样点，而不是均匀分布，提高特征的区分能力
     let s = 15.0; // 采样区域大小
-    let mut rng = fastrand::Rng::with_seed(42); // 使用固定种子保证可重复性
+    let mut rng = fastrand::Rng::with_seed(seed); // 使用固定种子保证可重复性
     
     // 生成高斯分布的采样点对
     for _ in 0..BRIEF_PATTERN_SIZE {
@@ -424,10 +545,35 @@ fn generate_brief_pattern() -> Vec<(f32, f32, f32, f32)> {
     pattern
 }
 
+/// ORB特征序列化格式的版本
+///
+/// 版本1没有文件头，直接以描述子数量开始；版本2加入文件头并为每个描述子记录金字塔层级。
+/// 修改序列化格式时递增该值，旧版本的特征会被识别为无效，需要重新计算。
+pub const ORB_FORMAT_VERSION: u8 = 2;
+
+/// 序列化数据的文件头：3字节标识加1字节版本号
+const ORB_FORMAT_HEADER: [u8; 4] = [b'O', b'R', b'B', ORB_FORMAT_VERSION];
+
+/// 每个描述子记录的字节数：x、y、角度、金字塔层级和256位描述子
+const DESCRIPTOR_RECORD_SIZE: usize = 4 + 4 + 4 + 1 + 32;
+
+/// 版本1（没有文件头）中每个描述子记录的字节数：x、y、角度和256位描述子
+const LEGACY_DESCRIPTOR_RECORD_SIZE: usize = 4 + 4 + 4 + 32;
+
+/// 文件头和描述子数量字段的总长度
+const FEATURES_PREFIX_SIZE: usize = ORB_FORMAT_HEADER.len() + 4;
+
+/// 金字塔层级每相差一级，匹配时在汉明距离上增加的惩罚
+///
+/// 同一特征在两张尺寸相近的图像中通常在同一层级被检测到，
+/// 惩罚使相同尺度的候选优先，但不完全排除跨层级的匹配。
+const OCTAVE_DISTANCE_PENALTY: u32 = 8;
+
 /// 序列化特征点和描述子
 fn serialize_features(descriptors: &[Descriptor]) -> String {
     // 将特征点信息转换为二进制数据
-    let mut data = Vec::new();
+    let mut data = Vec::with_capacity(FEATURES_PREFIX_SIZE + descriptors.len() * DESCRIPTOR_RECORD_SIZE);
+    data.extend_from_slice(&ORB_FORMAT_HEADER);
     
     // 存储描述子数量（固定为实际数量）
     let count = descriptors.len();
@@ -435,10 +581,11 @@ fn serialize_features(descriptors: &[Descriptor]) -> String {
     
     // 存储每个描述子
     for desc in descriptors {
-        // 存储位置和角度
+        // 存储位置、角度和金字塔层级
         data.extend_from_slice(&desc.x.to_le_bytes());
         data.extend_from_slice(&desc.y.to_le_bytes());
         data.extend_from_slice(&desc.angle.to_le_bytes());
+        data.push(desc.octave);
         
         // 存储描述子数据
         data.extend_from_slice(&desc.data);
@@ -477,27 +624,111 @@ pub fn calculate_orb_similarity(features1: &str, features2: &str) -> Result<f32,
     Ok(similarity)
 }
 
+/// 判断字符串是否为完整的ORB特征编码
+///
+/// 要求能按Base64解码、版本与当前格式一致，且长度恰好等于描述子数量对应的长度，
+/// 用于在比较前识别误传入的其他算法的哈希。
+pub fn is_valid_orb_features(features: &str) -> bool {
+    let Ok(data) = general_purpose::STANDARD.decode(features) else {
+        return false;
+    };
+    
+    deserialize_features(&data)
+        .map(|descriptors| data.len() == FEATURES_PREFIX_SIZE + descriptors.len() * DESCRIPTOR_RECORD_SIZE)
+        .unwrap_or(false)
+}
+
+/// 计算ORB特征的SimHash签名，用于LSH分桶
+///
+/// 特征字符串无法解码或没有描述子时返回None
+pub fn orb_signature(features: &str) -> Option<u64> {
+    let data = general_purpose::STANDARD.decode(features).ok()?;
+    let descriptors = deserialize_features(&data).ok()?;
+    if descriptors.is_empty() {
+        return None;
+    }
+    
+    let descriptor_bits: Vec<&[u8; 32]> = descriptors.iter().map(|desc| &desc.data).collect();
+    Some(hash_utils::simhash(&descriptor_bits))
+}
+
+/// 计算ORB特征的MinHash签名
+///
+/// 把每个256位描述子切分为4个64位的片段（附带片段序号）作为集合元素，
+/// 部分比特相同的描述子也能贡献相同的片段。特征字符串无法解码时返回None。
+pub fn orb_minhash(features: &str, num_permutations: usize) -> Option<Vec<u64>> {
+    let data = general_purpose::STANDARD.decode(features).ok()?;
+    let descriptors = deserialize_features(&data).ok()?;
+    
+    let shingles: Vec<[u8; 9]> = descriptors.iter()
+        .flat_map(|desc| {
+            desc.data.chunks(8).enumerate().map(|(chunk_idx, chunk)| {
+                let mut shingle = [0u8; 9];
+                shingle[0] = chunk_idx as u8;
+                shingle[1..].copy_from_slice(chunk);
+                shingle
+            })
+        })
+        .collect();
+    
+    Some(hash_utils::minhash(&shingles, num_permutations))
+}
+
+/// 识别序列化数据的格式版本
+///
+/// 带文件头的数据返回头中的版本号；没有文件头、但长度与版本1的布局一致的数据视为版本1；
+/// 都不符合时返回None。
+fn features_version(data: &[u8]) -> Option<u8> {
+    if data.len() >= FEATURES_PREFIX_SIZE && data[..3] == ORB_FORMAT_HEADER[..3] {
+        return Some(data[3]);
+    }
+    
+    let count = u32::from_le_bytes(data.get(..4)?.try_into().ok()?) as usize;
+    let legacy_len = count.checked_mul(LEGACY_DESCRIPTOR_RECORD_SIZE)?.checked_add(4)?;
+    (legacy_len == data.len()).then_some(1)
+}
+
+/// 返回ORB特征字符串的格式版本，无法识别时返回None
+///
+/// 用于在比较之前发现存储或导出的旧特征，版本不等于`ORB_FORMAT_VERSION`的特征需要重新计算。
+pub fn orb_features_version(features: &str) -> Option<u8> {
+    let data = general_purpose::STANDARD.decode(features).ok()?;
+    features_version(&data)
+}
+
 /// 反序列化特征
+///
+/// 格式版本与`ORB_FORMAT_VERSION`不一致（包括没有文件头的版本1数据）时返回不兼容版本的错误，
+/// 调用方应重新计算特征。
 fn deserialize_features(data: &[u8]) -> Result<Vec<Descriptor>, String> {
-    if data.len() < 4 {
+    if data.len() < FEATURES_PREFIX_SIZE {
         return Err("特征数据格式无效".to_string());
     }
     
+    match features_version(data) {
+        Some(ORB_FORMAT_VERSION) => {},
+        Some(version) => return Err(format!(
+            "不兼容的特征格式版本 {}（当前版本 {}），需要重新计算特征", version, ORB_FORMAT_VERSION)),
+        None => return Err("特征数据格式无效".to_string()),
+    }
+    
     // 读取描述子数量
     let mut count_bytes = [0u8; 4];
-    count_bytes.copy_from_slice(&data[0..4]);
+    count_bytes.copy_from_slice(&data[ORB_FORMAT_HEADER.len()..FEATURES_PREFIX_SIZE]);
     let count = u32::from_le_bytes(count_bytes) as usize;
     
-    let mut descriptors = Vec::with_capacity(count);
-    let descriptor_size = 4 + 4 + 4 + 32; // x, y, angle, data
-    
-    // 确保数据长度足够
-    if 4 + count * descriptor_size > data.len() {
+    // 确保数据长度足够（数量字段可能被篡改，先校验再分配内存）
+    let required = count.checked_mul(DESCRIPTOR_RECORD_SIZE)
+        .and_then(|len| len.checked_add(FEATURES_PREFIX_SIZE))
+        .ok_or_else(|| "特征数据格式无效".to_string())?;
+    if required > data.len() {
         return Err("特征数据截断".to_string());
     }
     
+    let mut descriptors = Vec::with_capacity(count);
+    
     for i in 0..count {
-        let offset = 4 + i * descriptor_size;
+        let offset = FEATURES_PREFIX_SIZE + i * DESCRIPTOR_RECORD_SIZE;
         
         // 读取位置和角度
         let mut x_bytes = [0u8; 4];
@@ -511,15 +742,17 @@ fn deserialize_features(data: &[u8]) -> Result<Vec<Descriptor>, String> {
         let x = u32::from_le_bytes(x_bytes);
         let y = u32::from_le_bytes(y_bytes);
         let angle = f32::from_le_bytes(angle_bytes);
+        let octave = data[offset+12];
         
         // 读取描述子数据
         let mut desc_data = [0u8; 32];
-        desc_data.copy_from_slice(&data[offset+12..offset+44]);
+        desc_data.copy_from_slice(&data[offset+13..offset+45]);
         
         descriptors.push(Descriptor {
             x,
             y,
             angle,
+            octave,
             data: desc_data,
         });
     }
@@ -527,51 +760,25 @@ fn deserialize_features(data: &[u8]) -> Result<Vec<Descriptor>, String> {
     Ok(descriptors)
 }
 
+/// 描述子数量的乘积超过该值时并行进行最近邻搜索
+///
+/// 检测流程本身已在候选对之间并行，描述子较少时再拆分任务的开销大于收益
+const PARALLEL_MATCH_MIN_WORK: usize = 4096;
+
 /// 匹配两组描述子
+///
+/// 对descriptors1中的每个描述子在descriptors2中查找最近和次近邻，
+/// 不再构建完整的距离矩阵；描述子较多时按descriptors1并行搜索。
 fn match_descriptors(descriptors1: &[Descriptor], descriptors2: &[Descriptor]) -> Vec<(usize, usize)> {
-    let mut matches = Vec::new();
-    let ratio_threshold = 0.8;
-    let max_distance = 80;
-    
-    // 使用并行计算优化距离矩阵
-    let distance_matrix: Vec<Vec<u32>> = descriptors1.iter()
-        .map(|desc1| {
-            descriptors2.iter()
-                .map(|desc2| compute_hamming_distance(&desc1.data, &desc2.data))
-                .collect()
-        })
-        .collect();
+    let find_match = |(i, desc1): (usize, &Descriptor)| {
+        match_descriptor(desc1, descriptors2).map(|best_idx| (i, best_idx))
+    };
     
-    // 优化的最近邻搜索
-    for (i, distances) in distance_matrix.iter().enumerate() {
-        let mut best_distance = u32::MAX;
-        let mut second_best = u32::MAX;
-        let mut best_idx = 0;
-        
-        // 使用SIMD优化的距离比较
-        for (j, &distance) in distances.iter().enumerate() {
-            if distance < best_distance {
-                second_best = best_distance;
-                best_distance = distance;
-                best_idx = j;
-            } else if distance < second_best {
-                second_best = distance;
-            }
-        }
-        
-        // 改进的Lowe's比率测试
-        if best_distance < max_distance {
-            let ratio = if second_best == u32::MAX {
-                0.0
-            } else {
-                best_distance as f32 / second_best as f32
-            };
-            
-            if ratio < ratio_threshold {
-                matches.push((i, best_idx));
-            }
-        }
-    }
+    let mut matches: Vec<(usize, usize)> = if descriptors1.len() * descriptors2.len() >= PARALLEL_MATCH_MIN_WORK {
+        descriptors1.par_iter().enumerate().filter_map(find_match).collect()
+    } else {
+        descriptors1.iter().enumerate().filter_map(find_match).collect()
+    };
     
     // 改进的几何验证
     if matches.len() > 10 {
@@ -581,6 +788,45 @@ fn match_descriptors(descriptors1: &[Descriptor], descriptors2: &[Descriptor]) -
     matches
 }
 
+/// 在候选描述子中查找与给定描述子的最佳匹配
+///
+/// 距离为汉明距离加上金字塔层级差对应的惩罚（见`OCTAVE_DISTANCE_PENALTY`），
+/// 通过Lowe比率测试（最近距离明显小于次近距离）时返回最近邻的索引
+fn match_descriptor(descriptor: &Descriptor, candidates: &[Descriptor]) -> Option<usize> {
+    let ratio_threshold = 0.8;
+    let max_distance = 80;
+    
+    let mut best_distance = u32::MAX;
+    let mut second_best = u32::MAX;
+    let mut best_idx = 0;
+    
+    for (j, candidate) in candidates.iter().enumerate() {
+        let octave_gap = descriptor.octave.abs_diff(candidate.octave) as u32;
+        let distance = compute_hamming_distance(&descriptor.data, &candidate.data)
+            + octave_gap * OCTAVE_DISTANCE_PENALTY;
+        if distance < best_distance {
+            second_best = best_distance;
+            best_distance = distance;
+            best_idx = j;
+        } else if distance < second_best {
+            second_best = distance;
+        }
+    }
+    
+    // 改进的Lowe's比率测试
+    if best_distance >= max_distance {
+        return None;
+    }
+    
+    let ratio = if second_best == u32::MAX {
+        0.0
+    } else {
+        best_distance as f32 / second_best as f32
+    };
+    
+    (ratio < ratio_threshold).then_some(best_idx)
+}
+
 /// 使用距离一致性过滤匹配点对，移除离群点
 fn filter_matches_by_distance_consistency(
     matches: &[(usize, usize)],
@@ -654,4 +900,46 @@ fn compute_hamming_distance(a: &[u8; 32], b: &[u8; 32]) -> u32 {
                .sum::<u32>()
      })
      .sum()
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::types::ImageInfo;
+    use crate::detection::duplicate::{find_duplicate_groups, DuplicateDetectionParams};
+    use image::{Rgb, RgbImage};
+    
+    #[test]
+    fn solid_colour_images_never_match() {
+        let options = HashOptions::default();
+        let features: Vec<String> = [[255, 255, 255], [0, 0, 0]].iter()
+            .map(|&colour| {
+                let img = DynamicImage::ImageRgb8(RgbImage::from_pixel(64, 64, Rgb(colour)));
+                calculate_orb_features_from_image(img, &options).unwrap()
+            })
+            .collect();
+        
+        // 没有特征点时仍是有效的特征编码，但与任何特征（包括同样为空的）相似度都是0
+        assert!(features.iter().all(|f| is_valid_orb_features(f)));
+        assert_eq!(calculate_orb_similarity(&features[0], &features[1]).unwrap(), 0.0);
+        assert_eq!(calculate_orb_similarity(&features[0], &features[0]).unwrap(), 0.0);
+        
+        let images: Vec<ImageInfo> = ["/white.png", "/black.png"].iter()
+            .map(|path| ImageInfo {
+                path: path.to_string(),
+                hash: String::new(),
+                algorithm: HashAlgorithm::ORB,
+                width: 64,
+                height: 64,
+                size_bytes: 100,
+                created_at: String::new(),
+                modified_at: String::new(),
+                source_root: String::new(),
+                thumbnail: None,
+            })
+            .collect();
+        // 阈值为0时任何被比较的对都会合并，空特征仍不能进入同一组
+        let params = DuplicateDetectionParams::new(Vec::new(), HashAlgorithm::ORB, 0.0, false);
+        assert!(find_duplicate_groups(&features, &images, &params).unwrap().is_empty());
+    }
+}
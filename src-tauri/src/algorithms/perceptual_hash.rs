@@ -1,30 +1,48 @@
-use crate::core::types::HashResult;
+use crate::core::types::{HashAlgorithm, HashError, HashOptions, HashResult};
 use crate::core::utils::image_utils;
 use crate::core::utils::math_utils;
-use image::GenericImageView;
+use image::DynamicImage;
 use std::path::Path;
 
 /// 计算图片的感知哈希 (Perceptual Hash / pHash)
 ///
 /// 感知哈希算法步骤:
+/// 0. （可选）高斯模糊，抑制JPEG压缩噪声
 /// 1. 将图像缩放为32x32大小
 /// 2. 将图像转换为灰度图
 /// 3. 对图像进行离散余弦变换(DCT)
-/// 4. 取DCT的低频区域(通常是左上角的8x8)
-/// 5. 计算这个区域的均值
-/// 6. 根据每个DCT系数与均值的比较生成64位哈希
+/// 4. 取DCT的低频区域(左上角的8x8，包含直流分量)
+/// 5. 计算这个区域的中位数
+/// 6. 根据每个DCT系数与中位数的比较生成64位哈希，位布局见`dct_low_freq_bits`
 ///
 /// 感知哈希对于图像的内容变化非常敏感，同时对于缩放、旋转、压缩等操作有较好的鲁棒性。
-pub fn calculate_perceptual_hash(path: &Path) -> Result<HashResult, String> {
+pub fn calculate_perceptual_hash(path: &Path, options: &HashOptions) -> Result<HashResult, HashError> {
     // 打开图像
-    let img = image_utils::open_image(path)?;
-    let (width, height) = img.dimensions();
+    let (img, width, height) = image_utils::open_image_downscaled(
+        path, options.max_pixels, options.max_decode_dimension)?;
+    let color_signature = options.color_signature.then(|| image_utils::color_signature(&img));
+
+    Ok(HashResult {
+        hash: calculate_phash_from_image(img, options),
+        algorithm: HashAlgorithm::Perceptual,
+        color_signature,
+        width,
+        height,
+    })
+}
+
+/// 对已解码的图像计算感知哈希，步骤与`calculate_perceptual_hash`相同
+///
+/// 供没有对应图像文件的场景使用，例如从视频中抽取的关键帧。
+pub fn calculate_phash_from_image(img: DynamicImage, options: &HashOptions) -> String {
+    let img = image_utils::preprocess_equalize(img, options.equalize_histogram, options.grayscale);
+    let img = image_utils::preprocess_blur(img, options.preprocess_blur, options.grayscale);
 
     // 缩放图像为32x32
-    let small_img = image_utils::resize_image(&img, 32, 32);
+    let small_img = image_utils::resize_image_with(&img, 32, 32, options.resize_filter.filter_type());
 
     // 转换为灰度图
-    let gray_img = image_utils::to_grayscale(&small_img);
+    let gray_img = image_utils::to_grayscale_with(&small_img, options.grayscale);
 
     // 转换为浮点数矩阵
     let matrix = image_utils::gray_image_to_matrix(&gray_img);
@@ -32,29 +50,7 @@ pub fn calculate_perceptual_hash(path: &Path) -> Result<HashResult, String> {
     // 应用DCT变换
     let dct_matrix = math_utils::dct_2d(&matrix);
 
-    // 提取左上角8x8的低频区域
-    let mut low_freq = Vec::with_capacity(64);
-    for y in 0..8 {
-        for x in 0..8 {
-            low_freq.push(dct_matrix[y][x]);
-        }
-    }
-
-    // 计算这个区域的中位数
-    let mut low_freq_copy = low_freq.clone();
-    let median = math_utils::median(&mut low_freq_copy);
-
-    // 根据每个值与中位数的比较生成哈希值
-    let mut hash = String::with_capacity(64);
-    for &val in &low_freq {
-        hash.push(if val > median { '1' } else { '0' });
-    }
-
-    Ok(HashResult {
-        hash,
-        width,
-        height,
-    })
+    dct_low_freq_bits(&dct_matrix)
 }
 
 /// 计算两个感知哈希的相似度
@@ -72,42 +68,97 @@ pub fn compare_perceptual_hash(hash1: &str, hash2: &str) -> f32 {
     100.0 * (1.0 - (distance as f32 / max_distance as f32))
 }
 
-/// 计算感知哈希并使用加权策略
-/// 这是一个增强版的pHash，对低频区域的不同位置使用不同权重
-pub fn calculate_weighted_phash(path: &Path) -> Result<HashResult, String> {
-    // 打开并处理图像，获取DCT系数
-    let img = image_utils::open_image(path)?;
-    let (width, height) = img.dimensions();
-
-    // 缩放图像为32x32
-    let small_img = image_utils::resize_image(&img, 32, 32);
-    let gray_img = image_utils::to_grayscale(&small_img);
-    let matrix = image_utils::gray_image_to_matrix(&gray_img);
-    let dct_matrix = math_utils::dct_2d(&matrix);
+/// 计算感知哈希的旧入口，结果与`calculate_perceptual_hash`完全相同
+///
+/// 这里从未真正应用过权重；保留该函数只是为了兼容，两者共用`dct_low_freq_bits`的位布局，
+/// 生成的哈希可以直接混合比较、放入同一个LSH索引。
+pub fn calculate_weighted_phash(path: &Path, options: &HashOptions) -> Result<HashResult, HashError> {
+    calculate_perceptual_hash(path, options)
+}
 
-    // 对左上角8x8区域应用权重
-    // 左上角(更低频)的权重更高
-    let mut weighted_hash = String::with_capacity(64);
-    let mut values = Vec::with_capacity(64);
+/// 从DCT系数矩阵的左上角8x8低频区域生成64位感知哈希
+///
+/// 位布局：第`i`位对应系数`dct[i / 8][i % 8]`（按行展开），第0位是直流分量`[0][0]`。
+/// 直流分量参与中位数计算并占据一位，与Python `imagehash.phash`一致，
+/// `hash_utils::from_imagehash_hex`转换的哈希因此可以直接比较。系数严格大于64个系数的中位数时为1。
+fn dct_low_freq_bits(dct_matrix: &[Vec<f64>]) -> String {
+    let low_freq: Vec<f64> = dct_matrix.iter()
+        .take(8)
+        .flat_map(|row| row.iter().take(8).copied())
+        .collect();
+
+    let median = math_utils::median_of(&low_freq);
+
+    low_freq.iter()
+        .map(|&val| if val > median { '1' } else { '0' })
+        .collect()
+}
 
-    for y in 0..8 {
-        for x in 0..8 {
-            values.push(dct_matrix[y][x]);
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::utils::hash_utils::{from_imagehash_hex, to_imagehash_hex};
+    
+    #[test]
+    fn imagehash_all_ones_round_trips() {
+        let bits = from_imagehash_hex("ffffffffffffffff", HashAlgorithm::Perceptual).unwrap();
+        assert_eq!(bits, "1".repeat(64));
+        assert_eq!(to_imagehash_hex(&bits, HashAlgorithm::Perceptual).unwrap(), "ffffffffffffffff");
+        assert_eq!(compare_perceptual_hash(&bits, &"1".repeat(64)), 100.0);
     }
-
-    // 获取中值用于二值化
-    let mut values_copy = values.clone();
-    let median = math_utils::median(&mut values_copy);
-
-    // 生成哈希值
-    for val in values {
-        weighted_hash.push(if val > median { '1' } else { '0' });
+    
+    #[test]
+    fn imagehash_nibble_pattern_matches_row_major_layout() {
+        // 每行8位中高4位为0、低4位为1：第i位对应系数dct[i / 8][i % 8]
+        let expected = "00001111".repeat(8);
+        assert_eq!(from_imagehash_hex("0f0f0f0f0f0f0f0f", HashAlgorithm::Perceptual).unwrap(), expected);
+        assert_eq!(to_imagehash_hex(&expected, HashAlgorithm::Perceptual).unwrap(), "0f0f0f0f0f0f0f0f");
+        // 差值哈希的比较方向相反，逐位取反
+        assert_eq!(from_imagehash_hex("0f0f0f0f0f0f0f0f", HashAlgorithm::Difference).unwrap(), "11110000".repeat(8));
+        
+        // 每行右半部分的系数高于中位数时，生成的哈希与imagehash的0f0f0f0f0f0f0f0f一致
+        let dct: Vec<Vec<f64>> = (0..32)
+            .map(|row| (0..32).map(|col| if row < 8 && col >= 4 { 100.0 + col as f64 } else { -(col as f64) }).collect())
+            .collect();
+        let bits = dct_low_freq_bits(&dct);
+        assert_eq!(bits, expected);
+        assert_eq!(to_imagehash_hex(&bits, HashAlgorithm::Perceptual).unwrap(), "0f0f0f0f0f0f0f0f");
+    }
+    
+    #[test]
+    fn bit_layout_is_row_major_with_dc_first() {
+        // 只有一个系数高于其余63个时，哈希中只有该系数对应的一位为1
+        for (row, col) in [(0, 0), (0, 1), (1, 0), (3, 5), (7, 7)] {
+            let mut dct = vec![vec![0.0; 32]; 32];
+            dct[row][col] = 10.0;
+            // 8x8区域以外的系数不参与哈希
+            dct[8][0] = 99.0;
+            dct[0][8] = 99.0;
+            let bits = dct_low_freq_bits(&dct);
+            let expected_bit = row * 8 + col;
+            assert_eq!(bits.len(), 64);
+            assert_eq!(bits.find('1'), Some(expected_bit), "系数[{}][{}]", row, col);
+            assert_eq!(bits.matches('1').count(), 1);
+            
+            // explain_similarity按相同的布局把比特映射回网格位置
+            let explanation = crate::algorithms::explain_similarity(&bits, &"0".repeat(64), HashAlgorithm::Perceptual).unwrap();
+            assert_eq!(explanation.differing_bits, vec![expected_bit]);
+            assert_eq!(explanation.differing_cells, vec![(row, col)]);
+        }
+        
+        // 水平渐变：直流分量最大，第0位为1；递增渐变的[0][1]系数为负，第1位为0
+        let gradient = DynamicImage::ImageLuma8(image::GrayImage::from_fn(64, 64, |x, _| image::Luma([(x * 4) as u8])));
+        let options = HashOptions::default();
+        let hash = calculate_phash_from_image(gradient.clone(), &options);
+        assert_eq!(&hash[..2], "10");
+        
+        // 按路径计算的两个入口与按图像计算的结果一致
+        let path = std::env::temp_dir().join(format!("delo-phash-layout-{}.png", std::process::id()));
+        gradient.save(&path).unwrap();
+        let from_path = calculate_perceptual_hash(&path, &options);
+        let weighted = calculate_weighted_phash(&path, &options);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(from_path.unwrap().hash, hash);
+        assert_eq!(weighted.unwrap().hash, hash);
     }
-
-    Ok(HashResult {
-        hash: weighted_hash,
-        width,
-        height,
-    })
 }
@@ -0,0 +1,41 @@
+use std::path::Path;
+use image::DynamicImage;
+use crate::core::types::{HashAlgorithm, HashError, HashOptions, HashResult};
+use crate::core::utils::image_utils;
+use super::perceptual_hash;
+
+/// 缩放副本检测时先把图像缩放到的规范尺寸（正方形边长）
+///
+/// 不同分辨率的同一张图都先用Lanczos滤波缩放到这个尺寸，再计算感知哈希，
+/// 使4000px的原图和800px的缩略图经过完全相同的处理，哈希差异只来自重采样误差。
+pub const SCALED_CANONICAL_SIZE: u32 = 128;
+
+/// 计算用于查找缩放副本的哈希
+///
+/// 缩放副本哈希算法步骤:
+/// 1. 使用Lanczos滤波将图像缩放为规范尺寸`SCALED_CANONICAL_SIZE`（不受`resize_filter`选项影响）
+/// 2. 对规范尺寸的图像计算感知哈希（不做预模糊，模糊半径与原始分辨率有关）
+///
+/// 哈希格式与感知哈希相同，区别在于检测时只接受宽高比一致的图像对，并使用更严格的默认阈值。
+pub fn calculate_scaled_duplicate_hash(path: &Path, options: &HashOptions) -> Result<HashResult, HashError> {
+    // 打开图像
+    let (img, width, height) = image_utils::open_image_downscaled(
+        path, options.max_pixels, options.max_decode_dimension)?;
+    let color_signature = options.color_signature.then(|| image_utils::color_signature(&img));
+    
+    Ok(HashResult {
+        hash: calculate_scaled_duplicate_hash_from_image(img, options),
+        algorithm: HashAlgorithm::ScaledDuplicate,
+        color_signature,
+        width,
+        height,
+    })
+}
+
+/// 对已解码的图像计算缩放副本哈希，步骤与`calculate_scaled_duplicate_hash`相同
+pub fn calculate_scaled_duplicate_hash_from_image(img: DynamicImage, options: &HashOptions) -> String {
+    let canonical = image_utils::resize_image(&img, SCALED_CANONICAL_SIZE, SCALED_CANONICAL_SIZE);
+    let options = HashOptions { preprocess_blur: None, ..options.clone() };
+    
+    perceptual_hash::calculate_phash_from_image(canonical, &options)
+}
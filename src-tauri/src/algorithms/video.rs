@@ -0,0 +1,146 @@
+use crate::core::types::{HashAlgorithm, HashError, HashOptions, HashResult};
+use crate::core::utils::hash_similarity;
+use crate::core::utils::hash_utils::compute_file_sha256;
+use crate::algorithms::perceptual_hash;
+use image::GenericImageView;
+use std::path::Path;
+use std::process::Command;
+
+/// 每个视频抽取的关键帧数量
+pub const VIDEO_KEYFRAME_COUNT: usize = 8;
+
+/// 视频哈希的前缀，用于在比较时区分视频和图像的哈希
+pub const VIDEO_HASH_PREFIX: &str = "video:";
+
+/// 对齐时两个帧序列至少需要重叠的比例，避免只靠首尾一两帧就判为相似
+const MIN_OVERLAP_RATIO: f32 = 0.5;
+
+/// 读取视频时长（秒）
+fn probe_duration(path: &Path) -> Result<f64, String> {
+    let output = Command::new("ffprobe")
+        .args(["-v", "error", "-show_entries", "format=duration",
+               "-of", "default=noprint_wrappers=1:nokey=1"])
+        .arg(path)
+        .output()
+        .map_err(|e| format!("无法运行ffprobe: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!("读取视频时长失败: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+
+    String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse::<f64>()
+        .map_err(|e| format!("无效的视频时长: {}", e))
+}
+
+/// 解码视频在指定时间点的一帧
+fn extract_frame(path: &Path, seconds: f64) -> Result<image::DynamicImage, String> {
+    let output = Command::new("ffmpeg")
+        .args(["-v", "error", "-ss", &format!("{:.3}", seconds), "-i"])
+        .arg(path)
+        .args(["-frames:v", "1", "-f", "image2pipe", "-vcodec", "png", "pipe:1"])
+        .output()
+        .map_err(|e| format!("无法运行ffmpeg: {}", e))?;
+
+    if !output.status.success() || output.stdout.is_empty() {
+        return Err(format!("抽取视频帧失败: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+
+    image::load_from_memory(&output.stdout)
+        .map_err(|e| format!("无法解码视频帧: {}", e))
+}
+
+/// 从视频中均匀抽取`count`个关键帧
+///
+/// 第k帧取在时长的`(k + 0.5) / count`处，避开片头片尾常见的黑场。
+pub fn extract_keyframes(path: &Path, count: usize) -> Result<Vec<image::DynamicImage>, String> {
+    let duration = probe_duration(path)?;
+    if duration <= 0.0 {
+        return Err(format!("视频时长为0: {}", path.display()));
+    }
+
+    (0..count)
+        .map(|k| extract_frame(path, duration * (k as f64 + 0.5) / count as f64))
+        .collect()
+}
+
+/// 计算视频的关键帧哈希
+///
+/// 对每个关键帧计算感知哈希，按时间顺序用逗号连接并加上`video:`前缀。
+/// 返回的宽高取自第一帧。
+pub fn calculate_video_hash(path: &Path, options: &HashOptions) -> Result<HashResult, HashError> {
+    let frames = extract_keyframes(path, VIDEO_KEYFRAME_COUNT)?;
+    let (width, height) = frames.first()
+        .map(|frame| frame.dimensions())
+        .ok_or_else(|| format!("未能抽取任何视频帧: {}", path.display()))?;
+
+    let frame_hashes: Vec<String> = frames.into_iter()
+        .map(|frame| perceptual_hash::calculate_phash_from_image(frame, options))
+        .collect();
+
+    Ok(HashResult {
+        hash: format!("{}{}", VIDEO_HASH_PREFIX, frame_hashes.join(",")),
+        algorithm: HashAlgorithm::Perceptual,
+        color_signature: None,
+        width,
+        height,
+    })
+}
+
+/// 计算视频的精确哈希，即文件内容的SHA-256
+///
+/// 不解码视频，宽高记为0（未知）。得到的哈希与图像的精确哈希格式相同，可以通过`validate_hash`。
+pub fn calculate_video_exact_hash(path: &Path) -> Result<HashResult, HashError> {
+    Ok(HashResult {
+        hash: compute_file_sha256(path)?,
+        algorithm: HashAlgorithm::Exact,
+        color_signature: None,
+        width: 0,
+        height: 0,
+    })
+}
+
+/// 判断哈希是否来自视频
+pub fn is_video_hash(hash: &str) -> bool {
+    hash.starts_with(VIDEO_HASH_PREFIX)
+}
+
+/// 比较两个视频的关键帧序列 (0-100)
+///
+/// 将两个帧序列按不同的偏移量对齐，计算重叠部分逐帧相似度的平均值，取最佳对齐。
+/// 这样一个视频是另一个视频的剪辑片段时，也能找到匹配的那一段。
+pub fn compare_video_hashes(hash1: &str, hash2: &str) -> f32 {
+    let frames1: Vec<&str> = hash1.trim_start_matches(VIDEO_HASH_PREFIX).split(',').collect();
+    let frames2: Vec<&str> = hash2.trim_start_matches(VIDEO_HASH_PREFIX).split(',').collect();
+    let (n1, n2) = (frames1.len() as isize, frames2.len() as isize);
+
+    let min_overlap = ((n1.min(n2) as f32 * MIN_OVERLAP_RATIO).ceil() as isize).max(1);
+
+    // offset表示frames2相对frames1的起始位置
+    (1 - n2..n1)
+        .filter_map(|offset| {
+            let start = offset.max(0);
+            let end = n1.min(offset + n2);
+            if end - start < min_overlap {
+                return None;
+            }
+
+            let total: f32 = (start..end)
+                .map(|i| hash_similarity(frames1[i as usize], frames2[(i - offset) as usize]))
+                .sum();
+            Some(total / (end - start) as f32)
+        })
+        .fold(0.0, f32::max)
+}
+
+/// 比较两个哈希，只要其中一个来自视频就返回结果
+///
+/// 两个都是视频时按关键帧序列比较；视频和图像之间相似度为0；都不是视频时返回None。
+pub fn compare_if_video(hash1: &str, hash2: &str) -> Option<f32> {
+    match (is_video_hash(hash1), is_video_hash(hash2)) {
+        (true, true) => Some(compare_video_hashes(hash1, hash2)),
+        (false, false) => None,
+        _ => Some(0.0),
+    }
+}
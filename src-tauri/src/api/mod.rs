@@ -1,46 +1,134 @@
-use crate::core::types::{DuplicateDetectionRequest, DuplicateGroup, HashAlgorithm};
+use crate::algorithms::{self, orb::{self, KeyPointInfo}};
+use crate::core::types::{
+    AlgorithmInfo, BenchmarkReport, CandidatePairEstimate, DetectionResults, DuplicateDetectionRequest, DuplicateGroup, HashAlgorithm, HashFolderResults, HashOptions,
+    HistogramBucket, ImageFileInfo, KeepRule, OrbParams, ResolveAction, ResolveReport, ResultSummary, SimilarityExplanation, ThresholdSuggestion, validate_threshold,
+};
+use crate::core::utils::file_utils::{expand_folder_glob, get_file_metadata, ScanOptions};
+use crate::core::utils::hash_utils;
+use crate::core::utils::image_utils::read_dimensions;
+use rayon::prelude::*;
 use crate::detection::duplicate::{
-    detect_duplicates, get_all_image_paths, DuplicateDetectionParams,
+    detect_duplicates, detect_duplicates_with_progress, estimate_candidate_pairs, find_matches as find_hash_matches, find_similar_to as find_similar_images, hash_folder as hash_image_folder,
+    find_similar_to_imagehash as find_similar_images_to_imagehash, get_all_image_paths,
+    similarity_histogram as compute_similarity_histogram, suggest_threshold as suggest_detection_threshold,
+    DuplicateDetectionParams,
 };
+use crate::detection::progress::{HashProgress, ProgressReporter, ScanProgress};
+use crate::detection::report::{render_html_report, render_json_report, REPORT_THUMBNAIL_SIZE};
+use crate::detection::resolve::resolve_duplicates as resolve_duplicate_groups;
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Instant;
-use tauri::command;
+use tauri::{command, AppHandle, Emitter, Manager, State};
 use walkdir::WalkDir;
 
 /// 获取文件夹中的图像文件路径
+///
+/// `follow_symlinks`为真时跟随符号链接（默认跳过）
 #[tauri::command(rename_all = "snake_case")]
-pub fn get_image_paths(folder_path: String, recursive: bool) -> Result<Vec<PathBuf>, String> {
+pub fn get_image_paths(
+    folder_path: String,
+    recursive: bool,
+    follow_symlinks: Option<bool>,
+) -> Result<Vec<PathBuf>, String> {
     let path = Path::new(&folder_path);
 
     if !path.exists() || !path.is_dir() {
         return Err(format!("无效的文件夹路径: {}", folder_path));
     }
 
-    crate::core::utils::file_utils::get_image_paths(path, recursive)
+    let options = ScanOptions {
+        recursive,
+        skip_hidden: true,
+        follow_symlinks: follow_symlinks.unwrap_or(false),
+        ..Default::default()
+    };
+    crate::core::utils::file_utils::get_image_paths(path, &options)
 }
 
+/// 最近一次检测结果的存储，由Tauri托管
+///
+/// 结果可能包含数万个重复组，一次性跨IPC传输很慢，前端通过分页命令按需获取
+pub type ResultStore = Mutex<Option<DetectionResults>>;
+
+/// 当前检测任务的取消标志，由Tauri托管
+#[derive(Default)]
+pub struct CancelFlag(AtomicBool);
+
 /// 查找重复图像
+///
+/// 检测结果保存在应用状态中，只返回摘要，重复组通过`get_result_page`分页获取。
+/// 检测在阻塞线程池中执行，期间可以通过`cancel_detection`取消，取消后返回已完成部分的结果。
 #[tauri::command(rename_all = "snake_case")]
-pub fn find_duplicates(req: DuplicateDetectionRequest) -> Result<Vec<DuplicateGroup>, String> {
+pub async fn find_duplicates(
+    app: AppHandle,
+    req: DuplicateDetectionRequest,
+) -> Result<ResultSummary, String> {
+    // 在进入阻塞线程池之前重置，参数检查和通配符展开期间收到的取消请求也会生效
+    app.state::<CancelFlag>().0.store(false, Ordering::Relaxed);
+    tauri::async_runtime::spawn_blocking(move || run_find_duplicates(&app, &req))
+        .await
+        .map_err(|e| format!("检测任务异常终止: {}", e))?
+}
+
+/// 取消正在进行的检测
+#[command]
+pub fn cancel_detection(cancel: State<'_, CancelFlag>) {
+    println!("收到取消检测请求");
+    cancel.0.store(true, Ordering::Relaxed);
+}
+
+/// 展开请求中包含glob通配符的文件夹路径
+///
+/// 同一个通配符展开得到的文件夹继承该项的分组标签；未设置分组时以原始项的位置作为标签，
+/// 因此“只查找跨文件夹的重复”仍按用户输入的每一项区分图库。
+fn expand_request_folders(req: &DuplicateDetectionRequest) -> Result<DuplicateDetectionRequest, String> {
+    let mut folder_paths = Vec::new();
+    let mut root_groups = Vec::new();
+
+    for (index, pattern) in req.folder_paths.iter().enumerate() {
+        let group = req.root_groups.as_ref()
+            .and_then(|groups| groups.get(index).copied())
+            .unwrap_or(index);
+        for folder in expand_folder_glob(pattern)? {
+            folder_paths.push(folder.to_string_lossy().into_owned());
+            root_groups.push(group);
+        }
+    }
+
+    // 没有通配符时保持请求不变
+    if folder_paths == req.folder_paths {
+        return Ok(req.clone());
+    }
+
+    println!("文件夹通配符展开为 {} 个文件夹", folder_paths.len());
+    Ok(DuplicateDetectionRequest {
+        folder_paths,
+        root_groups: Some(root_groups),
+        ..req.clone()
+    })
+}
+
+/// 执行重复检测并保存结果
+fn run_find_duplicates(app: &AppHandle, req: &DuplicateDetectionRequest) -> Result<ResultSummary, String> {
+    req.validate()?;
+    let req = &expand_request_folders(req)?;
+    let cancel = app.state::<CancelFlag>();
+    
     // 开始API调用计时
     let api_start_time = Instant::now();
     println!("开始处理重复图片检测请求...");
     
     // 转换参数
-    let folder_paths: Vec<PathBuf> = req.folder_paths.iter().map(|p| PathBuf::from(p)).collect();
-
-    let params = DuplicateDetectionParams {
-        folders: folder_paths,
-        algorithm: req.algorithm,
-        threshold: req.similarity_threshold as f32,
-        recursive: req.recursive,
-    };
+    let params = DuplicateDetectionParams::from(req);
 
     println!("算法: {:?}, 相似度阈值: {}, 递归扫描: {}", 
              req.algorithm, req.similarity_threshold, req.recursive);
 
     // 执行重复检测
-    let result = detect_duplicates(&params);
+    let result = detect_duplicates_with_progress(&params, &EventProgress { app, cancelled: &cancel.0 });
     
     // 计算API总耗时
     let api_total_time = api_start_time.elapsed();
@@ -48,49 +136,383 @@ pub fn find_duplicates(req: DuplicateDetectionRequest) -> Result<Vec<DuplicateGr
     
     // 打印结果摘要
     match &result {
-        Ok(groups) => {
+        Ok(DetectionResults { groups, errors, .. }) => {
             let total_images = groups.iter().map(|g| g.images.len()).sum::<usize>();
             let unique_images = groups.iter()
                 .flat_map(|g| g.images.iter().map(|img| img.path.clone()))
                 .collect::<std::collections::HashSet<_>>()
                 .len();
                 
-            println!("检测完成，找到 {} 组重复图片，共涉及 {} 张图片 (去重后 {} 张不同图片)", 
-                     groups.len(), total_images, unique_images);
+            println!("检测完成，找到 {} 组重复图片，共涉及 {} 张图片 (去重后 {} 张不同图片)，{} 个文件无法处理", 
+                     groups.len(), total_images, unique_images, errors.len());
         },
         Err(e) => {
             println!("检测失败: {}", e);
         }
     }
     
-    result
+    let mut results = result?;
+    // 阈值远离推荐范围时不报错，只在结果中提示，避免用户把空结果误认为程序故障
+    if let Some(warning) = req.algorithm.threshold_warning(req.similarity_threshold) {
+        println!("{}", warning);
+        results.warnings.push(warning);
+    }
+    let summary = results.summary();
+    let store = app.state::<ResultStore>();
+    *store.lock().map_err(|_| "结果存储锁被毒化".to_string())? = Some(results);
+    
+    Ok(summary)
+}
+
+/// 获取最近一次检测结果的摘要
+#[command]
+pub fn get_result_summary(store: State<'_, ResultStore>) -> Result<ResultSummary, String> {
+    let results = store.lock().map_err(|_| "结果存储锁被毒化".to_string())?;
+    results.as_ref()
+        .map(DetectionResults::summary)
+        .ok_or_else(|| "还没有检测结果".to_string())
+}
+
+/// 分页获取最近一次检测结果中的重复组
+#[tauri::command(rename_all = "snake_case")]
+pub fn get_result_page(
+    store: State<'_, ResultStore>,
+    offset: usize,
+    limit: usize,
+) -> Result<Vec<DuplicateGroup>, String> {
+    let results = store.lock().map_err(|_| "结果存储锁被毒化".to_string())?;
+    results.as_ref()
+        .map(|results| results.page(offset, limit))
+        .ok_or_else(|| "还没有检测结果".to_string())
+}
+
+/// 将最近一次检测结果导出为JSON文件
+///
+/// `hex_hashes`为true时二值哈希以十六进制导出，与其他imagehash工具的格式一致，文件约缩小为四分之一
+#[tauri::command(rename_all = "snake_case")]
+pub fn export_results_json(
+    store: State<'_, ResultStore>,
+    path: String,
+    hex_hashes: bool,
+) -> Result<(), String> {
+    let json = {
+        let results = store.lock().map_err(|_| "结果存储锁被毒化".to_string())?;
+        let results = results.as_ref().ok_or_else(|| "还没有检测结果".to_string())?;
+        render_json_report(results, hex_hashes)?
+    };
+
+    std::fs::write(&path, json)
+        .map_err(|e| format!("无法写入结果文件 {}: {}", path, e))?;
+
+    println!("检测结果已导出到 {}", path);
+    Ok(())
+}
+
+/// 将最近一次检测结果导出为自包含的HTML报告
+///
+/// 报告内嵌缩略图、文件大小和每组可释放的空间，可以离线在任何浏览器中查看。
+/// 生成缩略图需要解码每张图像，在阻塞线程池中执行。
+#[tauri::command(rename_all = "snake_case")]
+pub async fn export_report_html(app: AppHandle, path: String) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let start_time = Instant::now();
+
+        let html = {
+            let store = app.state::<ResultStore>();
+            let results = store.lock().map_err(|_| "结果存储锁被毒化".to_string())?;
+            let results = results.as_ref().ok_or_else(|| "还没有检测结果".to_string())?;
+            render_html_report(results, REPORT_THUMBNAIL_SIZE)
+        };
+
+        std::fs::write(&path, html)
+            .map_err(|e| format!("无法写入报告 {}: {}", path, e))?;
+
+        println!("HTML报告已导出到 {}，耗时: {:?}", path, start_time.elapsed());
+        Ok(())
+    })
+    .await
+    .map_err(|e| format!("导出任务异常终止: {}", e))?
+}
+
+/// 按保留规则处理重复组：每组保留一张图像，其余移到回收站、替换为硬链接或只预演
+///
+/// 返回每个文件的处理结果，单个文件失败不会中断其他文件。`DryRun`不修改任何文件。
+/// 文件操作在阻塞线程池中执行。
+#[tauri::command(rename_all = "snake_case")]
+pub async fn resolve_duplicates(
+    groups: Vec<DuplicateGroup>,
+    keep_rule: KeepRule,
+    action: ResolveAction,
+) -> Result<ResolveReport, String> {
+    tauri::async_runtime::spawn_blocking(move || resolve_duplicate_groups(&groups, keep_rule, action))
+        .await
+        .map_err(|e| format!("处理任务异常终止: {}", e))
+}
+
+/// 将检测进度转发为前端事件
+struct EventProgress<'a> {
+    app: &'a AppHandle,
+    cancelled: &'a AtomicBool,
+}
+
+impl ProgressReporter for EventProgress<'_> {
+    fn scan_progress(&self, progress: &ScanProgress) {
+        // 事件发送失败（例如窗口已关闭）不影响检测本身
+        let _ = self.app.emit("scan-progress", progress);
+    }
+    
+    fn hash_progress(&self, progress: &HashProgress) {
+        let _ = self.app.emit("hash-progress", progress);
+    }
+    
+    fn group_found(&self, group: &DuplicateGroup) {
+        let _ = self.app.emit("duplicate-group-found", group);
+    }
+    
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// 在文件夹中查找与参考图像相似的图像（以图搜图）
+///
+/// 返回按相似度降序排列的(路径, 相似度)列表。需要扫描并计算整个文件夹的哈希，在阻塞线程池中执行
+#[tauri::command(rename_all = "snake_case")]
+pub async fn find_similar_to(
+    reference_path: String,
+    folder_paths: Vec<String>,
+    algorithm: HashAlgorithm,
+    threshold: f32,
+    recursive: bool,
+) -> Result<Vec<(String, f32)>, String> {
+    validate_threshold(threshold)?;
+    let reference = Path::new(&reference_path);
+    if !reference.is_file() {
+        return Err(format!("无效的参考图片路径: {}", reference_path));
+    }
+
+    let folders = folder_paths.iter().map(PathBuf::from).collect();
+    let params = DuplicateDetectionParams::new(folders, algorithm, threshold, recursive);
+
+    let reference = reference.to_path_buf();
+    let matches = tauri::async_runtime::spawn_blocking(move || find_similar_images(&reference, &params))
+        .await
+        .map_err(|e| format!("查找任务异常终止: {}", e))??;
+
+    Ok(matches.into_iter()
+        .map(|(path, similarity)| (path.to_string_lossy().into_owned(), similarity))
+        .collect())
+}
+
+/// 只扫描并计算文件夹中所有图像的哈希，不做分组
+///
+/// 返回每张图像的路径、哈希和原始尺寸，以及无法处理的文件（逐个列出，即使全部失败也正常返回）；
+/// 可用于测量解码和哈希耗时或导出哈希库。在阻塞线程池中执行。
+#[tauri::command(rename_all = "snake_case")]
+pub async fn hash_folder(
+    folder_paths: Vec<String>,
+    algorithm: HashAlgorithm,
+    recursive: bool,
+) -> Result<HashFolderResults, String> {
+    let folders = folder_paths.iter().map(PathBuf::from).collect();
+    // 不分组，阈值不起作用
+    let params = DuplicateDetectionParams::new(folders, algorithm, algorithm.default_threshold(), recursive);
+
+    tauri::async_runtime::spawn_blocking(move || hash_image_folder(&params))
+        .await
+        .map_err(|e| format!("检测任务异常终止: {}", e))?
 }
 
-/// 获取支持的算法列表
+/// 比较两个Python `imagehash`库生成的十六进制哈希，返回相似度(0-100)
+///
+/// 支持均值、差值和感知哈希的默认64位格式
+#[tauri::command(rename_all = "snake_case")]
+pub fn compare_hex_hashes(hex1: String, hex2: String, algorithm: HashAlgorithm) -> Result<f32, String> {
+    let hash1 = hash_utils::from_imagehash_hex(&hex1, algorithm)?;
+    let hash2 = hash_utils::from_imagehash_hex(&hex2, algorithm)?;
+
+    algorithms::checked_similarity(&hash1, &hash2, algorithm)
+}
+
+/// 在文件夹中查找与imagehash十六进制哈希相似的图像
+///
+/// 返回按相似度降序排列的(路径, 相似度)列表。需要计算文件夹中所有图像的哈希，在阻塞线程池中执行
+#[tauri::command(rename_all = "snake_case")]
+pub async fn find_similar_to_imagehash(
+    hex_hash: String,
+    folder_paths: Vec<String>,
+    algorithm: HashAlgorithm,
+    threshold: f32,
+    recursive: bool,
+) -> Result<Vec<(String, f32)>, String> {
+    validate_threshold(threshold)?;
+    let folders = folder_paths.iter().map(PathBuf::from).collect();
+    let params = DuplicateDetectionParams::new(folders, algorithm, threshold, recursive);
+
+    let matches = tauri::async_runtime::spawn_blocking(move || find_similar_images_to_imagehash(&hex_hash, &params))
+        .await
+        .map_err(|e| format!("查找任务异常终止: {}", e))??;
+
+    Ok(matches.into_iter()
+        .map(|(path, similarity)| (path.to_string_lossy().into_owned(), similarity))
+        .collect())
+}
+
+/// 在预先计算的哈希列表中查找与参考哈希相似的项（纯哈希比较，不读取文件）
+///
+/// 哈希应为本程序此前输出的格式，返回按相似度降序排列的(下标, 相似度)列表
+#[tauri::command(rename_all = "snake_case")]
+pub fn find_matches(
+    reference_hash: String,
+    hashes: Vec<String>,
+    algorithm: HashAlgorithm,
+    threshold: f32,
+) -> Result<Vec<(usize, f32)>, String> {
+    validate_threshold(threshold)?;
+    find_hash_matches(&reference_hash, &hashes, algorithm, threshold)
+}
+
+/// 按固定大小分块比较两个文件，返回相同块所占的比例(0-100)
+///
+/// 用于发现只改写了文件头或文件尾的副本（例如重新写入标签），这类文件的SHA-256不同。
+/// `block_size`未设置时使用64KB。
+#[tauri::command(rename_all = "snake_case")]
+pub fn compare_files_by_blocks(
+    path1: String,
+    path2: String,
+    block_size: Option<usize>,
+) -> Result<f32, String> {
+    let block_size = block_size.unwrap_or(hash_utils::DEFAULT_FILE_BLOCK_SIZE);
+    let blocks1 = hash_utils::compute_file_block_hashes(Path::new(&path1), block_size)?;
+    let blocks2 = hash_utils::compute_file_block_hashes(Path::new(&path2), block_size)?;
+
+    Ok(hash_utils::block_hash_similarity(&blocks1, &blocks2))
+}
+
+/// 根据候选对的相似度分布建议一个阈值
+///
+/// 同时返回相似度直方图，便于前端绘制分布图。请求中的相似度阈值不影响结果，
+/// 扫描、哈希和算法参数与`find_duplicates`一致。在阻塞线程池中执行。
+#[tauri::command(rename_all = "snake_case")]
+pub async fn suggest_threshold(req: DuplicateDetectionRequest) -> Result<ThresholdSuggestion, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        req.validate()?;
+        let params = DuplicateDetectionParams::from(&expand_request_folders(&req)?);
+        suggest_detection_threshold(&params)
+    })
+    .await
+    .map_err(|e| format!("检测任务异常终止: {}", e))?
+}
+
+/// 统计候选对的相似度分布，分为`bins`个等宽区间
+///
+/// 帮助用户在正式检测前了解重复图像的相似度集中在哪个范围。在阻塞线程池中执行。
+#[tauri::command(rename_all = "snake_case")]
+pub async fn similarity_histogram(req: DuplicateDetectionRequest, bins: usize) -> Result<Vec<HistogramBucket>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        req.validate()?;
+        let params = DuplicateDetectionParams::from(&expand_request_folders(&req)?);
+        compute_similarity_histogram(&params, bins)
+    })
+    .await
+    .map_err(|e| format!("检测任务异常终止: {}", e))?
+}
+
+/// 运行一次完整检测并返回各阶段的计数和耗时，用于性能分析
+///
+/// 检测结果不会保存到应用状态中。在阻塞线程池中执行。
+#[tauri::command(rename_all = "snake_case")]
+pub async fn benchmark_detection(req: DuplicateDetectionRequest) -> Result<BenchmarkReport, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        req.validate()?;
+        let params = DuplicateDetectionParams::from(&expand_request_folders(&req)?);
+        let results = detect_duplicates(&params)?;
+        Ok(BenchmarkReport::from_results(&results))
+    })
+    .await
+    .map_err(|e| format!("检测任务异常终止: {}", e))?
+}
+
+/// 检测图像的ORB特征点（调试用），返回的坐标基于原始图像尺寸
+#[tauri::command(rename_all = "snake_case")]
+pub fn get_keypoints(path: String, algorithm_params: Option<OrbParams>) -> Result<Vec<KeyPointInfo>, String> {
+    let options = HashOptions {
+        orb: algorithm_params.unwrap_or_default(),
+        ..Default::default()
+    };
+
+    orb::get_keypoints(Path::new(&path), &options)
+}
+
+/// 计算两张图像的哈希值并解释它们的差异（哪些比特不同、汉明距离和相似度）
+#[tauri::command(rename_all = "snake_case")]
+pub fn explain_similarity(path1: String, path2: String, algorithm: HashAlgorithm) -> Result<SimilarityExplanation, String> {
+    let options = HashOptions::default();
+    let hash1 = algorithms::calculate_hash(Path::new(&path1), algorithm, &options)?;
+    let hash2 = algorithms::calculate_hash(Path::new(&path2), algorithm, &options)?;
+
+    algorithms::explain_similarity(&hash1.hash, &hash2.hash, algorithm)
+}
+
+/// 获取单个文件的图像信息（尺寸、大小和时间戳），不计算哈希
+///
+/// 只读取文件头获取尺寸，不解码整张图像，适合前端悬停时频繁调用。
+#[tauri::command(rename_all = "snake_case")]
+pub fn get_image_info(path: String) -> Result<ImageFileInfo, String> {
+    let file_path = Path::new(&path);
+    let (width, height) = read_dimensions(file_path)?;
+    let (size_bytes, created_at, modified_at) = get_file_metadata(file_path)?;
+
+    Ok(ImageFileInfo {
+        path,
+        width,
+        height,
+        size_bytes,
+        created_at,
+        modified_at,
+    })
+}
+
+/// 获取支持的算法列表及其描述信息
 #[command]
-pub fn get_supported_algorithms() -> Vec<String> {
-    vec![
-        "精确哈希".to_string(),
-        "均值哈希".to_string(),
-        "差值哈希".to_string(),
-        "感知哈希".to_string(),
-        "ORB特征".to_string(),
-    ]
+pub fn get_supported_algorithms() -> Vec<AlgorithmInfo> {
+    HashAlgorithm::all()
+        .iter()
+        .map(|algorithm| algorithm.info())
+        .collect()
 }
 
 /// 计算重复检测的统计信息
+///
+/// `estimate_pairs`为真时额外统计LSH候选对数量，帮助判断检测需要几秒还是几小时。
+/// 这需要计算哈希，图像较多时只对样本计算并给出估算值。扫描和估算在阻塞线程池中执行。
 #[tauri::command(rename_all = "snake_case")]
-pub fn get_detection_stats(req: DuplicateDetectionRequest) -> Result<DetectionStats, String> {
-    let folder_paths: Vec<PathBuf> = req.folder_paths.iter().map(|p| PathBuf::from(p)).collect();
+pub async fn get_detection_stats(req: DuplicateDetectionRequest, estimate_pairs: Option<bool>) -> Result<DetectionStats, String> {
+    req.validate()?;
+    let req = expand_request_folders(&req)?;
+    let params = DuplicateDetectionParams::from(&req);
+    let folder_count = params.folders.len();
 
-    // 获取所有图像路径
-    let all_paths = get_all_image_paths(&folder_paths, req.recursive)?;
+    let (all_paths, candidate_pairs) = tauri::async_runtime::spawn_blocking(move || {
+        // 获取所有图像路径
+        let all_paths = get_all_image_paths(&params.folders, &params.scan_options()?)?;
+
+        let candidate_pairs = if estimate_pairs.unwrap_or(false) {
+            Some(estimate_candidate_pairs(&all_paths, &params)?)
+        } else {
+            None
+        };
+        Ok::<_, String>((all_paths, candidate_pairs))
+    })
+    .await
+    .map_err(|e| format!("统计任务异常终止: {}", e))??;
 
     Ok(DetectionStats {
         image_count: all_paths.len(),
-        folder_count: folder_paths.len(),
+        folder_count,
         algorithm: req.algorithm.name().to_string(),
         similarity_threshold: req.similarity_threshold,
+        candidate_pairs,
     })
 }
 
@@ -104,7 +526,9 @@ pub struct DetectionStats {
     /// 使用的算法名称
     pub algorithm: String,
     /// 相似度阈值
-    pub similarity_threshold: u32,
+    pub similarity_threshold: f32,
+    /// LSH候选对数量，只在请求时统计
+    pub candidate_pairs: Option<CandidatePairEstimate>,
 }
 
 /// 文件夹统计信息
@@ -116,38 +540,110 @@ pub struct FolderStats {
     pub image_count: usize,
     /// 文件夹数（包括子文件夹）
     pub folder_count: usize,
+    /// 所有文件的总大小（字节）
+    pub total_bytes: u64,
+    /// 图像文件的总大小（字节）
+    pub image_bytes: u64,
+    /// 按扩展名（小写）统计的图像数量
+    pub by_extension: HashMap<String, usize>,
+    /// 图像总像素数（百万像素），仅在请求尺寸统计时计算
+    pub total_megapixels: Option<f64>,
+    /// 无法读取尺寸的图像数
+    pub unreadable_dimensions: usize,
+    /// 按顶层子文件夹统计的图像数量和大小，按图像大小降序排列，仅在递归且请求时统计
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subfolders: Option<Vec<SubfolderStats>>,
+}
+
+/// 单个顶层子文件夹内（含其所有子目录）的图像统计
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct SubfolderStats {
+    /// 子文件夹名称（相对于扫描根目录的第一级路径），根目录本身的文件记为"."
+    pub name: String,
+    /// 图像文件数
+    pub image_count: usize,
+    /// 图像文件的总大小（字节）
+    pub image_bytes: u64,
 }
 
 /// 获取文件夹的统计信息（文件总数、图像数等）
+///
+/// `include_dimensions`为真时，额外读取每张图像的文件头统计总像素数（不解码像素数据）。
+/// `follow_symlinks`为真时跟随符号链接（默认跳过），指向同一目标的多个链接只统计一次。
+/// `include_subfolders`为真且递归时，额外按顶层子文件夹汇总图像数量和大小，帮助用户决定从哪里开始详细扫描。
 #[tauri::command(rename_all = "snake_case")]
-pub fn get_folder_stats(folder_path: String, recursive: bool) -> Result<FolderStats, String> {
+pub fn get_folder_stats(
+    folder_path: String,
+    recursive: bool,
+    include_dimensions: Option<bool>,
+    follow_symlinks: Option<bool>,
+    include_subfolders: Option<bool>,
+) -> Result<FolderStats, String> {
     let path = Path::new(&folder_path);
 
     if !path.exists() || !path.is_dir() {
         return Err(format!("无效的文件夹路径: {}", folder_path));
     }
 
+    let include_dimensions = include_dimensions.unwrap_or(false);
+    let follow_symlinks = follow_symlinks.unwrap_or(false);
+    // 跟随符号链接时按规范路径去重，避免同一目标被重复统计
+    let mut seen = std::collections::HashSet::new();
+    let mut is_new_target = |path: &Path| {
+        !follow_symlinks || seen.insert(path.canonicalize().unwrap_or_else(|_| path.to_path_buf()))
+    };
     let mut stats = FolderStats {
         total_files: 0,
         image_count: 0,
         folder_count: 1, // 包括当前文件夹
+        total_bytes: 0,
+        image_bytes: 0,
+        by_extension: HashMap::new(),
+        total_megapixels: None,
+        unreadable_dimensions: 0,
+        subfolders: None,
     };
+    let mut image_paths = Vec::new();
+    let include_subfolders = recursive && include_subfolders.unwrap_or(false);
+    let mut subfolders: HashMap<String, SubfolderStats> = HashMap::new();
 
     // 如果递归，使用WalkDir遍历所有子目录和文件
     if recursive {
-        for entry in WalkDir::new(path).into_iter().filter_map(|e| e.ok()) {
+        // 跟随符号链接时WalkDir会检测循环并返回错误，与其他遍历错误一样跳过
+        for entry in WalkDir::new(path).follow_links(follow_symlinks).into_iter().filter_map(|e| e.ok()) {
             if entry.path() == path {
                 continue; // 跳过当前文件夹自身
             }
             
+            // 不跟随时跳过符号链接
+            if entry.path_is_symlink() && !follow_symlinks {
+                continue;
+            }
+            
             if entry.path().is_dir() {
-                stats.folder_count += 1;
-            } else if entry.path().is_file() {
+                if is_new_target(entry.path()) {
+                    stats.folder_count += 1;
+                }
+            } else if entry.path().is_file() && is_new_target(entry.path()) {
+                let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
                 stats.total_files += 1;
+                stats.total_bytes += size;
                 
                 // 检查是否为图像文件
                 if crate::core::utils::file_utils::is_image_file(entry.path()) {
                     stats.image_count += 1;
+                    stats.image_bytes += size;
+                    count_extension(&mut stats.by_extension, entry.path());
+                    if include_dimensions {
+                        image_paths.push(entry.path().to_path_buf());
+                    }
+                    if include_subfolders {
+                        let name = top_level_subfolder(path, entry.path());
+                        let subfolder = subfolders.entry(name.clone())
+                            .or_insert_with(|| SubfolderStats { name, ..Default::default() });
+                        subfolder.image_count += 1;
+                        subfolder.image_bytes += size;
+                    }
                 }
             }
         }
@@ -159,17 +655,25 @@ pub fn get_folder_stats(folder_path: String, recursive: bool) -> Result<FolderSt
             if let Ok(entry) = entry {
                 let path = entry.path();
                 
-                // 跳过符号链接
-                if path.is_symlink() {
+                // 不跟随时跳过符号链接
+                if path.is_symlink() && !follow_symlinks {
                     continue;
                 }
                 
-                if path.is_file() {
+                if path.is_file() && is_new_target(&path) {
+                    // 跟随符号链接，统计目标文件的大小
+                    let size = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
                     stats.total_files += 1;
+                    stats.total_bytes += size;
                     
                     // 检查是否为图像文件
                     if crate::core::utils::file_utils::is_image_file(&path) {
                         stats.image_count += 1;
+                        stats.image_bytes += size;
+                        count_extension(&mut stats.by_extension, &path);
+                        if include_dimensions {
+                            image_paths.push(path.clone());
+                        }
                     }
                 } else if path.is_dir() {
                     stats.folder_count += 1;
@@ -178,5 +682,44 @@ pub fn get_folder_stats(folder_path: String, recursive: bool) -> Result<FolderSt
         }
     }
 
+    if include_subfolders {
+        let mut subfolders: Vec<SubfolderStats> = subfolders.into_values().collect();
+        subfolders.sort_by(|a, b| b.image_bytes.cmp(&a.image_bytes).then_with(|| a.name.cmp(&b.name)));
+        stats.subfolders = Some(subfolders);
+    }
+
+    if include_dimensions {
+        // 并行读取文件头获取尺寸
+        let dimensions: Vec<Option<(u32, u32)>> = image_paths
+            .par_iter()
+            .map(|p| read_dimensions(p).ok())
+            .collect();
+
+        let total_pixels: u64 = dimensions.iter()
+            .flatten()
+            .map(|&(w, h)| w as u64 * h as u64)
+            .sum();
+
+        stats.unreadable_dimensions = dimensions.iter().filter(|d| d.is_none()).count();
+        stats.total_megapixels = Some(total_pixels as f64 / 1_000_000.0);
+    }
+
     Ok(stats)
 }
+
+/// 返回文件相对于扫描根目录的第一级子文件夹名称，直接位于根目录下的文件返回"."
+fn top_level_subfolder(root: &Path, file: &Path) -> String {
+    let relative = file.strip_prefix(root).unwrap_or(file);
+    let mut components = relative.components();
+    match (components.next(), components.next()) {
+        (Some(first), Some(_)) => first.as_os_str().to_string_lossy().into_owned(),
+        _ => ".".to_string(),
+    }
+}
+
+/// 按小写扩展名累加图像计数
+fn count_extension(counts: &mut HashMap<String, usize>, path: &Path) {
+    if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+        *counts.entry(ext.to_lowercase()).or_insert(0) += 1;
+    }
+}
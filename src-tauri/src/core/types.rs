@@ -1,5 +1,8 @@
 use serde::{Serialize, Deserialize};
-use std::path::PathBuf;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use image::imageops::FilterType;
 
 /// 哈希算法类型
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
@@ -12,11 +15,41 @@ pub enum HashAlgorithm {
     Difference,
     /// 感知哈希 (Perceptual Hash)
     Perceptual,
+    /// 边缘哈希 (基于Sobel梯度)
+    EdgeHash,
     /// 定向FAST和旋转BRIEF
     ORB,
+    /// 缩放副本：同一张图的不同分辨率版本（规范尺寸下的感知哈希加宽高比检查）
+    ScaledDuplicate,
 }
 
 impl HashAlgorithm {
+    /// 所有支持的算法
+    pub fn all() -> [HashAlgorithm; 7] {
+        [
+            Self::Exact,
+            Self::Average,
+            Self::Difference,
+            Self::Perceptual,
+            Self::EdgeHash,
+            Self::ORB,
+            Self::ScaledDuplicate,
+        ]
+    }
+    
+    /// 获取算法的英文标识符
+    pub fn id(&self) -> &'static str {
+        match self {
+            Self::Exact => "exact",
+            Self::Average => "ahash",
+            Self::Difference => "dhash",
+            Self::Perceptual => "phash",
+            Self::EdgeHash => "edgehash",
+            Self::ORB => "orb",
+            Self::ScaledDuplicate => "scaled",
+        }
+    }
+    
     /// 获取算法名称
     pub fn name(&self) -> &'static str {
         match self {
@@ -24,7 +57,9 @@ impl HashAlgorithm {
             Self::Average => "均值哈希",
             Self::Difference => "差值哈希",
             Self::Perceptual => "感知哈希",
+            Self::EdgeHash => "边缘哈希",
             Self::ORB => "ORB特征",
+            Self::ScaledDuplicate => "缩放副本",
         }
     }
     
@@ -32,6 +67,329 @@ impl HashAlgorithm {
     pub fn is_feature_based(&self) -> bool {
         matches!(self, Self::ORB)
     }
+    
+    /// 获取算法的简要说明
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::Exact => "对解码后的像素数据计算SHA-256，仅查找完全相同的图片",
+            Self::Average => "基于8x8灰度图与平均亮度的比较，速度快，对缩放和轻微修改有一定鲁棒性",
+            Self::Difference => "基于相邻像素的亮度差异，更好地捕捉纹理和边缘",
+            Self::Perceptual => "基于离散余弦变换的低频分量，对缩放、压缩等操作鲁棒，适合大多数场景",
+            Self::EdgeHash => "基于Sobel边缘强度的分布，不受颜色和亮度变化影响，适合线稿和截图",
+            Self::ORB => "基于特征点匹配，对旋转、裁剪和亮度变化鲁棒，但速度较慢",
+            Self::ScaledDuplicate => "专门查找同一张图片的不同分辨率版本（如原图与缩略图），要求宽高比一致，组内按像素数从大到小排列",
+        }
+    }
+    
+    /// 哈希值的比特长度，基于特征点的算法返回None
+    pub fn bit_length(&self) -> Option<u32> {
+        match self {
+            Self::Exact => Some(256),
+            Self::Average | Self::Difference | Self::Perceptual | Self::EdgeHash | Self::ScaledDuplicate => Some(64),
+            Self::ORB => None,
+        }
+    }
+    
+    /// 推荐的默认相似度阈值(0-100)
+    pub fn default_threshold(&self) -> f32 {
+        match self {
+            Self::Exact => 100.0,
+            Self::Average | Self::Difference | Self::Perceptual | Self::EdgeHash => 90.0,
+            Self::ORB => 60.0,
+            // 缩放只带来重采样误差，64位中最多允许3位不同
+            Self::ScaledDuplicate => 95.0,
+        }
+    }
+    
+    /// 推荐的相似度阈值范围(最小值, 最大值)，范围内的阈值通常能得到有意义的结果
+    ///
+    /// ORB的相似度是匹配特征点的比例，即使是同一张图像重新编码也很少超过80。
+    pub fn recommended_range(&self) -> (f32, f32) {
+        match self {
+            Self::Exact => (100.0, 100.0),
+            Self::Average | Self::Difference | Self::Perceptual | Self::EdgeHash => (80.0, 98.0),
+            Self::ORB => (30.0, 80.0),
+            Self::ScaledDuplicate => (90.0, 100.0),
+        }
+    }
+    
+    /// 检查阈值是否远离推荐范围，返回说明可能后果的提示
+    ///
+    /// 超出范围不超过`THRESHOLD_WARNING_MARGIN`时不提示；精确哈希的相似度只有0和100，任何阈值都不提示。
+    pub fn threshold_warning(&self, threshold: f32) -> Option<String> {
+        if *self == Self::Exact {
+            return None;
+        }
+        
+        let (min, max) = self.recommended_range();
+        if threshold > max + THRESHOLD_WARNING_MARGIN {
+            Some(format!("{}的相似度阈值{}远高于推荐范围{}-{}，很可能找不到任何重复", self, threshold, min, max))
+        } else if threshold < min - THRESHOLD_WARNING_MARGIN {
+            Some(format!("{}的相似度阈值{}远低于推荐范围{}-{}，结果中可能包含大量并不相似的图像", self, threshold, min, max))
+        } else {
+            None
+        }
+    }
+    
+    /// 获取算法的完整描述信息
+    pub fn info(&self) -> AlgorithmInfo {
+        AlgorithmInfo {
+            id: *self,
+            display_name: self.name().to_string(),
+            description: self.description().to_string(),
+            bit_length: self.bit_length(),
+            default_threshold: self.default_threshold(),
+            recommended_range: self.recommended_range(),
+            is_feature_based: self.is_feature_based(),
+        }
+    }
+}
+
+/// 阈值超出推荐范围多少（百分点）时才给出提示
+pub const THRESHOLD_WARNING_MARGIN: f32 = 10.0;
+
+/// 算法描述信息，用于前端渲染算法选择器
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlgorithmInfo {
+    /// 算法标识（与请求中的algorithm字段取值一致）
+    pub id: HashAlgorithm,
+    /// 显示名称
+    pub display_name: String,
+    /// 算法说明
+    pub description: String,
+    /// 哈希比特长度，基于特征点的算法为null
+    pub bit_length: Option<u32>,
+    /// 推荐的默认相似度阈值
+    pub default_threshold: f32,
+    /// 推荐的相似度阈值范围(最小值, 最大值)
+    pub recommended_range: (f32, f32),
+    /// 是否基于特征点
+    pub is_feature_based: bool,
+}
+
+impl fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for HashAlgorithm {
+    type Err = String;
+    
+    /// 解析算法名称，接受中文名称、英文标识符和枚举名（不区分大小写）
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalized = s.trim().to_lowercase();
+        
+        let algorithm = match normalized.as_str() {
+            "exact" | "sha256" => Self::Exact,
+            "average" | "ahash" => Self::Average,
+            "difference" | "dhash" => Self::Difference,
+            "perceptual" | "phash" => Self::Perceptual,
+            "edge" | "edgehash" => Self::EdgeHash,
+            "orb" => Self::ORB,
+            "scaled" | "scaledduplicate" | "scaled_duplicate" => Self::ScaledDuplicate,
+            _ => return Self::all()
+                .into_iter()
+                .find(|a| a.name() == s.trim())
+                .ok_or_else(|| format!("未知的算法: {}", s)),
+        };
+        
+        Ok(algorithm)
+    }
+}
+
+/// 图像缩放滤波器
+///
+/// 滤波质量越高速度越慢，对于感知哈希而言`Triangle`或`Nearest`通常已经足够。
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ResizeFilter {
+    /// 最近邻插值（最快）
+    Nearest,
+    /// 线性插值
+    Triangle,
+    /// 三次插值
+    CatmullRom,
+    /// 高斯滤波
+    Gaussian,
+    /// Lanczos窗口插值（质量最高，默认）
+    #[default]
+    Lanczos3,
+}
+
+impl ResizeFilter {
+    /// 转换为image库的滤波器类型
+    pub fn filter_type(&self) -> FilterType {
+        match self {
+            Self::Nearest => FilterType::Nearest,
+            Self::Triangle => FilterType::Triangle,
+            Self::CatmullRom => FilterType::CatmullRom,
+            Self::Gaussian => FilterType::Gaussian,
+            Self::Lanczos3 => FilterType::Lanczos3,
+        }
+    }
+}
+
+/// 灰度转换方式
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum GrayscaleMode {
+    /// 直接对sRGB编码值加权（默认，速度快，与早期版本的哈希一致）
+    #[default]
+    Gamma,
+    /// 先线性化sRGB再计算亮度，最后重新编码
+    ///
+    /// 对饱和色和暗部的亮度估计更接近人眼感知，适合色彩调整较多的图库；
+    /// 切换模式会改变所有哈希值，不同模式计算的哈希不可比较。
+    Linear,
+}
+
+/// 哈希计算选项（图像预处理相关）
+#[derive(Debug, Clone)]
+pub struct HashOptions {
+    /// 缩放图像时使用的滤波器
+    pub resize_filter: ResizeFilter,
+    /// 灰度转换方式
+    pub grayscale: GrayscaleMode,
+    /// 允许解码的最大像素数
+    pub max_pixels: u64,
+    /// 解码后图像的最大边长，超过时立即缩小（不影响报告的原始尺寸）
+    pub max_decode_dimension: u32,
+    /// 感知哈希前的高斯模糊σ，None表示不模糊
+    pub preprocess_blur: Option<f64>,
+    /// 均值和差值哈希前是否做亮度/对比度归一化
+    pub normalize: bool,
+    /// 计算哈希或特征前是否做直方图均衡化（适用于所有算法）
+    pub equalize_histogram: bool,
+    /// ORB算法参数
+    pub orb: OrbParams,
+    /// 二值哈希算法是否同时计算颜色签名，供颜色预过滤使用
+    pub color_signature: bool,
+}
+
+impl Default for HashOptions {
+    fn default() -> Self {
+        Self {
+            resize_filter: ResizeFilter::default(),
+            grayscale: GrayscaleMode::default(),
+            max_pixels: crate::core::utils::image_utils::DEFAULT_MAX_IMAGE_PIXELS,
+            max_decode_dimension: crate::core::utils::image_utils::DEFAULT_MAX_DECODE_DIMENSION,
+            preprocess_blur: None,
+            normalize: false,
+            equalize_histogram: false,
+            orb: OrbParams::default(),
+            color_signature: false,
+        }
+    }
+}
+
+/// ORB特征提取参数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OrbParams {
+    /// BRIEF采样模式的随机种子
+    ///
+    /// 描述子只有在使用相同采样模式时才可比较，修改种子会使之前计算的所有ORB特征失效
+    pub brief_seed: u64,
+    /// 每张图像最多保留的描述子数量
+    ///
+    /// 描述子越多，对细节丰富的图像和裁剪越鲁棒，但特征字符串越长（每个描述子约59字节Base64），
+    /// 匹配耗时也随两张图像描述子数量的乘积增长。缩略图等小图像用更小的值即可。
+    pub max_descriptors: usize,
+    /// FAST角点的最小响应得分，得分更低的角点在计算方向和描述子之前被丢弃，0表示不过滤
+    ///
+    /// 得分是圆周上16个像素与中心像素亮度差绝对值的平均值(0-255)。噪声较多的图像上
+    /// 调高该值可以去掉不稳定的弱角点，减少误匹配；设置过高时平坦的图像可能没有任何特征点。
+    pub min_corner_score: f32,
+}
+
+impl Default for OrbParams {
+    fn default() -> Self {
+        Self {
+            brief_seed: 42,
+            max_descriptors: 50,
+            min_corner_score: 0.0,
+        }
+    }
+}
+
+/// 按算法区分的参数
+///
+/// 以`algorithm`字段作为标签，变体必须与请求中选择的算法一致，例如
+/// `{"algorithm": "ORB", "max_descriptors": 100}`。设置后覆盖请求中对应的通用字段。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "algorithm")]
+pub enum AlgorithmParams {
+    /// 精确哈希没有可调参数
+    Exact,
+    /// 均值哈希
+    Average {
+        /// 是否做亮度/对比度归一化
+        #[serde(default)]
+        normalize: bool,
+    },
+    /// 差值哈希
+    Difference {
+        /// 是否做亮度/对比度归一化
+        #[serde(default)]
+        normalize: bool,
+    },
+    /// 感知哈希
+    Perceptual {
+        /// DCT前的高斯模糊σ，未设置时不模糊
+        #[serde(default)]
+        preprocess_blur: Option<f64>,
+    },
+    /// 边缘哈希没有可调参数
+    EdgeHash,
+    /// ORB特征提取参数
+    ORB(OrbParams),
+    /// 缩放副本没有可调参数
+    ScaledDuplicate,
+}
+
+impl AlgorithmParams {
+    /// 参数所属的算法
+    pub fn algorithm(&self) -> HashAlgorithm {
+        match self {
+            Self::Exact => HashAlgorithm::Exact,
+            Self::Average { .. } => HashAlgorithm::Average,
+            Self::Difference { .. } => HashAlgorithm::Difference,
+            Self::Perceptual { .. } => HashAlgorithm::Perceptual,
+            Self::EdgeHash => HashAlgorithm::EdgeHash,
+            Self::ORB(_) => HashAlgorithm::ORB,
+            Self::ScaledDuplicate => HashAlgorithm::ScaledDuplicate,
+        }
+    }
+}
+
+/// 检测流程的性能调优参数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DetectionConfig {
+    /// 哈希计算的批大小：每批图像并行解码和计算哈希，完成后合并结果并输出进度
+    pub hash_batch_size: usize,
+    /// 候选对查询的批大小：所有哈希共用一个LSH索引，查询按批并行进行以限制中间结果的内存占用
+    pub candidate_batch_size: usize,
+    /// 同时解码的最大图像数，0表示不限制（与工作线程数相同）
+    ///
+    /// 每个解码中的图像都以完整分辨率驻留在内存中，核心数多且图像很大时应调低该值。
+    /// 小于工作线程数时，哈希计算在该大小的独立线程池中进行
+    pub max_concurrent_decodes: usize,
+    /// LSH每个桶最多保存的图像数，None表示使用算法的默认值（1000到3000）
+    ///
+    /// 大量相似图像落入同一个桶时，超出的部分不会成为候选对，其中的真实重复会被漏掉。
+    /// 调大可以提高大簇相似图像的召回率，代价是更多的内存和候选对（相似度计算时间）。
+    pub max_bucket_size: Option<usize>,
+}
+
+impl Default for DetectionConfig {
+    fn default() -> Self {
+        Self {
+            hash_batch_size: 500,
+            candidate_batch_size: 10000,
+            max_concurrent_decodes: 8,
+            max_bucket_size: None,
+        }
+    }
 }
 
 /// 图像信息
@@ -41,15 +399,40 @@ pub struct ImageInfo {
     pub path: String,
     /// 图像哈希值或特征编码
     pub hash: String,
+    /// 计算哈希所用的算法
+    pub algorithm: HashAlgorithm,
     /// 图像宽度
     pub width: u32,
     /// 图像高度
     pub height: u32,
     /// 文件大小（字节）
     pub size_bytes: u64,
-    /// 创建时间
+    /// 创建时间（UTC的RFC 3339字符串，不可用时为空字符串）
     pub created_at: String,
-    /// 修改时间
+    /// 修改时间（UTC的RFC 3339字符串，不可用时为空字符串）
+    pub modified_at: String,
+    /// 图像所属的扫描根目录
+    #[serde(default)]
+    pub source_root: String,
+    /// Base64编码的PNG缩略图，只在请求了缩略图时生成
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thumbnail: Option<String>,
+}
+
+/// 单个文件的图像信息，不计算哈希，因此没有哈希和算法字段
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageFileInfo {
+    /// 图像路径
+    pub path: String,
+    /// 图像宽度
+    pub width: u32,
+    /// 图像高度
+    pub height: u32,
+    /// 文件大小（字节）
+    pub size_bytes: u64,
+    /// 创建时间（UTC的RFC 3339字符串，不可用时为空字符串）
+    pub created_at: String,
+    /// 修改时间（UTC的RFC 3339字符串，不可用时为空字符串）
     pub modified_at: String,
 }
 
@@ -60,13 +443,475 @@ pub struct DuplicateGroup {
     pub images: Vec<ImageInfo>,
     /// 相似度阈值
     pub similarity_threshold: f32,
+    /// 只保留组内最大的文件时可以释放的空间（字节）
+    #[serde(default)]
+    pub wasted_bytes: u64,
+}
+
+impl DuplicateGroup {
+    /// 根据组内图像创建重复组，并计算可释放的空间
+    pub fn new(images: Vec<ImageInfo>, similarity_threshold: f32) -> Self {
+        let total: u64 = images.iter().map(|img| img.size_bytes).sum();
+        let largest = images.iter().map(|img| img.size_bytes).max().unwrap_or(0);
+        
+        Self {
+            images,
+            similarity_threshold,
+            wasted_bytes: total - largest,
+        }
+    }
+    
+    /// 组内是否至少有两个不同的文件名（只比较文件名，不含目录）
+    pub fn has_different_names(&self) -> bool {
+        let mut names = self.images.iter()
+            .map(|img| Path::new(&img.path).file_name());
+        
+        match names.next() {
+            Some(first) => names.any(|name| name != first),
+            None => false,
+        }
+    }
+}
+
+/// 处理重复组时选择保留哪一张图像的规则
+///
+/// 规则无法区分时（例如大小相同）按路径字典序取第一个，保证结果确定。
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum KeepRule {
+    /// 保留文件最大的（默认，通常质量最高）
+    #[default]
+    Largest,
+    /// 保留文件最小的
+    Smallest,
+    /// 保留修改时间最新的
+    Newest,
+    /// 保留修改时间最早的（通常是原件）
+    Oldest,
+    /// 保留像素数最多的
+    HighestResolution,
+    /// 保留路径最短的
+    ShortestPath,
+}
+
+/// 对重复组中不保留的文件执行的操作
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ResolveAction {
+    /// 只保留一张，其余移到回收站
+    KeepOnly,
+    /// 其余文件替换为指向保留文件的硬链接（需要在同一文件系统上）
+    Hardlink,
+    /// 只生成计划，不修改任何文件
+    DryRun,
+}
+
+/// 单个重复组的处理计划
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupPlan {
+    /// 保留的图像路径
+    pub keep: String,
+    /// 需要处理（移到回收站或替换为硬链接）的图像
+    pub remove: Vec<ImageInfo>,
+}
+
+/// 单个文件的处理结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileResolution {
+    /// 文件路径
+    pub path: String,
+    /// 是否成功；预演时表示将被处理
+    pub success: bool,
+    /// 失败原因
+    pub error: Option<String>,
+}
+
+/// 单个重复组的处理结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupResolution {
+    /// 保留的图像路径
+    pub keep: String,
+    /// 组内其余文件的处理结果
+    pub files: Vec<FileResolution>,
+}
+
+/// 批量处理重复组的报告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResolveReport {
+    /// 执行的操作
+    pub action: ResolveAction,
+    /// 每个组的处理结果
+    pub groups: Vec<GroupResolution>,
+    /// 成功处理的文件数
+    pub succeeded: usize,
+    /// 处理失败的文件数
+    pub failed: usize,
+    /// 成功处理的文件释放（预演时为将释放）的空间（字节）
+    pub freed_bytes: u64,
+}
+
+/// 无法处理的文件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileError {
+    /// 文件路径
+    pub path: String,
+    /// 失败原因
+    pub reason: String,
+    /// 图像无法解码时的分类原因，供前端按类别汇总；目录读取失败等其他错误为None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decode_error: Option<DecodeError>,
+}
+
+impl FileError {
+    /// 根据哈希计算失败的原因创建错误记录
+    pub fn from_hash_error(path: String, error: HashError) -> Self {
+        Self {
+            path,
+            reason: error.to_string(),
+            decode_error: match error {
+                HashError::Decode(decode_error) => Some(decode_error),
+                HashError::Other(_) => None,
+            },
+        }
+    }
+}
+
+/// 图像解码失败的原因
+///
+/// 序列化为以`kind`为标签的JSON对象，例如`{"kind": "Corrupt", "path": "...", "message": "..."}`。
+/// `message`是完整的错误说明（已包含路径），与`Display`的输出一致。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "kind")]
+pub enum DecodeError {
+    /// 格式无法识别或不受支持
+    Unsupported { path: String, message: String },
+    /// 文件为空、被截断或内容损坏
+    Corrupt { path: String, message: String },
+    /// 像素数或解码所需内存超过上限
+    TooLarge { path: String, message: String },
+    /// 没有读取权限
+    PermissionDenied { path: String, message: String },
+    /// 文件不存在
+    NotFound { path: String, message: String },
+}
+
+impl DecodeError {
+    /// 出错的文件路径
+    pub fn path(&self) -> &str {
+        match self {
+            Self::Unsupported { path, .. } |
+            Self::Corrupt { path, .. } |
+            Self::TooLarge { path, .. } |
+            Self::PermissionDenied { path, .. } |
+            Self::NotFound { path, .. } => path,
+        }
+    }
+    
+    /// 完整的错误说明
+    pub fn message(&self) -> &str {
+        match self {
+            Self::Unsupported { message, .. } |
+            Self::Corrupt { message, .. } |
+            Self::TooLarge { message, .. } |
+            Self::PermissionDenied { message, .. } |
+            Self::NotFound { message, .. } => message,
+        }
+    }
+    
+    /// 在错误说明后追加更多信息，分类不变
+    pub fn with_detail(mut self, detail: &str) -> Self {
+        match &mut self {
+            Self::Unsupported { message, .. } |
+            Self::Corrupt { message, .. } |
+            Self::TooLarge { message, .. } |
+            Self::PermissionDenied { message, .. } |
+            Self::NotFound { message, .. } => message.push_str(detail),
+        }
+        self
+    }
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+/// 仍返回`String`错误的调用方可以直接用`?`传播解码错误
+impl From<DecodeError> for String {
+    fn from(error: DecodeError) -> Self {
+        error.to_string()
+    }
+}
+
+/// 计算哈希失败的原因（按路径或对已解码的图像）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HashError {
+    /// 图像无法解码
+    Decode(DecodeError),
+    /// 图像已解码但无法计算哈希（例如视频抽帧失败）
+    Other(String),
+}
+
+impl fmt::Display for HashError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Decode(error) => error.fmt(f),
+            Self::Other(message) => f.write_str(message),
+        }
+    }
 }
 
+impl From<DecodeError> for HashError {
+    fn from(error: DecodeError) -> Self {
+        Self::Decode(error)
+    }
+}
+
+impl From<String> for HashError {
+    fn from(message: String) -> Self {
+        Self::Other(message)
+    }
+}
+
+impl From<HashError> for String {
+    fn from(error: HashError) -> Self {
+        error.to_string()
+    }
+}
+
+/// 只计算哈希、不分组时单张图像的结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HashedImage {
+    /// 文件路径
+    pub path: String,
+    /// 哈希值
+    pub hash: String,
+    /// 原始宽度
+    pub width: u32,
+    /// 原始高度
+    pub height: u32,
+}
+
+/// 只计算哈希、不分组的扫描结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HashFolderResults {
+    /// 成功计算哈希的图像，按扫描顺序排列
+    pub images: Vec<HashedImage>,
+    /// 无法读取的目录和无法解码的文件
+    pub errors: Vec<FileError>,
+    /// 扫描耗时（毫秒）
+    pub scan_ms: u64,
+    /// 解码和计算哈希的耗时（毫秒）
+    pub hash_ms: u64,
+}
+
+/// 重复检测结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DetectionResults {
+    /// 重复图像组，按组大小降序排列
+    pub groups: Vec<DuplicateGroup>,
+    /// 无法解码或计算哈希的文件，不参与分组
+    pub errors: Vec<FileError>,
+    /// 各阶段的计数
+    #[serde(default)]
+    pub stats: DetectionCounts,
+    /// 各阶段的耗时
+    #[serde(default)]
+    pub timings: StageTimings,
+    /// 检测是否被取消，为真时结果只包含取消前已完成哈希的图像
+    #[serde(default)]
+    pub partial: bool,
+    /// 不影响检测执行的提示，例如阈值远离算法的推荐范围
+    #[serde(default)]
+    pub warnings: Vec<String>,
+}
+
+/// 检测流程各阶段处理的数量
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DetectionCounts {
+    /// 扫描到的图像数
+    pub images_scanned: usize,
+    /// 成功计算哈希的图像数
+    pub images_hashed: usize,
+    /// 计算哈希失败的图像数
+    pub images_failed: usize,
+    /// LSH生成的候选对数量
+    pub candidate_pairs: usize,
+    /// 经过预过滤后实际计算相似度的候选对数量
+    pub pairs_evaluated: usize,
+    /// 两端已在同一组中、因而跳过相似度计算的候选对数量
+    #[serde(default)]
+    pub pairs_skipped: usize,
+    /// 相似度超过阈值（并通过确认）的图像对数量
+    pub similar_pairs: usize,
+    /// 过滤后、按`max_groups`截断前的重复组总数
+    #[serde(default)]
+    pub total_groups: usize,
+}
+
+/// 检测流程各阶段的耗时（毫秒）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StageTimings {
+    /// 扫描文件夹
+    pub scan_ms: u64,
+    /// 文件大小和尺寸预过滤
+    pub prefilter_ms: u64,
+    /// 解码图像并计算哈希
+    pub hash_ms: u64,
+    /// LSH候选对生成（含候选对过滤）
+    pub candidate_ms: u64,
+    /// 相似度计算（含确认算法）
+    pub similarity_ms: u64,
+    /// 并查集分组和读取文件元数据
+    pub grouping_ms: u64,
+    /// 总耗时
+    pub total_ms: u64,
+}
+
+/// 性能基准报告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchmarkReport {
+    /// 各阶段的计数
+    pub stats: DetectionCounts,
+    /// 各阶段的耗时
+    pub timings: StageTimings,
+    /// 哈希阶段的吞吐量（张/秒）
+    pub hashing_images_per_sec: f64,
+    /// 找到的重复组数量
+    pub group_count: usize,
+}
+
+impl BenchmarkReport {
+    /// 根据检测结果生成基准报告
+    pub fn from_results(results: &DetectionResults) -> Self {
+        let processed = results.stats.images_hashed + results.stats.images_failed;
+        let hash_seconds = results.timings.hash_ms as f64 / 1000.0;
+        
+        Self {
+            stats: results.stats.clone(),
+            timings: results.timings.clone(),
+            hashing_images_per_sec: if hash_seconds > 0.0 { processed as f64 / hash_seconds } else { 0.0 },
+            group_count: results.groups.len(),
+        }
+    }
+}
+
+impl DetectionResults {
+    /// 获取结果摘要
+    pub fn summary(&self) -> ResultSummary {
+        ResultSummary {
+            group_count: self.groups.len(),
+            image_count: self.groups.iter().map(|g| g.images.len()).sum(),
+            errors: self.errors.clone(),
+            stats: self.stats.clone(),
+            timings: self.timings.clone(),
+            partial: self.partial,
+            warnings: self.warnings.clone(),
+        }
+    }
+    
+    /// 获取从`offset`开始的最多`limit`个重复组
+    pub fn page(&self, offset: usize, limit: usize) -> Vec<DuplicateGroup> {
+        self.groups.iter()
+            .skip(offset)
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+}
+
+/// 检测结果摘要，重复组本身通过分页获取
+///
+/// 除重复组外包含`DetectionResults`的全部内容，前端无需再单独请求统计和耗时
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ResultSummary {
+    /// 重复组数量
+    pub group_count: usize,
+    /// 所有重复组中的图像总数
+    pub image_count: usize,
+    /// 无法处理的文件
+    pub errors: Vec<FileError>,
+    /// 各阶段的计数
+    pub stats: DetectionCounts,
+    /// 各阶段的耗时
+    pub timings: StageTimings,
+    /// 检测是否被取消（结果不完整）
+    pub partial: bool,
+    /// 不影响检测执行的提示
+    #[serde(default)]
+    pub warnings: Vec<String>,
+}
+
+/// 相似度直方图中的一个区间
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistogramBucket {
+    /// 区间下界（包含）
+    pub min: f32,
+    /// 区间上界（不包含，最后一个区间包含100）
+    pub max: f32,
+    /// 相似度落在该区间的候选对数量
+    pub count: usize,
+}
+
+/// 根据候选对相似度分布给出的阈值建议
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThresholdSuggestion {
+    /// 建议的相似度阈值(0-100)
+    pub suggested_threshold: f32,
+    /// 参与统计的候选对数量
+    pub pair_count: usize,
+    /// 候选对相似度的直方图，按相似度升序排列
+    pub histogram: Vec<HistogramBucket>,
+}
+
+/// LSH候选对数量的统计，用于在检测前估计相似度计算的工作量
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CandidatePairEstimate {
+    /// 候选对数量（抽样时为估算值）
+    pub count: usize,
+    /// 是否为抽样估算；为false时是对全部图像实际生成候选对得到的准确值
+    pub estimated: bool,
+    /// 参与统计的图像数
+    pub sampled_images: usize,
+}
+
+/// 两个二值哈希之间差异的解释
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimilarityExplanation {
+    /// 使用的算法
+    pub algorithm: HashAlgorithm,
+    /// 第一张图像的哈希值
+    pub hash1: String,
+    /// 第二张图像的哈希值
+    pub hash2: String,
+    /// 哈希比特长度
+    pub bit_length: usize,
+    /// 不同的比特位置（从0开始）
+    pub differing_bits: Vec<usize>,
+    /// 不同比特在8x8网格中的位置(行, 列)
+    ///
+    /// 感知哈希对应DCT系数的位置（左上角为最低频），均值哈希和边缘哈希对应网格单元，
+    /// 差值哈希对应相邻像素对中左侧像素的位置
+    pub differing_cells: Vec<(usize, usize)>,
+    /// 汉明距离（不同比特的数量）
+    pub hamming_distance: usize,
+    /// 相似度(0-100)
+    pub similarity: f32,
+}
+
+/// 颜色签名：R、G、B三个通道各4个区间的直方图，每个区间的占比缩放到0-255
+///
+/// 灰度哈希无法区分结构相同但颜色不同的图像，这12个字节可以廉价地排除这类候选对。
+pub type ColorSignature = [u8; 12];
+
 /// 哈希计算结果
 #[derive(Debug, Clone)]
 pub struct HashResult {
     /// 哈希值或特征编码
     pub hash: String,
+    /// 计算哈希所用的算法，比较时据此拒绝不同算法的哈希
+    pub algorithm: HashAlgorithm,
+    /// 颜色签名，只在`HashOptions::color_signature`开启时由二值哈希算法计算
+    pub color_signature: Option<ColorSignature>,
     /// 图像宽度
     pub width: u32,
     /// 图像高度
@@ -100,8 +945,134 @@ pub struct DuplicateDetectionRequest {
     pub folder_paths: Vec<String>,
     /// 哈希算法
     pub algorithm: HashAlgorithm,
-    /// 相似度阈值(0-100)
-    pub similarity_threshold: u32,
+    /// 相似度阈值(0-100)，可以是小数，例如92.5
+    pub similarity_threshold: f32,
     /// 是否递归子文件夹
     pub recursive: bool,
+    /// 排除规则（gitignore风格的glob，相对于扫描根目录匹配）
+    #[serde(default)]
+    pub exclude_globs: Vec<String>,
+    /// 明确排除的文件路径（例如已经处理过的文件），按规范路径匹配，与`exclude_globs`互不影响
+    #[serde(default)]
+    pub exclude_paths: Vec<String>,
+    /// 是否跳过隐藏文件和目录（默认跳过）
+    #[serde(default = "default_true")]
+    pub skip_hidden: bool,
+    /// 是否跟随符号链接（默认跳过），指向同一目标的多个链接只计一次
+    #[serde(default)]
+    pub follow_symlinks: bool,
+    /// 缩放图像时使用的滤波器
+    #[serde(default)]
+    pub resize_filter: ResizeFilter,
+    /// 灰度转换方式
+    #[serde(default)]
+    pub grayscale: GrayscaleMode,
+    /// 允许解码的最大像素数，未设置时使用默认上限
+    #[serde(default)]
+    pub max_image_pixels: Option<u64>,
+    /// 最大工作线程数，未设置时使用所有CPU核心
+    #[serde(default)]
+    pub max_threads: Option<usize>,
+    /// 性能调优参数
+    #[serde(default)]
+    pub config: DetectionConfig,
+    /// 是否只查找跨文件夹的重复（例如合并两个图库时）
+    #[serde(default)]
+    pub cross_folder_only: bool,
+    /// 文件夹分组标签，与`folder_paths`一一对应，标签相同的文件夹视为同一个图库
+    #[serde(default)]
+    pub root_groups: Option<Vec<usize>>,
+    /// 是否在解码前按文件大小预过滤
+    #[serde(default)]
+    pub size_prefilter: bool,
+    /// 感知哈希前的高斯模糊σ，用于抑制JPEG压缩噪声，未设置时不模糊
+    #[serde(default)]
+    pub preprocess_blur: Option<f64>,
+    /// 是否在均值和差值哈希前做亮度/对比度归一化，提高对曝光调整的鲁棒性
+    #[serde(default)]
+    pub normalize: bool,
+    /// 是否在计算哈希或特征前做直方图均衡化，提高对对比度差异（如“自动增强”导出）的鲁棒性
+    #[serde(default)]
+    pub equalize_histogram: bool,
+    /// ORB特征提取参数
+    #[serde(default)]
+    pub orb_params: OrbParams,
+    /// 可释放空间小于该值（字节）的重复组不返回
+    #[serde(default)]
+    pub min_wasted_bytes: u64,
+    /// 是否只返回包含不同文件名的重复组（同名文件通常是预期中的备份）
+    #[serde(default)]
+    pub only_different_names: bool,
+    /// 宽高比相差超过该倍数的候选对直接判为不重复，例如1.2允许相差20%，未设置时不过滤
+    #[serde(default)]
+    pub max_aspect_ratio_factor: Option<f32>,
+    /// 长边小于该像素数的图像（图标、精灵图等）在哈希前跳过，未设置时不过滤
+    #[serde(default)]
+    pub min_dimension: Option<u32>,
+    /// 用于确认候选对的第二种算法，两种算法都超过阈值的图像才会被分组
+    #[serde(default)]
+    pub confirm_with: Option<HashAlgorithm>,
+    /// 确认算法的相似度阈值(0-100)，未设置时使用该算法的默认阈值
+    #[serde(default)]
+    pub confirm_threshold: Option<f32>,
+    /// 是否为结果中的每张图像生成Base64缩略图，需要同时设置`thumbnail_size`
+    #[serde(default)]
+    pub include_thumbnails: bool,
+    /// 缩略图的最大边长（像素）
+    #[serde(default)]
+    pub thumbnail_size: Option<u32>,
+    /// 所选算法的专用参数，变体必须与`algorithm`一致
+    #[serde(default)]
+    pub algorithm_params: Option<AlgorithmParams>,
+    /// 是否启用颜色预过滤，排除结构相似但颜色明显不同的候选对（只对二值哈希算法生效）
+    #[serde(default)]
+    pub use_color_prefilter: bool,
+    /// 精确哈希得到的重复组在返回前逐字节比较文件内容，只保留完全相同的文件
+    #[serde(default)]
+    pub verify_bytes: bool,
+    /// 精确哈希之后再按64KB分块哈希比较文件，相同块比例(0-100)不低于该值的文件也归为一组，未设置时不比较
+    #[serde(default)]
+    pub block_match_threshold: Option<f32>,
+    /// 最多返回的重复组数量，按组大小和可释放空间排序后截断，统计中仍报告真实的组数
+    #[serde(default)]
+    pub max_groups: Option<usize>,
+}
+
+impl DuplicateDetectionRequest {
+    /// 检查请求参数是否有效
+    pub fn validate(&self) -> Result<(), String> {
+        validate_threshold(self.similarity_threshold)?;
+        if let Some(block_threshold) = self.block_match_threshold {
+            if !(0.0..=100.0).contains(&block_threshold) {
+                return Err(format!("分块匹配阈值必须在0到100之间: {}", block_threshold));
+            }
+        }
+        if let Some(params) = &self.algorithm_params {
+            if params.algorithm() != self.algorithm {
+                return Err(format!("算法参数属于{}，与所选算法{}不一致", params.algorithm(), self.algorithm));
+            }
+        }
+        let min_corner_score = self.orb_params.min_corner_score;
+        if !(0.0..=255.0).contains(&min_corner_score) {
+            return Err(format!("最小角点得分必须在0到255之间: {}", min_corner_score));
+        }
+        if let Some(AlgorithmParams::ORB(orb_params)) = &self.algorithm_params {
+            if !(0.0..=255.0).contains(&orb_params.min_corner_score) {
+                return Err(format!("最小角点得分必须在0到255之间: {}", orb_params.min_corner_score));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 检查相似度阈值是否在0到100之间，所有接收阈值的命令共用同一个错误信息
+pub fn validate_threshold(threshold: f32) -> Result<(), String> {
+    if !(0.0..=100.0).contains(&threshold) {
+        return Err(format!("相似度阈值必须在0到100之间: {}", threshold));
+    }
+    Ok(())
+}
+
+fn default_true() -> bool {
+    true
 }
\ No newline at end of file
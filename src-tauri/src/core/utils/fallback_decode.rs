@@ -0,0 +1,332 @@
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+use image::{DynamicImage, GrayImage, RgbImage, RgbaImage};
+use tiff::decoder::{Decoder, DecodingResult};
+use tiff::ColorType;
+
+/// `image`无法解码时可以尝试的备用格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FallbackFormat {
+    Tiff,
+    Bmp,
+}
+
+impl FallbackFormat {
+    /// 格式名称，用于错误信息
+    pub fn name(self) -> &'static str {
+        match self {
+            FallbackFormat::Tiff => "TIFF",
+            FallbackFormat::Bmp => "BMP",
+        }
+    }
+}
+
+/// 根据文件头判断文件是否为可以使用备用解码器的格式
+pub fn detect_fallback_format(path: &Path) -> Option<FallbackFormat> {
+    let mut head = [0u8; 4];
+    File::open(path).ok()?.read_exact(&mut head).ok()?;
+
+    match head {
+        // 经典TIFF和BigTIFF，小端与大端
+        [b'I', b'I', 42 | 43, 0] | [b'M', b'M', 0, 42 | 43] => Some(FallbackFormat::Tiff),
+        [b'B', b'M', _, _] => Some(FallbackFormat::Bmp),
+        _ => None,
+    }
+}
+
+/// 使用备用解码器解码图像，像素数超过`max_pixels`时拒绝解码
+pub fn decode_with_fallback(path: &Path, format: FallbackFormat, max_pixels: u64) -> Result<DynamicImage, String> {
+    match format {
+        FallbackFormat::Tiff => decode_tiff_first_page(path, max_pixels),
+        FallbackFormat::Bmp => decode_bmp_tolerant(path, max_pixels),
+    }
+}
+
+fn check_pixels(width: u32, height: u32, max_pixels: u64) -> Result<(), String> {
+    let pixels = width as u64 * height as u64;
+    if pixels > max_pixels {
+        return Err(format!("{}x{} ({} 像素) 超过上限 {} 像素", width, height, pixels, max_pixels));
+    }
+    if pixels == 0 {
+        return Err("图片尺寸为0".to_string());
+    }
+    Ok(())
+}
+
+/// 将浮点通道值(0-1)转换为8位
+fn unit_to_u8(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// 使用`tiff`库直接解码TIFF文件的第一页
+///
+/// `image`只支持8/16位的灰度、RGB和RGBA，以及8位CMYK。这里额外支持1/2/4位灰度、
+/// 32/64位整数和浮点样本、16位CMYK、带透明通道的CMYK、多波段以及按平面存储的样本。
+/// 多页TIFF只读取第一页。调色板和YCbCr等颜色类型仍然不支持，返回`tiff`库的具体错误。
+pub fn decode_tiff_first_page(path: &Path, max_pixels: u64) -> Result<DynamicImage, String> {
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    let mut decoder = Decoder::new(BufReader::new(file)).map_err(|e| e.to_string())?;
+
+    let (width, height) = decoder.dimensions().map_err(|e| e.to_string())?;
+    check_pixels(width, height, max_pixels)?;
+
+    let color_type = decoder.colortype().map_err(|e| e.to_string())?;
+    let (bits, samples) = match color_type {
+        ColorType::Gray(bits) => (bits, 1),
+        ColorType::GrayA(bits) => (bits, 2),
+        ColorType::RGB(bits) => (bits, 3),
+        ColorType::RGBA(bits) | ColorType::CMYK(bits) => (bits, 4),
+        ColorType::CMYKA(bits) => (bits, 5),
+        ColorType::Multiband { bit_depth, num_samples } => (bit_depth, num_samples as usize),
+        other => return Err(format!("不支持的TIFF颜色类型 {:?}", other)),
+    };
+
+    let mut data = DecodingResult::U8(Vec::new());
+    let layout = decoder.read_image_to_buffer(&mut data).map_err(|e| e.to_string())?;
+    let planes = layout.planes.max(1);
+    if planes > 1 && data.as_buffer(0).byte_len() < layout.complete_len {
+        return Err("按平面存储的TIFF无法一次读取全部平面".to_string());
+    }
+
+    // 每个平面包含的样本数：按像素交错存储时为全部样本，按平面存储时为1
+    let plane_samples = if planes > 1 { 1 } else { samples };
+    let values = normalize_samples(&data, bits, width as usize, height as usize, plane_samples, planes)?;
+
+    let pixel_count = width as usize * height as usize;
+    // 按平面存储时第`s`个样本位于第`s`个平面
+    let sample = |pixel: usize, s: usize| -> f32 {
+        if planes > 1 {
+            values[s * pixel_count + pixel]
+        } else {
+            values[pixel * samples + s]
+        }
+    };
+
+    let img = match color_type {
+        ColorType::Gray(_) | ColorType::Multiband { num_samples: 1 | 2, .. } => {
+            DynamicImage::ImageLuma8(GrayImage::from_fn(width, height, |x, y| {
+                let i = y as usize * width as usize + x as usize;
+                image::Luma([unit_to_u8(sample(i, 0))])
+            }))
+        },
+        ColorType::GrayA(_) => {
+            DynamicImage::ImageRgba8(RgbaImage::from_fn(width, height, |x, y| {
+                let i = y as usize * width as usize + x as usize;
+                let v = unit_to_u8(sample(i, 0));
+                image::Rgba([v, v, v, unit_to_u8(sample(i, 1))])
+            }))
+        },
+        ColorType::RGBA(_) => {
+            DynamicImage::ImageRgba8(RgbaImage::from_fn(width, height, |x, y| {
+                let i = y as usize * width as usize + x as usize;
+                image::Rgba([0, 1, 2, 3].map(|s| unit_to_u8(sample(i, s))))
+            }))
+        },
+        ColorType::CMYK(_) | ColorType::CMYKA(_) => {
+            DynamicImage::ImageRgb8(RgbImage::from_fn(width, height, |x, y| {
+                let i = y as usize * width as usize + x as usize;
+                let k = 1.0 - sample(i, 3);
+                image::Rgb([0, 1, 2].map(|s| unit_to_u8((1.0 - sample(i, s)) * k)))
+            }))
+        },
+        // RGB以及三个以上波段的多波段图像，多余的波段被忽略
+        _ => {
+            DynamicImage::ImageRgb8(RgbImage::from_fn(width, height, |x, y| {
+                let i = y as usize * width as usize + x as usize;
+                image::Rgb([0, 1, 2].map(|s| unit_to_u8(sample(i, s))))
+            }))
+        },
+    };
+
+    Ok(img)
+}
+
+/// 将解码结果转换为0-1范围内的浮点样本，按`planes`个平面依次排列
+///
+/// 不足8位的样本在每行内紧密排列、行末按字节对齐，这里逐行展开。
+fn normalize_samples(
+    data: &DecodingResult,
+    bits: u8,
+    width: usize,
+    height: usize,
+    plane_samples: usize,
+    planes: usize
+) -> Result<Vec<f32>, String> {
+    let total = width * height * plane_samples * planes;
+    let uint_max = if bits >= 64 { u64::MAX as f64 } else { ((1u64 << bits) - 1) as f64 };
+
+    let values: Vec<f32> = match data {
+        DecodingResult::U8(v) if bits < 8 => {
+            let row_samples = width * plane_samples;
+            let row_bytes = (row_samples * bits as usize).div_ceil(8);
+            let mask = (1u16 << bits) - 1;
+            let mut out = Vec::with_capacity(total);
+            for row in v.chunks(row_bytes).take(height * planes) {
+                for s in 0..row_samples {
+                    let bit = s * bits as usize;
+                    let byte = row.get(bit / 8).copied().unwrap_or(0) as u16;
+                    let shift = 8 - bits as usize - bit % 8;
+                    out.push(((byte >> shift) & mask) as f32 / mask as f32);
+                }
+            }
+            out
+        },
+        DecodingResult::U8(v) => v.iter().map(|&x| (x as f64 / uint_max) as f32).collect(),
+        DecodingResult::U16(v) => v.iter().map(|&x| (x as f64 / uint_max) as f32).collect(),
+        DecodingResult::U32(v) => v.iter().map(|&x| (x as f64 / uint_max) as f32).collect(),
+        DecodingResult::U64(v) => v.iter().map(|&x| (x as f64 / uint_max) as f32).collect(),
+        DecodingResult::F16(v) => v.iter().map(|&x| f32::from(x)).collect(),
+        DecodingResult::F32(v) => v.clone(),
+        DecodingResult::F64(v) => v.iter().map(|&x| x as f32).collect(),
+        // 有符号样本把取值范围线性映射到0-1
+        DecodingResult::I8(v) => v.iter().map(|&x| (x as f32 - i8::MIN as f32) / 255.0).collect(),
+        DecodingResult::I16(v) => v.iter().map(|&x| (x as f32 - i16::MIN as f32) / 65535.0).collect(),
+        DecodingResult::I32(v) => v.iter().map(|&x| ((x as f64 - i32::MIN as f64) / u32::MAX as f64) as f32).collect(),
+        DecodingResult::I64(v) => v.iter().map(|&x| ((x as f64 - i64::MIN as f64) / u64::MAX as f64) as f32).collect(),
+    };
+
+    if values.len() < total {
+        return Err(format!("TIFF数据不完整: 需要 {} 个样本，实际 {} 个", total, values.len()));
+    }
+    Ok(values)
+}
+
+/// BMP文件头（BITMAPFILEHEADER）的长度
+const BMP_FILE_HEADER_SIZE: usize = 14;
+
+/// 容错地解码未压缩的BMP文件
+///
+/// `image`对BMP头部的校验很严格，一些程序写出的文件会被拒绝，例如：位平面数不为1、
+/// 调色板颜色数超出位深允许的范围、非标准长度的信息头（如OS/2 2.x的64字节头）、
+/// 带透明掩码的BI_ALPHABITFIELDS，以及64位BMP。这里忽略这些字段的不一致，
+/// 只支持未压缩（BI_RGB）和位域（BI_BITFIELDS/BI_ALPHABITFIELDS）两种存储方式，RLE等压缩格式返回错误。
+pub fn decode_bmp_tolerant(path: &Path, max_pixels: u64) -> Result<DynamicImage, String> {
+    let data = std::fs::read(path).map_err(|e| e.to_string())?;
+    let u16_at = |offset: usize| -> Result<u16, String> {
+        data.get(offset..offset + 2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]))
+            .ok_or_else(|| "BMP文件头不完整".to_string())
+    };
+    let u32_at = |offset: usize| -> Result<u32, String> {
+        data.get(offset..offset + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .ok_or_else(|| "BMP文件头不完整".to_string())
+    };
+
+    let pixel_offset = u32_at(10)? as usize;
+    let header_size = u32_at(BMP_FILE_HEADER_SIZE)? as usize;
+    if header_size < 40 {
+        return Err(format!("不支持的BMP信息头长度 {}", header_size));
+    }
+
+    let raw_width = u32_at(18)? as i32;
+    let raw_height = u32_at(22)? as i32;
+    if raw_width <= 0 || raw_height == 0 || raw_height == i32::MIN {
+        return Err(format!("无效的BMP尺寸 {}x{}", raw_width, raw_height));
+    }
+    let width = raw_width as u32;
+    let height = raw_height.unsigned_abs();
+    let top_down = raw_height < 0;
+    check_pixels(width, height, max_pixels)?;
+
+    let bit_count = u16_at(28)?;
+    let compression = u32_at(30)?;
+    let colors_used = u32_at(46)? as usize;
+
+    // 位域掩码：信息头至少52字节时位于信息头内，否则紧跟在40字节的信息头之后
+    let masks = match compression {
+        0 => None,
+        3 | 6 => {
+            let base = BMP_FILE_HEADER_SIZE + 40;
+            let alpha = if compression == 6 || header_size >= 56 { u32_at(base + 12)? } else { 0 };
+            Some([u32_at(base)?, u32_at(base + 4)?, u32_at(base + 8)?, alpha])
+        },
+        other => return Err(format!("不支持的BMP压缩方式 {}", other)),
+    };
+
+    let palette: Vec<[u8; 3]> = if bit_count <= 8 {
+        let start = BMP_FILE_HEADER_SIZE + header_size;
+        let max_colors = 1usize << bit_count;
+        let available = pixel_offset.saturating_sub(start) / 4;
+        let count = if colors_used == 0 { max_colors } else { colors_used.min(max_colors) }.min(available);
+        (0..count)
+            .filter_map(|i| data.get(start + i * 4..start + i * 4 + 3))
+            .map(|b| [b[2], b[1], b[0]])
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    let row_bytes = (width as usize * bit_count as usize).div_ceil(32) * 4;
+    let needed = row_bytes * height as usize;
+    let pixels = data.get(pixel_offset..pixel_offset + needed)
+        .ok_or_else(|| format!("BMP像素数据不完整: 需要 {} 字节", needed))?;
+
+    let mut img = RgbaImage::new(width, height);
+    for y in 0..height {
+        let row_index = if top_down { y } else { height - 1 - y } as usize;
+        let row = &pixels[row_index * row_bytes..(row_index + 1) * row_bytes];
+        for x in 0..width as usize {
+            let pixel = match (bit_count, masks) {
+                (1 | 2 | 4 | 8, None) => {
+                    let bits = bit_count as usize;
+                    let bit = x * bits;
+                    let index = (row[bit / 8] >> (8 - bits - bit % 8)) & ((1u16 << bits) - 1) as u8;
+                    // 索引超出调色板时按黑色处理
+                    let [r, g, b] = palette.get(index as usize).copied().unwrap_or([0, 0, 0]);
+                    [r, g, b, 255]
+                },
+                (16, None) => {
+                    let v = u16::from_le_bytes([row[x * 2], row[x * 2 + 1]]) as u32;
+                    let [r, g, b, _] = apply_masks(v, [0x7C00, 0x03E0, 0x001F, 0]);
+                    [r, g, b, 255]
+                },
+                (24, None) => [row[x * 3 + 2], row[x * 3 + 1], row[x * 3], 255],
+                (32, None) => [row[x * 4 + 2], row[x * 4 + 1], row[x * 4], 255],
+                (64, None) => {
+                    // 每通道16位定点数(s2.13)，线性光，顺序为BGRA
+                    let channel = |i: usize| {
+                        let v = i16::from_le_bytes([row[x * 8 + i * 2], row[x * 8 + i * 2 + 1]]) as f32 / 8192.0;
+                        v.clamp(0.0, 1.0)
+                    };
+                    let srgb = |v: f32| unit_to_u8(v.powf(1.0 / 2.2));
+                    [srgb(channel(2)), srgb(channel(1)), srgb(channel(0)), unit_to_u8(channel(3))]
+                },
+                (16, Some(masks)) => {
+                    let v = u16::from_le_bytes([row[x * 2], row[x * 2 + 1]]) as u32;
+                    apply_masks(v, masks)
+                },
+                (32, Some(masks)) => {
+                    let v = u32::from_le_bytes([row[x * 4], row[x * 4 + 1], row[x * 4 + 2], row[x * 4 + 3]]);
+                    apply_masks(v, masks)
+                },
+                (bits, _) => return Err(format!("不支持的BMP位深 {}", bits)),
+            };
+            img.put_pixel(x as u32, y, image::Rgba(pixel));
+        }
+    }
+
+    // 没有透明掩码的图像丢弃透明通道
+    let has_alpha = bit_count == 64 || masks.is_some_and(|m| m[3] != 0);
+    if has_alpha {
+        Ok(DynamicImage::ImageRgba8(img))
+    } else {
+        Ok(DynamicImage::ImageRgb8(DynamicImage::ImageRgba8(img).to_rgb8()))
+    }
+}
+
+/// 按RGBA位域掩码提取各通道并缩放到8位，掩码为0的通道为255（透明通道）或0
+fn apply_masks(value: u32, masks: [u32; 4]) -> [u8; 4] {
+    let mut out = [0u8; 4];
+    for (i, &mask) in masks.iter().enumerate() {
+        if mask == 0 {
+            out[i] = if i == 3 { 255 } else { 0 };
+            continue;
+        }
+        let shift = mask.trailing_zeros();
+        let max = (mask >> shift) as f32;
+        out[i] = ((((value & mask) >> shift) as f32 / max) * 255.0).round() as u8;
+    }
+    out
+}
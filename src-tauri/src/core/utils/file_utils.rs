@@ -1,6 +1,11 @@
 use std::path::{Path, PathBuf};
+use std::collections::HashSet;
 use std::fs;
-use walkdir::WalkDir;
+use std::time::{Duration, SystemTime};
+use chrono::{DateTime, SecondsFormat, Utc};
+use walkdir::{DirEntry, WalkDir};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use crate::core::types::FileError;
 
 /// 支持的图像格式后缀名
 pub const SUPPORTED_IMAGE_EXTENSIONS: [&str; 7] = [
@@ -17,8 +22,170 @@ pub fn is_image_file(path: &Path) -> bool {
     false
 }
 
+/// 支持的视频格式后缀名，仅在启用`video`功能时参与扫描
+pub const SUPPORTED_VIDEO_EXTENSIONS: [&str; 3] = [
+    "mp4", "mov", "m4v"
+];
+
+/// 检查文件是否是支持的视频文件，未启用`video`功能时总是返回false
+pub fn is_video_file(path: &Path) -> bool {
+    if !cfg!(feature = "video") {
+        return false;
+    }
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| SUPPORTED_VIDEO_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+/// 检查文件是否应被扫描：支持的图像，以及启用`video`功能时的视频
+pub fn is_media_file(path: &Path) -> bool {
+    is_image_file(path) || is_video_file(path)
+}
+
+/// 图像扫描选项
+#[derive(Debug, Clone, Default)]
+pub struct ScanOptions {
+    /// 是否递归子文件夹
+    pub recursive: bool,
+    /// 排除规则，匹配相对于扫描根目录的路径
+    pub exclude: Option<GlobSet>,
+    /// 明确排除的文件的规范路径，由合并多个根目录的扫描结果时剔除
+    pub excluded_files: HashSet<PathBuf>,
+    /// 是否跳过隐藏文件和目录
+    pub skip_hidden: bool,
+    /// 是否跟随符号链接（默认跳过）
+    ///
+    /// 跟随时由WalkDir检测符号链接循环，同一目标文件按规范路径去重只收集一次。
+    pub follow_symlinks: bool,
+}
+
+impl ScanOptions {
+    /// 检查路径是否应被跳过（命中排除规则或为隐藏项）
+    pub fn is_excluded(&self, root: &Path, path: &Path) -> bool {
+        let relative = path.strip_prefix(root).unwrap_or(path);
+        // 扫描根目录本身永远不跳过
+        if relative.as_os_str().is_empty() {
+            return false;
+        }
+
+        if self.skip_hidden && is_hidden(path) {
+            return true;
+        }
+
+        match &self.exclude {
+            Some(set) => set.is_match(relative),
+            None => false,
+        }
+    }
+}
+
+/// 检查文件或目录是否为隐藏项
+///
+/// 以`.`开头的名称视为隐藏；在Windows上还会检查隐藏文件属性。
+/// 只检查路径的最后一个部分，递归遍历时父目录已在上层被跳过。
+pub fn is_hidden(path: &Path) -> bool {
+    let dot_hidden = path.file_name()
+        .and_then(|name| name.to_str())
+        .map(|name| name.starts_with('.'))
+        .unwrap_or(false);
+
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::MetadataExt;
+        const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+
+        if !dot_hidden {
+            return fs::symlink_metadata(path)
+                .map(|m| m.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0)
+                .unwrap_or(false);
+        }
+    }
+
+    dot_hidden
+}
+
+/// 将gitignore风格的glob模式编译为GlobSet，没有模式时返回None
+pub fn build_glob_set(patterns: &[String]) -> Result<Option<GlobSet>, String> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = Glob::new(pattern)
+            .map_err(|e| format!("无效的排除规则 {}: {}", pattern, e))?;
+        builder.add(glob);
+    }
+
+    builder.build()
+        .map(Some)
+        .map_err(|e| format!("编译排除规则失败: {}", e))
+}
+
+/// 将可能包含glob通配符的文件夹路径展开为具体的目录
+///
+/// 例如`/photos/202*/raw`展开为所有匹配的年份目录，结果按路径排序，只保留目录。
+/// 不含通配符或本身就是存在的路径时原样返回；通配符没有匹配任何目录时返回错误。
+pub fn expand_folder_glob(pattern: &str) -> Result<Vec<PathBuf>, String> {
+    let literal = PathBuf::from(pattern);
+    if !pattern.contains(['*', '?', '[']) || literal.exists() {
+        return Ok(vec![literal]);
+    }
+
+    let mut folders: Vec<PathBuf> = glob::glob(pattern)
+        .map_err(|e| format!("无效的文件夹通配符 {}: {}", pattern, e))?
+        .filter_map(|entry| entry.ok())
+        .filter(|path| path.is_dir())
+        .collect();
+
+    if folders.is_empty() {
+        return Err(format!("通配符没有匹配任何文件夹: {}", pattern));
+    }
+    folders.sort();
+    Ok(folders)
+}
+
 /// 获取目录中的所有图像文件路径
-pub fn get_image_paths(dir_path: &Path, recursive: bool) -> Result<Vec<PathBuf>, String> {
+pub fn get_image_paths(dir_path: &Path, options: &ScanOptions) -> Result<Vec<PathBuf>, String> {
+    get_image_paths_with_progress(dir_path, options, &|_| {}, &|_| {})
+}
+
+/// 逐个回调目录中的图像文件路径，不在内存中保留完整的路径列表
+///
+/// 遍历到图像时立即调用`on_image`，调用方可以边扫描边处理（例如送入哈希计算），
+/// 扫描超大目录树时内存占用不随文件数增长（跟随符号链接时用于去重的规范路径集合除外）。
+pub fn get_image_paths_streaming(
+    dir_path: &Path,
+    options: &ScanOptions,
+    on_image: &mut dyn FnMut(PathBuf)
+) -> Result<(), String> {
+    visit_image_paths(dir_path, options, &|_| {}, &|_| {}, on_image)
+}
+
+/// 获取目录中的所有图像文件路径，每遍历到一个文件就调用一次`on_file`
+///
+/// `on_file`的参数表示该文件是否为（将被收集的）图像，用于在遍历大目录树时报告进度。
+/// 无法读取的子目录（例如没有权限）不会中断扫描，而是通过`on_error`报告后跳过。
+pub fn get_image_paths_with_progress(
+    dir_path: &Path,
+    options: &ScanOptions,
+    on_file: &(dyn Fn(bool) + Sync),
+    on_error: &(dyn Fn(FileError) + Sync)
+) -> Result<Vec<PathBuf>, String> {
+    let mut image_paths = Vec::new();
+    visit_image_paths(dir_path, options, on_file, on_error, &mut |path| image_paths.push(path))?;
+    Ok(image_paths)
+}
+
+/// 遍历目录，对每个图像文件调用`on_image`，是各种获取图像路径函数的共同实现
+fn visit_image_paths(
+    dir_path: &Path,
+    options: &ScanOptions,
+    on_file: &(dyn Fn(bool) + Sync),
+    on_error: &(dyn Fn(FileError) + Sync),
+    on_image: &mut dyn FnMut(PathBuf)
+) -> Result<(), String> {
     if !dir_path.exists() {
         return Err(format!("目录不存在: {}", dir_path.display()));
     }
@@ -27,46 +194,103 @@ pub fn get_image_paths(dir_path: &Path, recursive: bool) -> Result<Vec<PathBuf>,
         return Err(format!("路径不是一个目录: {}", dir_path.display()));
     }
     
-    let mut image_paths = Vec::new();
+    // 跟随符号链接时，多个链接可能指向同一文件，按规范路径去重
+    let mut seen = HashSet::new();
+    let mut collect = |path: &Path| {
+        if !path.is_file() {
+            return;
+        }
+        
+        // 空文件同样收集，解码时作为损坏文件记入错误列表，而不是悄悄跳过
+        let is_image = is_media_file(path);
+        if is_image && options.follow_symlinks {
+            let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+            if !seen.insert(canonical.clone()) {
+                return;
+            }
+            on_file(true);
+            // 指向文件的符号链接记录为其目标路径，后续读取元数据时不会被当作链接拒绝
+            on_image(if path.is_symlink() { canonical } else { path.to_path_buf() });
+            return;
+        }
+        
+        on_file(is_image);
+        if is_image {
+            on_image(path.to_path_buf());
+        }
+    };
     
     // 根据是否递归使用不同的方式遍历
-    if recursive {
-        // 递归遍历所有子目录
+    if options.recursive {
+        // 递归遍历所有子目录，被排除的目录整体跳过
+        // 跟随符号链接时WalkDir会检测循环并为其返回错误，与其他遍历错误一样报告后跳过
         for entry in WalkDir::new(dir_path)
+            .follow_links(options.follow_symlinks)
             .into_iter()
-            .filter_map(|e| e.ok())
+            .filter_entry(|e: &DirEntry| !options.is_excluded(dir_path, e.path()))
         {
-            let path = entry.path();
-            // 跳过符号链接
-            if path.is_symlink() {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    on_error(FileError {
+                        path: e.path().unwrap_or(dir_path).to_string_lossy().into_owned(),
+                        reason: format!("无法读取: {}", e),
+                        decode_error: None,
+                    });
+                    continue;
+                }
+            };
+            
+            // 不跟随时跳过符号链接
+            if entry.path_is_symlink() && !options.follow_symlinks {
                 continue;
             }
             
-            if path.is_file() && is_image_file(path) {
-                image_paths.push(path.to_path_buf());
-            }
+            collect(entry.path());
         }
     } else {
         // 只遍历当前目录
-        if let Ok(entries) = fs::read_dir(dir_path) {
-            for entry in entries.filter_map(|e| e.ok()) {
-                let path = entry.path();
-                // 跳过符号链接
-                if path.is_symlink() {
+        let entries = match fs::read_dir(dir_path) {
+            Ok(entries) => entries,
+            Err(e) => {
+                on_error(FileError {
+                    path: dir_path.to_string_lossy().into_owned(),
+                    reason: format!("无法读取目录: {}", e),
+                    decode_error: None,
+                });
+                return Ok(());
+            }
+        };
+        for entry in entries {
+            let path = match entry {
+                Ok(entry) => entry.path(),
+                Err(e) => {
+                    on_error(FileError {
+                        path: dir_path.to_string_lossy().into_owned(),
+                        reason: format!("无法读取目录项: {}", e),
+                        decode_error: None,
+                    });
                     continue;
                 }
-                
-                if path.is_file() && is_image_file(&path) {
-                    image_paths.push(path);
-                }
+            };
+            
+            // 不跟随时跳过符号链接
+            if (path.is_symlink() && !options.follow_symlinks) || options.is_excluded(dir_path, &path) {
+                continue;
             }
+            
+            collect(&path);
         }
     }
     
-    Ok(image_paths)
+    Ok(())
 }
 
 /// 获取文件的元数据信息
+///
+/// 返回(文件大小, 创建时间, 修改时间)。时间为UTC的RFC 3339字符串，
+/// 例如`2024-05-01T08:30:00Z`，可直接被JavaScript的`Date`解析且按字典序即时间序；
+/// 平台无法提供对应时间时返回空字符串（早期版本返回Unix秒数字符串，缺失时为"0"）。
 pub fn get_file_metadata(path: &Path) -> Result<(u64, String, String), String> {
     // 检查是否是符号链接
     if path.is_symlink() {
@@ -78,19 +302,64 @@ pub fn get_file_metadata(path: &Path) -> Result<(u64, String, String), String> {
     
     let size_bytes = metadata.len();
     
-    // 创建时间
+    // 创建时间，平台不支持时回退到ctime
     let created_at = metadata.created()
         .ok()
-        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
-        .map(|d| d.as_secs().to_string())
-        .unwrap_or_else(|| "0".to_string());
+        .or_else(|| ctime_fallback(&metadata))
+        .map(format_timestamp)
+        .unwrap_or_default();
     
     // 修改时间
     let modified_at = metadata.modified()
-        .ok()
-        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
-        .map(|d| d.as_secs().to_string())
-        .unwrap_or_else(|| "0".to_string());
+        .map(format_timestamp)
+        .unwrap_or_default();
     
     Ok((size_bytes, created_at, modified_at))
-}
\ No newline at end of file
+}
+
+/// 创建时间不可用时的回退值
+///
+/// Linux上很多文件系统不提供创建时间，此时使用inode状态变更时间(ctime)，
+/// 对于未被修改过元数据的文件，它通常就是文件写入磁盘的时间。
+#[cfg(unix)]
+fn ctime_fallback(metadata: &fs::Metadata) -> Option<SystemTime> {
+    use std::os::unix::fs::MetadataExt;
+    
+    let secs = u64::try_from(metadata.ctime()).ok()?;
+    let nanos = u32::try_from(metadata.ctime_nsec()).unwrap_or(0);
+    SystemTime::UNIX_EPOCH.checked_add(Duration::new(secs, nanos))
+}
+
+/// 非Unix平台（Windows）总是提供创建时间，无需回退
+#[cfg(not(unix))]
+fn ctime_fallback(_metadata: &fs::Metadata) -> Option<SystemTime> {
+    None
+}
+
+/// 将系统时间格式化为UTC的RFC 3339字符串（精确到秒）
+pub fn format_timestamp(time: SystemTime) -> String {
+    DateTime::<Utc>::from(time).to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// 将文件移到系统回收站
+pub fn move_to_trash(path: &Path) -> Result<(), String> {
+    trash::delete(path).map_err(|e| format!("无法移到回收站: {}", e))
+}
+
+/// 将`path`替换为指向`target`的硬链接
+///
+/// 先在同一目录下以临时名称创建硬链接，再重命名覆盖原文件，
+/// 任何一步失败时原文件保持不变。两个文件必须位于同一文件系统。
+pub fn replace_with_hardlink(path: &Path, target: &Path) -> Result<(), String> {
+    let file_name = path.file_name()
+        .ok_or_else(|| format!("无效的文件路径: {}", path.display()))?;
+    let temp_path = path.with_file_name(format!(".{}.delo-link", file_name.to_string_lossy()));
+    
+    fs::hard_link(target, &temp_path)
+        .map_err(|e| format!("无法创建硬链接: {}", e))?;
+    
+    fs::rename(&temp_path, path).map_err(|e| {
+        let _ = fs::remove_file(&temp_path);
+        format!("无法替换文件: {}", e)
+    })
+}
@@ -1,3 +1,6 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
 use std::path::Path;
 use sha2::{Sha256, Digest};
 use crate::core::types::HashAlgorithm;
@@ -14,18 +17,212 @@ pub fn deserialize_from_base64(encoded: &str) -> Result<Vec<u8>, String> {
         .map_err(|e| format!("Base64解码失败: {}", e))
 }
 
+/// 将"0"/"1"组成的二值哈希转换为十六进制字符串
+///
+/// 每4位转换为一个十六进制字符，高位在前，64位哈希得到16个字符。
+/// 输入不是二值哈希或长度不是4的倍数时返回None（如精确哈希、ORB特征）。
+pub fn binary_to_hex(bits: &str) -> Option<String> {
+    if bits.is_empty() || !bits.len().is_multiple_of(4) || !bits.bytes().all(|b| b == b'0' || b == b'1') {
+        return None;
+    }
+
+    Some(bits.as_bytes()
+        .chunks(4)
+        .map(|nibble| {
+            let value = nibble.iter().fold(0u32, |acc, &b| (acc << 1) | (b - b'0') as u32);
+            std::char::from_digit(value, 16).unwrap_or('0')
+        })
+        .collect())
+}
+
+/// 将64位的"0"/"1"二值哈希打包为`u64`，第一个字符对应最高位（与`binary_to_hex`的顺序一致）
+///
+/// 长度不是64或含有其他字符时返回None（如精确哈希、ORB特征和视频关键帧哈希）。
+pub fn binary_to_u64(bits: &str) -> Option<u64> {
+    if bits.len() != 64 {
+        return None;
+    }
+
+    bits.bytes().try_fold(0u64, |acc, b| match b {
+        b'0' => Some(acc << 1),
+        b'1' => Some((acc << 1) | 1),
+        _ => None,
+    })
+}
+
+/// 将十六进制字符串还原为"0"/"1"组成的二值哈希，是`binary_to_hex`的逆变换
+pub fn hex_to_binary(hex: &str) -> Result<String, String> {
+    hex.chars()
+        .map(|c| {
+            c.to_digit(16)
+                .map(|value| format!("{:04b}", value))
+                .ok_or_else(|| format!("无效的十六进制哈希: {}", hex))
+        })
+        .collect()
+}
+
+/// 将Python `imagehash`库的十六进制哈希转换为本程序的二值哈希
+///
+/// 两者的比特顺序一致：8x8的比特按行优先展开，第一个比特对应第一个十六进制字符的最高位，
+/// 即`str(imagehash.phash(img))`与`binary_to_hex`的编码方式相同。区别在于：
+/// - 差值哈希：imagehash在右侧像素更亮时置1，本程序在左侧像素更亮时置1，因此需要逐位取反；
+/// - 均值哈希：比较方向相同，直接转换；
+/// - 感知哈希：比较方向相同，直接转换。imagehash使用未归一化的DCT，首行首列系数的相对大小不同，
+///   加上灰度转换和缩放滤波器的差异，同一张图的两种哈希通常相差几个比特，应按相似度而非相等比较。
+///
+/// 只支持默认`hash_size=8`的64位哈希，其他算法没有对应的imagehash格式。
+pub fn from_imagehash_hex(hex: &str, algorithm: HashAlgorithm) -> Result<String, String> {
+    if hex.len() != 16 {
+        return Err(format!("imagehash哈希应为16个十六进制字符（hash_size=8），实际为{}个: {}", hex.len(), hex));
+    }
+    let bits = hex_to_binary(hex)?;
+
+    match algorithm {
+        HashAlgorithm::Average | HashAlgorithm::Perceptual => Ok(bits),
+        HashAlgorithm::Difference => Ok(invert_bits(&bits)),
+        _ => Err(format!("{:?}算法没有对应的imagehash格式", algorithm)),
+    }
+}
+
+/// 将本程序的二值哈希转换为Python `imagehash`库的十六进制格式，是`from_imagehash_hex`的逆变换
+pub fn to_imagehash_hex(bits: &str, algorithm: HashAlgorithm) -> Result<String, String> {
+    let bits = match algorithm {
+        HashAlgorithm::Average | HashAlgorithm::Perceptual => bits.to_string(),
+        HashAlgorithm::Difference => invert_bits(bits),
+        _ => return Err(format!("{:?}算法没有对应的imagehash格式", algorithm)),
+    };
+
+    binary_to_hex(&bits).ok_or_else(|| format!("不是有效的二值哈希: {}", bits))
+}
+
+/// 将二值哈希逐位取反
+fn invert_bits(bits: &str) -> String {
+    bits.chars()
+        .map(|c| match c {
+            '0' => '1',
+            '1' => '0',
+            other => other,
+        })
+        .collect()
+}
+
 /// 计算文件的SHA-256哈希值
+///
+/// 按`DEFAULT_FILE_BLOCK_SIZE`分块读取，内存占用与文件大小无关。
 pub fn compute_file_sha256(path: &Path) -> Result<String, String> {
-    let data = std::fs::read(path)
+    let mut file = File::open(path)
         .map_err(|e| format!("读取文件失败: {}", e))?;
     
     let mut hasher = Sha256::new();
-    hasher.update(&data);
+    let mut buffer = vec![0u8; DEFAULT_FILE_BLOCK_SIZE];
+    loop {
+        let filled = fill_buffer(&mut file, &mut buffer)?;
+        hasher.update(&buffer[..filled]);
+        if filled < buffer.len() {
+            break;
+        }
+    }
     let result = hasher.finalize();
     
     Ok(format!("{:x}", result))
 }
 
+/// 逐块比较两个文件的内容是否完全相同
+///
+/// 每次只读取两个文件的一块，遇到第一个不同的块即返回，内存占用与文件大小无关。
+pub fn files_identical(path1: &Path, path2: &Path) -> Result<bool, String> {
+    let mut file1 = File::open(path1)
+        .map_err(|e| format!("读取文件失败: {}", e))?;
+    let mut file2 = File::open(path2)
+        .map_err(|e| format!("读取文件失败: {}", e))?;
+    
+    let mut buffer1 = vec![0u8; DEFAULT_FILE_BLOCK_SIZE];
+    let mut buffer2 = vec![0u8; DEFAULT_FILE_BLOCK_SIZE];
+    loop {
+        let filled1 = fill_buffer(&mut file1, &mut buffer1)?;
+        let filled2 = fill_buffer(&mut file2, &mut buffer2)?;
+        if buffer1[..filled1] != buffer2[..filled2] {
+            return Ok(false);
+        }
+        if filled1 < buffer1.len() {
+            return Ok(true);
+        }
+    }
+}
+
+/// 从文件中读取数据直到填满缓冲区，文件结束时返回不足缓冲区长度的字节数
+fn fill_buffer(file: &mut File, buffer: &mut [u8]) -> Result<usize, String> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match file.read(&mut buffer[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(format!("读取文件失败: {}", e)),
+        }
+    }
+    Ok(filled)
+}
+
+/// 分块哈希的默认块大小（64KB）
+pub const DEFAULT_FILE_BLOCK_SIZE: usize = 64 * 1024;
+
+/// 将文件按固定大小分块，计算每块的FNV-1a哈希值
+///
+/// 用于“模糊精确”比较：只改写了文件头或文件尾（如重新写入标签）的副本，
+/// 其余块的哈希保持不变。最后一块可能不足`block_size`。
+pub fn compute_file_block_hashes(path: &Path, block_size: usize) -> Result<Vec<u64>, String> {
+    let mut file = File::open(path)
+        .map_err(|e| format!("读取文件失败: {}", e))?;
+    
+    let mut buffer = vec![0u8; block_size.max(1)];
+    let mut hashes = Vec::new();
+    
+    loop {
+        // 填满一整块，文件结束时得到不完整的最后一块
+        let filled = fill_buffer(&mut file, &mut buffer)?;
+        
+        if filled == 0 {
+            break;
+        }
+        hashes.push(fnv1a_64(&buffer[..filled]));
+        if filled < buffer.len() {
+            break;
+        }
+    }
+    
+    Ok(hashes)
+}
+
+/// 比较两个文件的分块哈希，返回相同块所占的比例(0-100)
+///
+/// 按多重集合计算交集，与块的位置无关，因此在末尾追加数据或改写了若干块的副本仍能匹配；
+/// 但插入或删除的字节数不是块大小的整数倍时，之后的所有块都会错位，相似度会很低。
+/// 分母取块数较多的一方，两个空文件视为相同。
+pub fn block_hash_similarity(blocks1: &[u64], blocks2: &[u64]) -> f32 {
+    let total = blocks1.len().max(blocks2.len());
+    if total == 0 {
+        return 100.0;
+    }
+    
+    let mut counts: HashMap<u64, usize> = HashMap::with_capacity(blocks1.len());
+    for &block in blocks1 {
+        *counts.entry(block).or_insert(0) += 1;
+    }
+    
+    let shared = blocks2.iter()
+        .filter(|block| match counts.get_mut(block) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                true
+            },
+            _ => false,
+        })
+        .count();
+    
+    100.0 * shared as f32 / total as f32
+}
+
 /// 计算二进制数据的SHA-256哈希值
 pub fn compute_data_sha256(data: &[u8]) -> String {
     let mut hasher = Sha256::new();
@@ -83,4 +280,88 @@ pub fn split_hash_for_lsh(hash: &str, num_bands: usize) -> Vec<String> {
         })
         .filter(|s| !s.is_empty())
         .collect()
-}
\ No newline at end of file
+}
+
+/// 计算一组二值特征向量的SimHash签名(64位)
+///
+/// 每个特征的第j个比特为签名的第`j % 64`位投一票（1为+1，0为-1），
+/// 票数为正的位在签名中置1。包含大量相似特征的两组特征会得到汉明距离很小的签名，
+/// 因此可以直接用于二值哈希的LSH分段和汉明距离比较。
+pub fn simhash<T: AsRef<[u8]>>(features: &[T]) -> u64 {
+    let mut votes = [0i64; 64];
+    
+    for feature in features {
+        for (byte_idx, &byte) in feature.as_ref().iter().enumerate() {
+            for bit in 0..8 {
+                let position = (byte_idx * 8 + bit) % 64;
+                if byte & (1 << bit) != 0 {
+                    votes[position] += 1;
+                } else {
+                    votes[position] -= 1;
+                }
+            }
+        }
+    }
+    
+    votes.iter()
+        .enumerate()
+        .filter(|(_, &vote)| vote > 0)
+        .fold(0u64, |signature, (i, _)| signature | (1 << i))
+}
+
+
+/// 计算字节串的FNV-1a哈希值(64位)
+///
+/// 结果与平台和编译器版本无关，适合需要持久化或跨构建比较的签名
+pub fn fnv1a_64(data: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+    
+    data.iter().fold(OFFSET_BASIS, |hash, &byte| (hash ^ byte as u64).wrapping_mul(PRIME))
+}
+
+/// SplitMix64混合函数，将一个64位值打散为另一个伪随机的64位值
+pub fn splitmix64(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+/// 计算一组片段(shingle)的MinHash签名
+///
+/// 使用`num_permutations`个独立的哈希函数模拟随机排列，签名的每一位是该哈希函数下
+/// 所有片段的最小哈希值。两个集合签名中相同位置相等的比例是其Jaccard相似度的无偏估计，
+/// 签名也可以直接分段用于LSH。空集合的签名全部为`u64::MAX`。
+pub fn minhash<T: AsRef<[u8]>>(shingles: &[T], num_permutations: usize) -> Vec<u64> {
+    let base_hashes: Vec<u64> = shingles.iter()
+        .map(|shingle| fnv1a_64(shingle.as_ref()))
+        .collect();
+    
+    (0..num_permutations as u64)
+        .map(|seed| {
+            let salt = splitmix64(seed);
+            base_hashes.iter()
+                .map(|&hash| splitmix64(hash ^ salt))
+                .min()
+                .unwrap_or(u64::MAX)
+        })
+        .collect()
+}
+
+/// 比较两个MinHash签名，返回估计的Jaccard相似度(0-100)
+///
+/// 签名长度不同时只比较共同的前缀部分
+pub fn minhash_similarity(signature1: &[u64], signature2: &[u64]) -> f32 {
+    let len = signature1.len().min(signature2.len());
+    if len == 0 {
+        return 0.0;
+    }
+    
+    let equal = signature1.iter()
+        .zip(signature2.iter())
+        .filter(|(a, b)| a == b)
+        .count();
+    
+    100.0 * equal as f32 / len as f32
+}
@@ -1,22 +1,269 @@
+use std::fs::File;
+use std::io::{BufRead, Read, Seek, SeekFrom};
 use std::path::Path;
-use image::{DynamicImage, GenericImageView, imageops::FilterType, GrayImage};
+use image::{DynamicImage, GenericImageView, imageops::FilterType, GrayImage, ImageError, ImageFormat, ImageReader, Limits};
+use crate::core::types::{ColorSignature, DecodeError, GrayscaleMode};
+use crate::core::utils::{fallback_decode, hash_utils, math_utils};
+
+/// 默认允许解码的最大像素数（约1.5亿像素）
+///
+/// 超过该值的图像会在解码前被拒绝，防止恶意构造的“解压炸弹”文件耗尽内存。
+pub const DEFAULT_MAX_IMAGE_PIXELS: u64 = 150_000_000;
+
+/// 哈希计算时解码后图像的默认最大边长
+pub const DEFAULT_MAX_DECODE_DIMENSION: u32 = 2048;
 
 /// 打开图像文件
-pub fn open_image(path: &Path) -> Result<DynamicImage, String> {
-    image::open(path)
-        .map_err(|e| format!("无法打开图片 {}: {}", path.display(), e))
+pub fn open_image(path: &Path) -> Result<DynamicImage, DecodeError> {
+    open_image_with_limit(path, DEFAULT_MAX_IMAGE_PIXELS)
+}
+
+/// 打开图像文件，像素数超过`max_pixels`时拒绝解码
+///
+/// 先检查文件是否被截断，再只读取文件头获取尺寸进行检查，最后带着内存限制进行完整解码。
+/// `image`无法解析的TIFF和BMP文件会再交给`fallback_decode`中的备用解码器，
+/// 两者都失败时错误信息中同时包含两个解码器给出的原因，分类沿用`image`给出的分类。
+pub fn open_image_with_limit(path: &Path, max_pixels: u64) -> Result<DynamicImage, DecodeError> {
+    check_complete(path)?;
+    
+    // 读不出尺寸的文件仍然交给备用解码器，由它自己检查像素数
+    if let Ok((width, height)) = read_dimensions(path) {
+        check_pixel_limit(&path.display().to_string(), width, height, max_pixels)?;
+    }
+    
+    decode_with_limit(path, max_pixels).or_else(|error| {
+        let Some(format) = fallback_decode::detect_fallback_format(path) else {
+            return Err(error);
+        };
+        fallback_decode::decode_with_fallback(path, format, max_pixels)
+            .map_err(|fallback_error| error.with_detail(&format!("；备用{}解码器也无法解码: {}", format.name(), fallback_error)))
+    })
+}
+
+/// 使用`image`带着内存限制解码图像文件
+fn decode_with_limit(path: &Path, max_pixels: u64) -> Result<DynamicImage, DecodeError> {
+    let source = path.display().to_string();
+    let reader = ImageReader::open(path)
+        .and_then(|reader| reader.with_guessed_format())
+        .map_err(|e| io_decode_error(&source, e))?;
+    
+    decode_reader(reader, &source, max_pixels)
+}
+
+/// 带着内存限制解码，失败时按`image`给出的错误类型分类
+///
+/// `source`是错误中报告的图像来源，文件为其路径。
+fn decode_reader<R: BufRead + Seek>(mut reader: ImageReader<R>, source: &str, max_pixels: u64) -> Result<DynamicImage, DecodeError> {
+    // 每像素最多按16字节（RGBA 32位浮点）估算解码所需内存
+    let mut limits = Limits::default();
+    limits.max_alloc = Some(max_pixels.saturating_mul(16));
+    reader.limits(limits);
+    
+    reader.decode().map_err(|e| image_decode_error(source, e))
+}
+
+/// 像素数超过`max_pixels`时返回`TooLarge`错误
+fn check_pixel_limit(source: &str, width: u32, height: u32, max_pixels: u64) -> Result<(), DecodeError> {
+    let pixels = width as u64 * height as u64;
+    if pixels > max_pixels {
+        return Err(DecodeError::TooLarge {
+            path: source.to_string(),
+            message: format!("图片尺寸过大 {}: {}x{} ({} 像素) 超过上限 {} 像素",
+                             source, width, height, pixels, max_pixels),
+        });
+    }
+    Ok(())
+}
+
+/// 按`image`的错误类型对解码失败进行分类
+fn image_decode_error(source: &str, error: ImageError) -> DecodeError {
+    let message = format!("无法打开图片 {}: {}", source, error);
+    let path = source.to_string();
+    match error {
+        ImageError::IoError(e) => io_decode_error(source, e),
+        ImageError::Unsupported(_) => DecodeError::Unsupported { path, message },
+        ImageError::Limits(_) => DecodeError::TooLarge { path, message },
+        ImageError::Decoding(_) | ImageError::Parameter(_) | ImageError::Encoding(_) => {
+            DecodeError::Corrupt { path, message }
+        },
+    }
+}
+
+/// 按IO错误的类型对打开文件失败进行分类，不存在和无权限之外的IO错误视为文件损坏
+fn io_decode_error(source: &str, error: std::io::Error) -> DecodeError {
+    let message = format!("无法打开图片 {}: {}", source, error);
+    let path = source.to_string();
+    match error.kind() {
+        std::io::ErrorKind::NotFound => DecodeError::NotFound { path, message },
+        std::io::ErrorKind::PermissionDenied => DecodeError::PermissionDenied { path, message },
+        _ => DecodeError::Corrupt { path, message },
+    }
+}
+
+/// 从内存中的字节解码图像，像素数超过`DEFAULT_MAX_IMAGE_PIXELS`时拒绝解码
+///
+/// 用于压缩包、远程来源等不在磁盘上的图像；格式根据文件头猜测。
+/// 与按路径解码共用像素数限制和错误分类，错误中的路径为`<内存中的图像>`。
+pub fn open_image_from_bytes(data: &[u8]) -> Result<DynamicImage, DecodeError> {
+    let source = "<内存中的图像>";
+    let reader = || {
+        ImageReader::new(std::io::Cursor::new(data))
+            .with_guessed_format()
+            .map_err(|e| io_decode_error(source, e))
+    };
+    
+    let (width, height) = reader()?
+        .into_dimensions()
+        .map_err(|e| image_decode_error(source, e))?;
+    check_pixel_limit(source, width, height, DEFAULT_MAX_IMAGE_PIXELS)?;
+    
+    decode_reader(reader()?, source, DEFAULT_MAX_IMAGE_PIXELS)
+}
+
+/// 检查图像文件是否完整
+///
+/// 未下载完成的JPEG/PNG文件通常仍能解码出一张残缺的图像（缺失部分填充为灰色），
+/// 因此通过查找文件末尾的结束标记（JPEG的EOI、PNG的IEND块）来识别被截断的文件。
+/// 其他格式不做检查。
+pub fn check_complete(path: &Path) -> Result<(), DecodeError> {
+    // 结束标记之后可能还附带少量数据，只在文件末尾的一段范围内查找
+    const TAIL_LEN: u64 = 1024;
+    
+    let open_error = |e: std::io::Error| io_decode_error(&path.display().to_string(), e);
+    let corrupt = |message: String| DecodeError::Corrupt { path: path.display().to_string(), message };
+    
+    let mut file = File::open(path).map_err(open_error)?;
+    let len = file.metadata().map_err(open_error)?.len();
+    if len == 0 {
+        return Err(corrupt(format!("图片文件为空: {}", path.display())));
+    }
+    
+    let mut head = [0u8; 16];
+    let head_len = file.read(&mut head).map_err(open_error)?;
+    
+    let end_marker: &[u8] = match image::guess_format(&head[..head_len]) {
+        Ok(ImageFormat::Jpeg) => &[0xFF, 0xD9],
+        Ok(ImageFormat::Png) => b"IEND",
+        _ => return Ok(()),
+    };
+    
+    let mut tail = Vec::new();
+    file.seek(SeekFrom::Start(len.saturating_sub(TAIL_LEN)))
+        .and_then(|_| file.read_to_end(&mut tail))
+        .map_err(open_error)?;
+    
+    if tail.windows(end_marker.len()).any(|window| window == end_marker) {
+        Ok(())
+    } else {
+        Err(corrupt(format!("图片文件不完整（可能未下载完成）: {}", path.display())))
+    }
+}
+
+/// 只读取文件头获取图像尺寸，不解码像素数据
+pub fn read_dimensions(path: &Path) -> Result<(u32, u32), String> {
+    ImageReader::open(path)
+        .and_then(|reader| reader.with_guessed_format())
+        .map_err(|e| format!("无法打开图片 {}: {}", path.display(), e))?
+        .into_dimensions()
+        .map_err(|e| format!("无法读取图片尺寸 {}: {}", path.display(), e))
+}
+
+/// 打开图像文件并在解码后立即缩小，使最长边不超过`max_dimension`
+///
+/// 哈希算法只需要很小的图像，提前缩小可以尽早释放全尺寸的解码结果。
+/// 返回缩小后的图像以及原始宽高。
+pub fn open_image_downscaled(
+    path: &Path,
+    max_pixels: u64,
+    max_dimension: u32
+) -> Result<(DynamicImage, u32, u32), DecodeError> {
+    let img = open_image_with_limit(path, max_pixels)?;
+    let (width, height) = img.dimensions();
+    
+    if width.max(height) <= max_dimension {
+        return Ok((img, width, height));
+    }
+    
+    // thumbnail使用快速的整数缩放算法，保持宽高比
+    Ok((img.thumbnail(max_dimension, max_dimension), width, height))
+}
+
+/// 将已解码的图像缩小，使最长边不超过`max_dimension`，与`open_image_downscaled`的缩放方式相同
+pub fn downscale(img: &DynamicImage, max_dimension: u32) -> DynamicImage {
+    let (width, height) = img.dimensions();
+    if width.max(height) <= max_dimension {
+        img.clone()
+    } else {
+        img.thumbnail(max_dimension, max_dimension)
+    }
+}
+
+/// 生成图像缩略图，返回Base64编码的PNG
+///
+/// 缩略图保持宽高比，最长边不超过`max_size`。
+pub fn generate_thumbnail(path: &Path, max_size: u32) -> Result<String, String> {
+    let (img, _, _) = open_image_downscaled(path, DEFAULT_MAX_IMAGE_PIXELS, max_size.max(1))?;
+
+    let mut png = Vec::new();
+    img.write_to(&mut std::io::Cursor::new(&mut png), ImageFormat::Png)
+        .map_err(|e| format!("无法编码缩略图 {}: {}", path.display(), e))?;
+
+    Ok(hash_utils::serialize_to_base64(&png))
 }
 
 /// 将图像调整为指定大小
 pub fn resize_image(img: &DynamicImage, width: u32, height: u32) -> DynamicImage {
-    img.resize_exact(width, height, FilterType::Lanczos3)
+    resize_image_with(img, width, height, FilterType::Lanczos3)
+}
+
+/// 使用指定的滤波器将图像调整为指定大小
+pub fn resize_image_with(img: &DynamicImage, width: u32, height: u32, filter: FilterType) -> DynamicImage {
+    img.resize_exact(width, height, filter)
 }
 
 /// 将图像转换为灰度图
+///
+/// 直接对gamma编码的sRGB值加权，速度快；对照片的整体结构足够准确。
 pub fn to_grayscale(img: &DynamicImage) -> GrayImage {
     img.to_luma8()
 }
 
+/// 在线性光空间中将图像转换为灰度图
+///
+/// 先把sRGB值解码为线性光强度，按Rec. 709系数计算亮度后再编码回sRGB。
+/// 与`to_grayscale`相比，饱和色（尤其是红色和蓝色）不会被估计得过暗，
+/// 对仅调整了色彩的副本得到的哈希更稳定，但速度较慢。
+pub fn to_grayscale_linear(img: &DynamicImage) -> GrayImage {
+    let to_linear: Vec<f64> = (0..=255u8)
+        .map(|v| {
+            let c = v as f64 / 255.0;
+            if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+        })
+        .collect();
+
+    let rgb = img.to_rgb8();
+    GrayImage::from_fn(rgb.width(), rgb.height(), |x, y| {
+        let [r, g, b] = rgb.get_pixel(x, y).0;
+        let luminance = 0.2126 * to_linear[r as usize]
+            + 0.7152 * to_linear[g as usize]
+            + 0.0722 * to_linear[b as usize];
+        let encoded = if luminance <= 0.0031308 {
+            luminance * 12.92
+        } else {
+            1.055 * luminance.powf(1.0 / 2.4) - 0.055
+        };
+        image::Luma([(encoded * 255.0).round().clamp(0.0, 255.0) as u8])
+    })
+}
+
+/// 按指定方式将图像转换为灰度图
+pub fn to_grayscale_with(img: &DynamicImage, mode: GrayscaleMode) -> GrayImage {
+    match mode {
+        GrayscaleMode::Gamma => to_grayscale(img),
+        GrayscaleMode::Linear => to_grayscale_linear(img),
+    }
+}
+
 /// 计算灰度图像的平均像素值
 pub fn average_pixel_value(img: &GrayImage) -> u8 {
     let sum: u32 = img.pixels().map(|p| p[0] as u32).sum();
@@ -52,17 +299,19 @@ pub fn bits_to_bytes(bits: &str) -> Vec<u8> {
 pub type ImageMatrix = Vec<Vec<f64>>;
 
 /// 将灰度图像转换为浮点数矩阵
+///
+/// 与`matrix_to_gray_image`一样按行顺序处理：哈希计算已经在图像之间并行，
+/// 这里再启动rayon任务只会增加调度开销，还会在解码线程池中嵌套并行。
 pub fn gray_image_to_matrix(img: &GrayImage) -> ImageMatrix {
-    let (width, height) = img.dimensions();
-    let mut matrix = vec![vec![0.0f64; width as usize]; height as usize];
-    
-    for y in 0..height {
-        for x in 0..width {
-            matrix[y as usize][x as usize] = img.get_pixel(x, y)[0] as f64;
-        }
+    let width = img.width() as usize;
+    if width == 0 {
+        return vec![Vec::new(); img.height() as usize];
     }
     
-    matrix
+    img.as_raw()
+        .chunks(width)
+        .map(|row| row.iter().map(|&p| p as f64).collect())
+        .collect()
 }
 
 /// 将浮点数矩阵转换为灰度图像
@@ -71,11 +320,13 @@ pub fn matrix_to_gray_image(matrix: &ImageMatrix) -> GrayImage {
     let width = matrix[0].len();
     
     let mut img = GrayImage::new(width as u32, height as u32);
+    if width == 0 {
+        return img;
+    }
     
-    for y in 0..height {
-        for x in 0..width {
-            let value = matrix[y][x].round().clamp(0.0, 255.0) as u8;
-            img.put_pixel(x as u32, y as u32, image::Luma([value]));
+    for (row, values) in img.chunks_mut(width).zip(matrix) {
+        for (pixel, &value) in row.iter_mut().zip(values) {
+            *pixel = value.round().clamp(0.0, 255.0) as u8;
         }
     }
     
@@ -93,4 +344,318 @@ pub fn threshold_matrix(matrix: &ImageMatrix, threshold: f64) -> String {
     }
     
     bits
-}
\ No newline at end of file
+}
+/// 对灰度图像进行高斯模糊
+///
+/// 使用可分离卷积：先水平方向、再垂直方向各做一次一维卷积，
+/// 复杂度为O(n·k)而不是完整二维卷积的O(n·k²)。边界像素按边缘值延伸。
+pub fn gaussian_blur(img: &GrayImage, sigma: f64) -> GrayImage {
+    let (width, height) = img.dimensions();
+    if sigma <= 0.0 || width == 0 || height == 0 {
+        return img.clone();
+    }
+    
+    let kernel = math_utils::gaussian_kernel_1d(sigma);
+    let radius = (kernel.len() / 2) as i64;
+    
+    // 水平方向卷积，中间结果保留浮点精度
+    let mut horizontal = vec![vec![0.0f64; width as usize]; height as usize];
+    for y in 0..height {
+        for x in 0..width {
+            horizontal[y as usize][x as usize] = kernel.iter()
+                .enumerate()
+                .map(|(k, weight)| {
+                    let sx = (x as i64 + k as i64 - radius).clamp(0, width as i64 - 1);
+                    weight * img.get_pixel(sx as u32, y)[0] as f64
+                })
+                .sum();
+        }
+    }
+    
+    // 垂直方向卷积
+    let mut blurred = vec![vec![0.0f64; width as usize]; height as usize];
+    for (y, row) in blurred.iter_mut().enumerate() {
+        for (x, value) in row.iter_mut().enumerate() {
+            *value = kernel.iter()
+                .enumerate()
+                .map(|(k, weight)| {
+                    let sy = (y as i64 + k as i64 - radius).clamp(0, height as i64 - 1);
+                    weight * horizontal[sy as usize][x]
+                })
+                .sum();
+        }
+    }
+    
+    matrix_to_gray_image(&blurred)
+}
+
+/// 按需对图像做高斯模糊预处理，用于在计算哈希前抑制JPEG压缩噪声
+///
+/// `sigma`为None时原样返回图像；否则按`mode`转换为灰度并返回模糊后的灰度图像。
+pub fn preprocess_blur(img: DynamicImage, sigma: Option<f64>, mode: GrayscaleMode) -> DynamicImage {
+    match sigma {
+        Some(sigma) if sigma > 0.0 => DynamicImage::ImageLuma8(gaussian_blur(&to_grayscale_with(&img, mode), sigma)),
+        _ => img,
+    }
+}
+
+/// 归一化时两端各裁掉的像素比例，避免个别极亮或极暗的像素决定拉伸范围
+const NORMALIZE_CLIP_RATIO: f64 = 0.01;
+
+/// 对灰度图做亮度/对比度归一化（直方图拉伸）
+///
+/// 将第1和第99百分位的亮度线性拉伸到0-255，超出部分截断。
+/// 只在整体曝光或对比度上不同的两个副本因此得到相近的灰度分布；
+/// 亮度几乎一致的图像（例如纯色图）原样返回。
+pub fn normalize_brightness(img: &GrayImage) -> GrayImage {
+    let mut histogram = [0usize; 256];
+    for pixel in img.pixels() {
+        histogram[pixel[0] as usize] += 1;
+    }
+
+    let clip = (img.pixels().len() as f64 * NORMALIZE_CLIP_RATIO) as usize;
+    let low = clipped_level(&histogram, clip, 0..256).unwrap_or(0);
+    let high = clipped_level(&histogram, clip, (0..256).rev()).unwrap_or(255);
+
+    if high <= low {
+        return img.clone();
+    }
+
+    let scale = 255.0 / (high - low) as f64;
+    let mut normalized = img.clone();
+    for pixel in normalized.pixels_mut() {
+        let value = (pixel[0] as f64 - low as f64) * scale;
+        pixel[0] = value.round().clamp(0.0, 255.0) as u8;
+    }
+    normalized
+}
+
+/// 按`levels`的顺序累计直方图，返回跳过`clip`个像素后遇到的第一个亮度级
+fn clipped_level(histogram: &[usize; 256], clip: usize, levels: impl Iterator<Item = usize>) -> Option<usize> {
+    let mut remaining = clip;
+    for level in levels {
+        if histogram[level] > remaining {
+            return Some(level);
+        }
+        remaining -= histogram[level];
+    }
+    None
+}
+
+/// 按需对图像做亮度归一化预处理
+///
+/// `normalize`为false时原样返回图像；否则在原始分辨率下按`mode`转换为灰度并归一化，
+/// 在缩小到哈希尺寸之前拉伸，可以保留暗部或低对比度图像中的细微差异。
+pub fn preprocess_normalize(img: DynamicImage, normalize: bool, mode: GrayscaleMode) -> DynamicImage {
+    if normalize {
+        DynamicImage::ImageLuma8(normalize_brightness(&to_grayscale_with(&img, mode)))
+    } else {
+        img
+    }
+}
+
+/// 对灰度图做直方图均衡化
+///
+/// 用累计分布函数把亮度重新映射，使输出的直方图尽量均匀。
+/// 比`normalize_brightness`的线性拉伸更强，能抵消非线性的对比度调整（例如“自动增强”）。
+pub fn equalize_histogram(img: &GrayImage) -> GrayImage {
+    let mut histogram = [0usize; 256];
+    for pixel in img.pixels() {
+        histogram[pixel[0] as usize] += 1;
+    }
+
+    let total = img.pixels().len();
+    // 第一个非空亮度级的累计值，映射到0
+    let cdf_min = histogram.iter().copied().find(|&count| count > 0).unwrap_or(0);
+    if total <= cdf_min {
+        // 纯色图像无法均衡化
+        return img.clone();
+    }
+
+    let mut lut = [0u8; 256];
+    let mut cdf = 0;
+    for (level, &count) in histogram.iter().enumerate() {
+        cdf += count;
+        let value = (cdf.saturating_sub(cdf_min)) as f64 * 255.0 / (total - cdf_min) as f64;
+        lut[level] = value.round() as u8;
+    }
+
+    let mut equalized = img.clone();
+    for pixel in equalized.pixels_mut() {
+        pixel[0] = lut[pixel[0] as usize];
+    }
+    equalized
+}
+
+/// 按需对图像做直方图均衡化预处理
+///
+/// `equalize`为false时原样返回图像；否则按`mode`转换为灰度并均衡化。
+pub fn preprocess_equalize(img: DynamicImage, equalize: bool, mode: GrayscaleMode) -> DynamicImage {
+    if equalize {
+        DynamicImage::ImageLuma8(equalize_histogram(&to_grayscale_with(&img, mode)))
+    } else {
+        img
+    }
+}
+
+/// 使用Sobel算子计算图像矩阵的梯度幅值
+///
+/// 边界像素没有完整的3x3邻域，不参与计算，因此返回的矩阵在每个方向上比输入小2。
+pub fn sobel_magnitude(matrix: &ImageMatrix) -> ImageMatrix {
+    let height = matrix.len();
+    let width = matrix.first().map_or(0, |row| row.len());
+    if height < 3 || width < 3 {
+        return Vec::new();
+    }
+    
+    let mut magnitude = vec![vec![0.0f64; width - 2]; height - 2];
+    
+    for (y, row) in magnitude.iter_mut().enumerate() {
+        for (x, value) in row.iter_mut().enumerate() {
+            // 以(x+1, y+1)为中心的3x3邻域
+            let p = |dx: usize, dy: usize| matrix[y + dy][x + dx];
+            
+            let gx = (p(2, 0) + 2.0 * p(2, 1) + p(2, 2)) - (p(0, 0) + 2.0 * p(0, 1) + p(0, 2));
+            let gy = (p(0, 2) + 2.0 * p(1, 2) + p(2, 2)) - (p(0, 0) + 2.0 * p(1, 0) + p(2, 0));
+            
+            *value = (gx * gx + gy * gy).sqrt();
+        }
+    }
+    
+    magnitude
+}
+
+/// 积分图类型 - 每个元素为其左上方（不含所在行列）所有像素值之和
+pub type IntegralImage = Vec<Vec<u64>>;
+
+/// 计算灰度图像的积分图
+///
+/// 返回的积分图比图像多一行一列：`integral[y][x]`为区域`[0, x) × [0, y)`内的像素和，
+/// 配合`box_sum`可以在O(1)时间内求任意矩形区域的像素和。
+pub fn integral_image(img: &GrayImage) -> IntegralImage {
+    let (width, height) = img.dimensions();
+    let mut integral = vec![vec![0u64; width as usize + 1]; height as usize + 1];
+    
+    for y in 0..height as usize {
+        let mut row_sum = 0u64;
+        for x in 0..width as usize {
+            row_sum += img.get_pixel(x as u32, y as u32)[0] as u64;
+            integral[y + 1][x + 1] = integral[y][x + 1] + row_sum;
+        }
+    }
+    
+    integral
+}
+
+/// 使用积分图计算矩形区域`[x0, x1) × [y0, y1)`内的像素和
+///
+/// 坐标超出图像范围时会被截断到图像边界，空区域返回0。
+pub fn box_sum(integral: &IntegralImage, x0: usize, y0: usize, x1: usize, y1: usize) -> u64 {
+    let max_y = integral.len().saturating_sub(1);
+    let max_x = integral.first().map_or(0, |row| row.len().saturating_sub(1));
+    
+    let (x1, y1) = (x1.min(max_x), y1.min(max_y));
+    if x0 >= x1 || y0 >= y1 {
+        return 0;
+    }
+    
+    integral[y1][x1] + integral[y0][x0] - integral[y0][x1] - integral[y1][x0]
+}
+
+/// 计算图像的颜色签名
+///
+/// 先缩小到16x16，再统计每个通道落入4个等宽亮度区间的像素比例。
+pub fn color_signature(img: &DynamicImage) -> ColorSignature {
+    const SIDE: u32 = 16;
+    let small = img.resize_exact(SIDE, SIDE, FilterType::Triangle).to_rgb8();
+    
+    let mut counts = [0u32; 12];
+    for pixel in small.pixels() {
+        for (channel, &value) in pixel.0.iter().enumerate() {
+            counts[channel * 4 + (value >> 6) as usize] += 1;
+        }
+    }
+    
+    let total = SIDE * SIDE;
+    counts.map(|count| (count * 255 / total) as u8)
+}
+
+/// 计算两个颜色签名的距离(0-1)
+///
+/// 三个通道直方图L1距离的平均值除以最大可能值，0表示颜色分布相同，1表示完全不重叠。
+pub fn color_signature_distance(a: &ColorSignature, b: &ColorSignature) -> f32 {
+    let l1: u32 = a.iter()
+        .zip(b.iter())
+        .map(|(&x, &y)| x.abs_diff(y) as u32)
+        .sum();
+    
+    l1 as f32 / (3.0 * 2.0 * 255.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// 直接累加矩形区域`[x0, x1) × [y0, y1)`内的像素，作为`box_sum`的对照
+    fn naive_sum(img: &GrayImage, x0: usize, y0: usize, x1: usize, y1: usize) -> u64 {
+        let x1 = x1.min(img.width() as usize);
+        let y1 = y1.min(img.height() as usize);
+        (y0..y1)
+            .flat_map(|y| (x0..x1).map(move |x| (x, y)))
+            .map(|(x, y)| img.get_pixel(x as u32, y as u32)[0] as u64)
+            .sum()
+    }
+    
+    #[test]
+    fn box_sum_matches_naive_sum() {
+        let mut rng = fastrand::Rng::with_seed(3);
+        // 非正方形且宽高不同，能发现行列下标写反的错误
+        let (width, height) = (37usize, 23usize);
+        let img = GrayImage::from_fn(width as u32, height as u32, |_, _| image::Luma([rng.u8(..)]));
+        let integral = integral_image(&img);
+        
+        assert_eq!(integral.len(), height + 1);
+        assert_eq!(integral[0].len(), width + 1);
+        assert_eq!(integral[height][width], naive_sum(&img, 0, 0, width, height));
+        
+        // 随机矩形，右下角可以超出图像以覆盖截断
+        for _ in 0..500 {
+            let x0 = rng.usize(..=width);
+            let y0 = rng.usize(..=height);
+            let x1 = rng.usize(x0..=width + 2);
+            let y1 = rng.usize(y0..=height + 2);
+            assert_eq!(box_sum(&integral, x0, y0, x1, y1), naive_sum(&img, x0, y0, x1, y1),
+                       "区域 [{}, {}) × [{}, {})", x0, x1, y0, y1);
+        }
+        
+        // 单独覆盖首尾行列和单个像素
+        let edges = [
+            (0, 0, width, 1),
+            (0, height - 1, width, height),
+            (0, 0, 1, height),
+            (width - 1, 0, width, height),
+            (0, 0, 1, 1),
+            (width - 1, height - 1, width, height),
+        ];
+        for (x0, y0, x1, y1) in edges {
+            assert_eq!(box_sum(&integral, x0, y0, x1, y1), naive_sum(&img, x0, y0, x1, y1),
+                       "区域 [{}, {}) × [{}, {})", x0, x1, y0, y1);
+        }
+        
+        // 空区域和完全在图像外的区域
+        assert_eq!(box_sum(&integral, 5, 5, 5, 9), 0);
+        assert_eq!(box_sum(&integral, width, 0, width + 4, height), 0);
+    }
+    
+    #[test]
+    fn in_memory_decode_errors_are_categorized() {
+        let mut png = Vec::new();
+        DynamicImage::ImageLuma8(GrayImage::new(32, 32))
+            .write_to(&mut std::io::Cursor::new(&mut png), ImageFormat::Png)
+            .unwrap();
+        assert_eq!(open_image_from_bytes(&png).unwrap().dimensions(), (32, 32));
+        
+        assert!(matches!(open_image_from_bytes(b"not an image"), Err(DecodeError::Unsupported { .. })));
+        assert!(matches!(open_image_from_bytes(&png[..png.len() / 2]), Err(DecodeError::Corrupt { .. })));
+    }
+}
@@ -54,6 +54,49 @@ pub fn dct_1d(input: &[f64]) -> Vec<f64> {
     output
 }
 
+/// 2D逆离散余弦变换(IDCT)
+/// 将`dct_2d`的结果从频率域还原为空间域
+///
+/// 只保留低频系数（其余置0）再还原，可以直观看到感知哈希实际捕获的图像内容，
+/// 结果可通过`image_utils::matrix_to_gray_image`转换为图像。
+pub fn idct_2d(matrix: &[Vec<f64>]) -> Vec<Vec<f64>> {
+    let n = matrix.len();
+
+    // 列方向IDCT
+    let mut result = vec![vec![0.0f64; n]; n];
+    for x in 0..n {
+        let col: Vec<f64> = matrix.iter().map(|row| row[x]).collect();
+        for (row, value) in result.iter_mut().zip(idct_1d(&col)) {
+            row[x] = value;
+        }
+    }
+
+    // 行方向IDCT
+    result.iter().map(|row| idct_1d(row)).collect()
+}
+
+/// 1D逆离散余弦变换(IDCT)，即`dct_1d`的逆变换
+pub fn idct_1d(input: &[f64]) -> Vec<f64> {
+    let n = input.len();
+
+    (0..n)
+        .map(|i| {
+            input.iter()
+                .enumerate()
+                .map(|(k, &coefficient)| {
+                    let alpha = if k == 0 {
+                        (1.0 / n as f64).sqrt()
+                    } else {
+                        (2.0 / n as f64).sqrt()
+                    };
+                    let angle = std::f64::consts::PI * (2 * i + 1) as f64 * k as f64 / (2 * n) as f64;
+                    alpha * coefficient * angle.cos()
+                })
+                .sum()
+        })
+        .collect()
+}
+
 /// 计算两点之间的欧几里得距离
 pub fn euclidean_distance(x1: f64, y1: f64, x2: f64, y2: f64) -> f64 {
     let dx = x2 - x1;
@@ -67,7 +110,14 @@ pub fn manhattan_distance(x1: f64, y1: f64, x2: f64, y2: f64) -> f64 {
 }
 
 /// 计算一组数据的中位数
+///
+/// 注意：会就地排序传入的切片，NaN按相等处理，位置不确定。空切片返回0.0。
+/// 不希望修改输入或数据可能含NaN时使用`median_of`。
+#[allow(dead_code)] // 内部调用方都已改用`median_of`，保留给可以接受就地排序、省去一次复制的调用方
 pub fn median(values: &mut [f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
     values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
     
     let mid = values.len() / 2;
@@ -78,6 +128,24 @@ pub fn median(values: &mut [f64]) -> f64 {
     }
 }
 
+/// 计算一组数据的中位数，不修改输入
+///
+/// NaN会被忽略；没有有效值时返回0.0。
+pub fn median_of(values: &[f64]) -> f64 {
+    let mut sorted: Vec<f64> = values.iter().copied().filter(|v| !v.is_nan()).collect();
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    sorted.sort_by(f64::total_cmp);
+    
+    let mid = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        (sorted[mid - 1] + sorted[mid]) / 2.0
+    } else {
+        sorted[mid]
+    }
+}
+
 /// 计算一组数据的均值
 pub fn mean(values: &[f64]) -> f64 {
     if values.is_empty() {
@@ -135,4 +203,38 @@ pub fn gaussian_kernel(size: usize, sigma: f64) -> Vec<Vec<f64>> {
     }
     
     kernel
-}
\ No newline at end of file
+}
+/// 生成一维高斯核，半径为3σ（向上取整），用于可分离卷积
+pub fn gaussian_kernel_1d(sigma: f64) -> Vec<f64> {
+    let radius = (3.0 * sigma).ceil().max(1.0) as usize;
+    
+    let kernel: Vec<f64> = (0..=2 * radius)
+        .map(|i| {
+            let d = i as f64 - radius as f64;
+            (-(d * d) / (2.0 * sigma * sigma)).exp()
+        })
+        .collect();
+    
+    // 归一化
+    let sum: f64 = kernel.iter().sum();
+    kernel.into_iter().map(|g| g / sum).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn median_handles_empty_input_and_sorts_in_place() {
+        assert_eq!(median(&mut []), 0.0);
+        assert_eq!(median_of(&[]), 0.0);
+        
+        let mut values = [3.0, 1.0, 4.0, 2.0];
+        assert_eq!(median_of(&values), 2.5);
+        assert_eq!(values, [3.0, 1.0, 4.0, 2.0]);
+        assert_eq!(median(&mut values), 2.5);
+        assert_eq!(values, [1.0, 2.0, 3.0, 4.0]);
+        
+        assert_eq!(median_of(&[f64::NAN, 5.0, 1.0, 3.0]), 3.0);
+    }
+}
@@ -2,6 +2,7 @@ pub mod file_utils;
 pub mod image_utils;
 pub mod math_utils;
 pub mod hash_utils;
+pub mod fallback_decode;
 
 // 重新导出常用工具函数
 pub use file_utils::*;
@@ -25,9 +26,19 @@ pub fn hamming_distance_bytes(bytes1: &[u8], bytes2: &[u8]) -> u32 {
         .sum()
 }
 
+/// 统计二进制哈希字符串中`1`的个数
+pub fn popcount(hash: &str) -> u32 {
+    hash.bytes().filter(|&b| b == b'1').count() as u32
+}
+
 /// 计算两个哈希值的相似度百分比 (0-100)
 pub fn hash_similarity(hash1: &str, hash2: &str) -> f32 {
     let distance = hamming_distance(hash1, hash2);
     let max_distance = hash1.len() as f32;
     100.0 * (1.0 - (distance as f32 / max_distance))
+}
+
+/// 计算两个打包为`u64`的64位二值哈希的相似度百分比 (0-100)，与`hash_similarity`结果一致
+pub fn packed_hash_similarity(hash1: u64, hash2: u64) -> f32 {
+    100.0 * (1.0 - ((hash1 ^ hash2).count_ones() as f32 / 64.0))
 }
\ No newline at end of file
@@ -2,12 +2,21 @@ use std::path::{Path, PathBuf};
 use std::fs;
 use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::Instant;
 use rayon::prelude::*;
-use crate::core::types::{HashAlgorithm, HashResult, DuplicateGroup, ImageInfo};
-use crate::core::utils::file_utils::{get_image_paths, get_file_metadata};
+use crate::core::types::{
+    AlgorithmParams, HashAlgorithm, HashError, HashOptions, HashResult, DuplicateGroup, ImageInfo, OrbParams, HistogramBucket, ThresholdSuggestion, CandidatePairEstimate,
+    DetectionCounts, StageTimings,
+    DuplicateDetectionRequest, ResizeFilter, GrayscaleMode, DetectionConfig, DetectionResults, FileError, HashedImage, HashFolderResults,
+};
+use crate::core::utils::file_utils::{get_image_paths_with_progress, get_file_metadata, build_glob_set, ScanOptions};
+use crate::core::utils::image_utils::{self, DEFAULT_MAX_IMAGE_PIXELS};
+use crate::core::utils::hash_utils;
+use crate::core::utils::packed_hash_similarity;
 use crate::algorithms;
-use crate::detection::lsh::{LSHIndex, compute_candidate_pairs};
+use crate::detection::lsh::{LSHIndex, compute_candidate_pairs, compute_packed_candidate_pairs};
+use crate::detection::progress::{HashProgress, NoProgress, ProgressReporter, ProgressThrottle, ScanProgress};
 
 /// 检测重复图像请求参数
 #[derive(Debug, Clone)]
@@ -20,109 +29,904 @@ pub struct DuplicateDetectionParams {
     pub threshold: f32,
     /// 是否递归子文件夹
     pub recursive: bool,
+    /// 排除规则（gitignore风格的glob）
+    pub exclude_globs: Vec<String>,
+    /// 明确排除的文件，在哈希前按规范路径从扫描结果中去掉
+    pub exclude_paths: Vec<PathBuf>,
+    /// 是否跳过隐藏文件和目录
+    pub skip_hidden: bool,
+    /// 是否跟随符号链接
+    pub follow_symlinks: bool,
+    /// 缩放图像时使用的滤波器
+    pub resize_filter: ResizeFilter,
+    /// 灰度转换方式
+    pub grayscale: GrayscaleMode,
+    /// 允许解码的最大像素数
+    pub max_image_pixels: u64,
+    /// 最大工作线程数，None表示使用全局线程池
+    pub max_threads: Option<usize>,
+    /// 性能调优参数
+    pub config: DetectionConfig,
+    /// 是否只查找跨文件夹的重复（同一文件夹分组内的图像不会配对）
+    pub cross_folder_only: bool,
+    /// 文件夹分组标签，与`folders`一一对应；None时每个文件夹自成一组
+    pub root_groups: Option<Vec<usize>>,
+    /// 是否在解码前按文件大小预过滤
+    ///
+    /// 精确匹配时只对大小相同的文件计算哈希（像素相同但编码不同的文件会被漏掉）；
+    /// 其他算法跳过大小相差悬殊的候选对。
+    pub size_prefilter: bool,
+    /// 感知哈希前的高斯模糊σ，None表示不模糊
+    pub preprocess_blur: Option<f64>,
+    /// 均值和差值哈希前是否做亮度/对比度归一化
+    pub normalize: bool,
+    /// 计算哈希或特征前是否做直方图均衡化
+    pub equalize_histogram: bool,
+    /// ORB特征提取参数
+    pub orb_params: OrbParams,
+    /// 可释放空间小于该值（字节）的重复组不返回，在分组完成后过滤
+    pub min_wasted_bytes: u64,
+    /// 是否只返回包含至少两个不同文件名的重复组，在分组完成后过滤
+    pub only_different_names: bool,
+    /// 宽高比相差超过该倍数的候选对不计算相似度，None表示不过滤
+    ///
+    /// 缩放到正方形网格的哈希（均值、差值、感知哈希）会丢失宽高比信息，
+    /// 这个过滤可以廉价地排除形状完全不同却碰巧哈希相近的图像。
+    pub max_aspect_ratio_factor: Option<f32>,
+    /// 长边小于该像素数的图像在哈希前跳过，None表示不过滤
+    ///
+    /// 只读取文件头获取尺寸，不解码图像；无法读取尺寸的文件保留，由哈希阶段报告错误。
+    pub min_dimension: Option<u32>,
+    /// 用于确认的第二种算法，None表示只使用主算法
+    ///
+    /// 主算法的LSH和相似度计算照常生成候选对，之后只为通过的候选对中的图像计算第二种哈希，
+    /// 两种算法都超过阈值的图像对才会被分组。适合用快速的差值哈希配合较慢的感知哈希或精确哈希。
+    pub confirm_with: Option<HashAlgorithm>,
+    /// 确认算法的相似度阈值，None表示使用该算法的默认阈值
+    pub confirm_threshold: Option<f32>,
+    /// 是否在分组时为每张图像生成缩略图
+    pub include_thumbnails: bool,
+    /// 缩略图的最大边长，None时即使`include_thumbnails`为true也不生成
+    pub thumbnail_size: Option<u32>,
+    /// 是否启用颜色预过滤（只对均值、差值、感知和边缘哈希生效）
+    ///
+    /// 计算哈希时同时计算颜色签名，颜色分布相差超过`COLOR_PREFILTER_MAX_DISTANCE`的候选对
+    /// 在比较哈希前被排除，避免结构相同但颜色不同的图像被灰度哈希判为重复。
+    pub use_color_prefilter: bool,
+    /// 是否对精确哈希（主算法或确认算法）得到的组逐字节比较文件内容
+    ///
+    /// 精确哈希比较的是解码后的像素，像素相同但编码或元数据不同的文件也会分到一组；
+    /// 开启后组按文件内容拆分，只保留字节完全相同的文件，为删除操作提供绝对的确定性。
+    pub verify_bytes: bool,
+    /// 精确哈希之后按文件分块哈希补充分组的相同块比例阈值(0-100)，None表示不比较
+    ///
+    /// 只改写了文件头或文件尾（如重新写入标签或少量像素）的副本精确哈希不同；
+    /// 开启后相同块所占比例不低于该值的文件也合并到同一组（见`hash_utils::block_hash_similarity`）。
+    /// 只对精确哈希生效，需要完整读取每个成功解码的文件。
+    pub block_match_threshold: Option<f32>,
+    /// 最多返回的重复组数量，`None`表示不限制
+    ///
+    /// 在全部分组和过滤完成后才截断，`DetectionCounts::total_groups`记录截断前的组数。
+    pub max_groups: Option<usize>,
+}
+
+impl DuplicateDetectionParams {
+    /// 使用默认的扫描和调优选项创建检测参数
+    pub fn new(folders: Vec<PathBuf>, algorithm: HashAlgorithm, threshold: f32, recursive: bool) -> Self {
+        Self {
+            folders,
+            algorithm,
+            threshold,
+            recursive,
+            exclude_globs: Vec::new(),
+            exclude_paths: Vec::new(),
+            skip_hidden: true,
+            follow_symlinks: false,
+            resize_filter: ResizeFilter::default(),
+            grayscale: GrayscaleMode::default(),
+            max_image_pixels: DEFAULT_MAX_IMAGE_PIXELS,
+            max_threads: None,
+            config: DetectionConfig::default(),
+            cross_folder_only: false,
+            root_groups: None,
+            size_prefilter: false,
+            preprocess_blur: None,
+            normalize: false,
+            equalize_histogram: false,
+            orb_params: OrbParams::default(),
+            min_wasted_bytes: 0,
+            only_different_names: false,
+            max_aspect_ratio_factor: None,
+            min_dimension: None,
+            confirm_with: None,
+            confirm_threshold: None,
+            include_thumbnails: false,
+            thumbnail_size: None,
+            use_color_prefilter: false,
+            verify_bytes: false,
+            block_match_threshold: None,
+            max_groups: None,
+        }
+    }
+    
+    /// 将每张图像的根目录索引映射为文件夹分组标签
+    fn image_root_groups(&self, root_indices: &[usize]) -> Vec<usize> {
+        match &self.root_groups {
+            // 未指定分组标签的文件夹各自成组，使用不会与用户标签冲突的值
+            Some(groups) => root_indices.iter()
+                .map(|&idx| groups.get(idx).copied().unwrap_or(usize::MAX - idx))
+                .collect(),
+            None => root_indices.to_vec(),
+        }
+    }
+    
+    /// 根据检测参数构建扫描选项
+    pub fn scan_options(&self) -> Result<ScanOptions, String> {
+        Ok(ScanOptions {
+            recursive: self.recursive,
+            exclude: build_glob_set(&self.exclude_globs)?,
+            excluded_files: self.exclude_paths.iter()
+                .map(|path| path.canonicalize().unwrap_or_else(|_| path.clone()))
+                .collect(),
+            skip_hidden: self.skip_hidden,
+            follow_symlinks: self.follow_symlinks,
+        })
+    }
+    
+    /// 根据检测参数构建哈希计算选项
+    pub fn hash_options(&self) -> HashOptions {
+        HashOptions {
+            resize_filter: self.resize_filter,
+            grayscale: self.grayscale,
+            max_pixels: self.max_image_pixels,
+            preprocess_blur: self.preprocess_blur,
+            normalize: self.normalize,
+            equalize_histogram: self.equalize_histogram,
+            orb: self.orb_params.clone(),
+            color_signature: self.use_color_prefilter,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&DuplicateDetectionRequest> for DuplicateDetectionParams {
+    fn from(req: &DuplicateDetectionRequest) -> Self {
+        let mut params = Self {
+            folders: req.folder_paths.iter().map(PathBuf::from).collect(),
+            algorithm: req.algorithm,
+            threshold: req.similarity_threshold,
+            recursive: req.recursive,
+            exclude_globs: req.exclude_globs.clone(),
+            exclude_paths: req.exclude_paths.iter().map(PathBuf::from).collect(),
+            skip_hidden: req.skip_hidden,
+            follow_symlinks: req.follow_symlinks,
+            resize_filter: req.resize_filter,
+            grayscale: req.grayscale,
+            max_image_pixels: req.max_image_pixels.unwrap_or(DEFAULT_MAX_IMAGE_PIXELS),
+            max_threads: req.max_threads,
+            config: req.config.clone(),
+            cross_folder_only: req.cross_folder_only,
+            root_groups: req.root_groups.clone(),
+            size_prefilter: req.size_prefilter,
+            preprocess_blur: req.preprocess_blur,
+            normalize: req.normalize,
+            equalize_histogram: req.equalize_histogram,
+            orb_params: req.orb_params.clone(),
+            min_wasted_bytes: req.min_wasted_bytes,
+            only_different_names: req.only_different_names,
+            max_aspect_ratio_factor: req.max_aspect_ratio_factor,
+            min_dimension: req.min_dimension,
+            confirm_with: req.confirm_with,
+            confirm_threshold: req.confirm_threshold,
+            include_thumbnails: req.include_thumbnails,
+            thumbnail_size: req.thumbnail_size,
+            use_color_prefilter: req.use_color_prefilter,
+            verify_bytes: req.verify_bytes,
+            block_match_threshold: req.block_match_threshold,
+            max_groups: req.max_groups,
+        };
+        
+        // 算法专用参数覆盖对应的通用字段
+        match &req.algorithm_params {
+            Some(AlgorithmParams::Average { normalize } | AlgorithmParams::Difference { normalize }) => {
+                params.normalize = *normalize;
+            },
+            Some(AlgorithmParams::Perceptual { preprocess_blur }) => {
+                params.preprocess_blur = *preprocess_blur;
+            },
+            Some(AlgorithmParams::ORB(orb_params)) => {
+                params.orb_params = orb_params.clone();
+            },
+            Some(AlgorithmParams::Exact | AlgorithmParams::EdgeHash | AlgorithmParams::ScaledDuplicate) | None => {},
+        }
+        
+        params
+    }
 }
 
 /// 执行重复图像检测
-pub fn detect_duplicates(params: &DuplicateDetectionParams) -> Result<Vec<DuplicateGroup>, String> {
+///
+/// 设置了`max_threads`时，所有并行工作都在一个独立的线程池中执行，
+/// 否则使用rayon的全局线程池。
+pub fn detect_duplicates(params: &DuplicateDetectionParams) -> Result<DetectionResults, String> {
+    detect_duplicates_with_progress(params, &NoProgress)
+}
+
+/// 执行重复图像检测，并通过`progress`报告进度
+pub fn detect_duplicates_with_progress(
+    params: &DuplicateDetectionParams,
+    progress: &dyn ProgressReporter
+) -> Result<DetectionResults, String> {
+    with_thread_pool(params.max_threads, || run_detection(params, progress))
+}
+
+/// 在指定线程数的独立线程池中执行任务，未指定时直接在全局线程池中执行
+fn with_thread_pool<T, F>(max_threads: Option<usize>, task: F) -> Result<T, String>
+where
+    T: Send,
+    F: FnOnce() -> Result<T, String> + Send,
+{
+    match max_threads {
+        Some(threads) => {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(threads.max(1))
+                .build()
+                .map_err(|e| format!("创建线程池失败: {}", e))?;
+            println!("使用独立线程池，线程数: {}", pool.current_num_threads());
+            pool.install(task)
+        },
+        None => task(),
+    }
+}
+
+/// 在文件夹中查找与参考图像相似的图像
+///
+/// 只将每张图像与参考图像比较，不做全量两两分组，因此比完整的重复检测快得多。
+/// 结果按相似度从高到低排序，不包含参考图像本身。
+pub fn find_similar_to(
+    reference: &Path,
+    params: &DuplicateDetectionParams
+) -> Result<Vec<(PathBuf, f32)>, String> {
+    with_thread_pool(params.max_threads, || {
+        let reference_hash = algorithms::calculate_hash(reference, params.algorithm, &params.hash_options())?;
+        find_similar_to_hash(&reference_hash.hash, reference.canonicalize().ok(), params)
+    })
+}
+
+/// 在文件夹中查找与Python `imagehash`库生成的十六进制哈希相似的图像
+///
+/// 哈希按`hash_utils::from_imagehash_hex`的约定转换后与本程序计算的哈希比较，
+/// 已有imagehash哈希库的用户无需重新计算即可查找对应的文件。
+pub fn find_similar_to_imagehash(
+    hex_hash: &str,
+    params: &DuplicateDetectionParams
+) -> Result<Vec<(PathBuf, f32)>, String> {
+    let reference_hash = hash_utils::from_imagehash_hex(hex_hash, params.algorithm)?;
+    with_thread_pool(params.max_threads, || find_similar_to_hash(&reference_hash, None, params))
+}
+
+/// 在文件夹中查找与参考哈希相似的图像，`exclude`为需要排除的参考图像的规范路径
+fn find_similar_to_hash(
+    reference_hash: &str,
+    exclude: Option<PathBuf>,
+    params: &DuplicateDetectionParams
+) -> Result<Vec<(PathBuf, f32)>, String> {
+    // 参考哈希可能来自外部，扫描之前先确认它属于所选算法
+    algorithms::validate_hash(reference_hash, params.algorithm)?;
+    
+    let total_start_time = Instant::now();
+    let options = params.hash_options();
+    
+    let paths: Vec<PathBuf> = get_all_image_paths(&params.folders, &params.scan_options()?)?
+        .into_iter()
+        .filter(|path| exclude.is_none() || path.canonicalize().ok() != exclude)
+        .collect();
+    
+    if paths.is_empty() {
+        return Ok(Vec::new());
+    }
+    
+    let hashes = compute_image_hashes(&paths, params.algorithm, &options, &params.config, &NoProgress, total_start_time);
+    
+    let mut matches: Vec<(PathBuf, f32)> = paths.into_par_iter()
+        .zip(hashes.into_par_iter())
+        .filter_map(|(path, hash)| Some((path, hash?.ok()?)))
+        .map(|(path, hash)| {
+            let similarity = algorithms::calculate_similarity(reference_hash, &hash.hash, params.algorithm);
+            (path, similarity)
+        })
+        .filter(|(_, similarity)| *similarity >= params.threshold)
+        .collect();
+    
+    matches.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    
+    println!("相似图片查找完成，找到 {} 张相似图片 (耗时: {:?})", 
+             matches.len(), total_start_time.elapsed());
+    
+    Ok(matches)
+}
+
+/// 在预先计算的哈希列表中查找与参考哈希相似的项，不访问文件系统
+///
+/// 返回按相似度降序排列的(下标, 相似度)列表，相似度相同时按下标升序。
+/// 任一哈希不属于所选算法时返回错误并指出其下标。
+pub fn find_matches(
+    reference_hash: &str,
+    hashes: &[String],
+    algorithm: HashAlgorithm,
+    threshold: f32
+) -> Result<Vec<(usize, f32)>, String> {
+    algorithms::validate_hash(reference_hash, algorithm)?;
+    for (index, hash) in hashes.iter().enumerate() {
+        algorithms::validate_hash(hash, algorithm)
+            .map_err(|e| format!("第 {} 个哈希无效: {}", index, e))?;
+    }
+    
+    let mut matches: Vec<(usize, f32)> = hashes.par_iter()
+        .enumerate()
+        .map(|(index, hash)| (index, algorithms::calculate_similarity(reference_hash, hash, algorithm)))
+        .filter(|(_, similarity)| *similarity >= threshold)
+        .collect();
+    
+    matches.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal).then(a.0.cmp(&b.0)));
+    
+    Ok(matches)
+}
+
+/// 只扫描并计算哈希，不生成候选对也不分组
+///
+/// 用于单独测量解码和哈希的耗时、确认文件夹中的图像都能正常解码，
+/// 或者导出整个图库的哈希供其他工具使用。
+pub fn hash_folder(params: &DuplicateDetectionParams) -> Result<HashFolderResults, String> {
+    with_thread_pool(params.max_threads, || {
+        let scan_start_time = Instant::now();
+        let (scanned, scan_errors) = get_all_image_paths_with_roots(&params.folders, &params.scan_options()?, &NoProgress)?;
+        let paths: Vec<PathBuf> = scanned.into_iter().map(|(_, path)| path).collect();
+        let scan_ms = scan_start_time.elapsed().as_millis() as u64;
+        
+        let hash_start_time = Instant::now();
+        let hash_results = compute_image_hashes(
+            &paths,
+            params.algorithm,
+            &params.hash_options(),
+            &params.config,
+            &NoProgress,
+            scan_start_time
+        );
+        let hash_ms = hash_start_time.elapsed().as_millis() as u64;
+        
+        let mut images = Vec::with_capacity(paths.len());
+        let mut errors = scan_errors;
+        for (path, result) in paths.iter().zip(hash_results) {
+            let path = path.to_string_lossy().into_owned();
+            match result {
+                Some(Ok(hash)) => images.push(HashedImage {
+                    path,
+                    hash: hash.hash,
+                    width: hash.width,
+                    height: hash.height,
+                }),
+                Some(Err(error)) => errors.push(FileError::from_hash_error(path, error)),
+                None => {},
+            }
+        }
+        
+        println!("哈希导出完成，成功 {} 张，失败 {} 个 (扫描: {}ms, 哈希: {}ms)",
+                 images.len(), errors.len(), scan_ms, hash_ms);
+        
+        Ok(HashFolderResults { images, errors, scan_ms, hash_ms })
+    })
+}
+
+/// 相似度直方图的区间数量，每个区间宽2个百分点
+const SIMILARITY_HISTOGRAM_BUCKETS: usize = 50;
+
+/// 统计候选对的相似度分布并建议一个阈值
+///
+/// 只做扫描、哈希和LSH候选对生成，不做分组。重复图像和不相关图像的相似度通常形成两个峰，
+/// 使用Otsu方法在直方图上寻找使两类间方差最大的分界点作为建议阈值；
+/// 候选对少于两个时无法判断，返回算法的默认阈值。
+pub fn suggest_threshold(params: &DuplicateDetectionParams) -> Result<ThresholdSuggestion, String> {
+    with_thread_pool(params.max_threads, || {
+        let total_start_time = Instant::now();
+        let similarities = candidate_similarities(params, total_start_time)?;
+        let histogram = build_histogram(&similarities, SIMILARITY_HISTOGRAM_BUCKETS);
+        
+        let counts: Vec<usize> = histogram.iter().map(|bucket| bucket.count).collect();
+        let suggested_threshold = match otsu_split(&counts) {
+            Some(bucket) if similarities.len() >= 2 => histogram[bucket].min,
+            _ => params.algorithm.default_threshold(),
+        };
+        
+        println!("阈值建议完成，{} 个候选对，建议阈值 {} (耗时: {:?})", 
+                 similarities.len(), suggested_threshold, total_start_time.elapsed());
+        
+        Ok(ThresholdSuggestion {
+            suggested_threshold,
+            pair_count: similarities.len(),
+            histogram,
+        })
+    })
+}
+
+/// 统计候选对的相似度分布，分为`bins`个等宽区间
+///
+/// 与`suggest_threshold`使用相同的流程，但不做建议，由调用方决定区间数量。
+pub fn similarity_histogram(params: &DuplicateDetectionParams, bins: usize) -> Result<Vec<HistogramBucket>, String> {
+    with_thread_pool(params.max_threads, || {
+        let total_start_time = Instant::now();
+        let similarities = candidate_similarities(params, total_start_time)?;
+        
+        println!("相似度直方图统计完成，{} 个候选对 (耗时: {:?})", 
+                 similarities.len(), total_start_time.elapsed());
+        
+        Ok(build_histogram(&similarities, bins))
+    })
+}
+
+/// 统计候选对数量时最多计算哈希的图像数，图像更多时按此数量抽样估算
+pub const CANDIDATE_ESTIMATE_SAMPLE_SIZE: usize = 2000;
+
+/// 统计（或估算）一组图像的LSH候选对数量
+///
+/// 图像不超过`CANDIDATE_ESTIMATE_SAMPLE_SIZE`张时计算全部哈希并实际生成候选对；
+/// 更多时按扫描顺序等间隔抽样，用样本中的候选对数量乘以总对数与样本对数之比估算，
+/// 避免在统计阶段就完成整个检测的哈希计算。
+/// 统计的是LSH生成的原始候选对，不含仅跨文件夹、文件大小等后续过滤。
+pub fn estimate_candidate_pairs(
+    paths: &[PathBuf],
+    params: &DuplicateDetectionParams
+) -> Result<CandidatePairEstimate, String> {
+    with_thread_pool(params.max_threads, || {
+        let total_start_time = Instant::now();
+        let estimated = paths.len() > CANDIDATE_ESTIMATE_SAMPLE_SIZE;
+        
+        let sample: Vec<PathBuf> = if estimated {
+            let step = paths.len() as f64 / CANDIDATE_ESTIMATE_SAMPLE_SIZE as f64;
+            (0..CANDIDATE_ESTIMATE_SAMPLE_SIZE)
+                .map(|k| paths[(k as f64 * step) as usize].clone())
+                .collect()
+        } else {
+            paths.to_vec()
+        };
+        
+        let hashes: Vec<String> = compute_image_hashes(&sample, params.algorithm, &params.hash_options(), &params.config, &NoProgress, total_start_time)
+            .into_iter()
+            .filter_map(|hash| Some(hash?.ok()?.hash))
+            .collect();
+        let sample_pairs = compute_candidate_pairs(&hashes, params.algorithm, params.config.candidate_batch_size, params.config.max_bucket_size).len();
+        
+        let count = if !estimated {
+            sample_pairs
+        } else if hashes.len() < 2 {
+            0
+        } else {
+            // 候选对数量随图像数的平方增长，按总对数与样本对数之比放大
+            let total = paths.len() as f64;
+            let hashed = hashes.len() as f64;
+            (sample_pairs as f64 * (total * (total - 1.0)) / (hashed * (hashed - 1.0))).round() as usize
+        };
+        
+        println!("候选对统计完成，{} 张图像中{}{} 个候选对 (耗时: {:?})", 
+                 paths.len(), if estimated { "估计有" } else { "共有" }, count, total_start_time.elapsed());
+        
+        Ok(CandidatePairEstimate {
+            count,
+            estimated,
+            sampled_images: hashes.len(),
+        })
+    })
+}
+
+/// 扫描并计算哈希，返回所有LSH候选对的相似度（不做阈值过滤和分组）
+fn candidate_similarities(params: &DuplicateDetectionParams, total_start_time: Instant) -> Result<Vec<f32>, String> {
+    let paths = get_all_image_paths(&params.folders, &params.scan_options()?)?;
+    let hashes: Vec<String> = compute_image_hashes(&paths, params.algorithm, &params.hash_options(), &params.config, &NoProgress, total_start_time)
+        .into_iter()
+        .filter_map(|hash| Some(hash?.ok()?.hash))
+        .collect();
+    
+    Ok(compute_candidate_pairs(&hashes, params.algorithm, params.config.candidate_batch_size, params.config.max_bucket_size)
+        .into_par_iter()
+        .map(|(i, j)| algorithms::calculate_similarity(&hashes[i], &hashes[j], params.algorithm))
+        .collect())
+}
+
+/// 将相似度(0-100)分入`bins`个等宽区间，`bins`至少为1
+fn build_histogram(similarities: &[f32], bins: usize) -> Vec<HistogramBucket> {
+    let bins = bins.max(1);
+    let bucket_width = 100.0 / bins as f32;
+    
+    let mut counts = vec![0usize; bins];
+    for &similarity in similarities {
+        let bucket = ((similarity / bucket_width) as usize).min(bins - 1);
+        counts[bucket] += 1;
+    }
+    
+    counts.into_iter()
+        .enumerate()
+        .map(|(i, count)| HistogramBucket {
+            min: i as f32 * bucket_width,
+            max: (i + 1) as f32 * bucket_width,
+            count,
+        })
+        .collect()
+}
+
+/// 使用Otsu方法寻找直方图的最佳分界
+///
+/// 返回上半部分第一个区间的下标，即分界点位于该区间的下界；直方图为空或只有一个非空区间时返回None。
+fn otsu_split(counts: &[usize]) -> Option<usize> {
+    let total: usize = counts.iter().sum();
+    let weighted_total: f64 = counts.iter().enumerate().map(|(i, &c)| i as f64 * c as f64).sum();
+    
+    let mut best: Option<(usize, f64)> = None;
+    let mut lower_count = 0usize;
+    let mut lower_weighted = 0.0f64;
+    
+    for split in 1..counts.len() {
+        lower_count += counts[split - 1];
+        lower_weighted += (split - 1) as f64 * counts[split - 1] as f64;
+        let upper_count = total - lower_count;
+        if lower_count == 0 || upper_count == 0 {
+            continue;
+        }
+        
+        let lower_mean = lower_weighted / lower_count as f64;
+        let upper_mean = (weighted_total - lower_weighted) / upper_count as f64;
+        let variance = lower_count as f64 * upper_count as f64 * (lower_mean - upper_mean).powi(2);
+        
+        if best.is_none_or(|(_, best_variance)| variance > best_variance) {
+            best = Some((split, variance));
+        }
+    }
+    
+    best.map(|(split, _)| split)
+}
+
+/// 在当前线程池中执行检测流程
+fn run_detection(params: &DuplicateDetectionParams, progress: &dyn ProgressReporter) -> Result<DetectionResults, String> {
     // 开始计时
     let total_start_time = Instant::now();
     
     // 1. 收集所有图像路径
     let scan_start_time = Instant::now();
-    let mut all_image_paths = Vec::new();
+    let scan_options = params.scan_options()?;
+    let (scanned, scan_errors) = get_all_image_paths_with_roots(&params.folders, &scan_options, progress)?;
+    let (mut root_indices, mut all_image_paths): (Vec<usize>, Vec<PathBuf>) = scanned.into_iter().unzip();
     
-    for folder in &params.folders {
-        let mut paths = get_image_paths(folder, params.recursive)?;
-        all_image_paths.append(&mut paths);
+    if all_image_paths.is_empty() {
+        return Ok(DetectionResults {
+            errors: scan_errors,
+            ..Default::default()
+        });
     }
     
-    if all_image_paths.is_empty() {
-        return Ok(Vec::new());
+    // 扫描期间已取消，还没有任何哈希可以分组
+    if progress.is_cancelled() {
+        return Ok(DetectionResults {
+            errors: scan_errors,
+            partial: true,
+            ..Default::default()
+        });
     }
     
+    let mut metrics = RunMetrics::default();
+    metrics.stats.images_scanned = all_image_paths.len();
+    
     // 计算图片扫描时间
     let scan_time = scan_start_time.elapsed();
+    metrics.timings.scan_ms = scan_time.as_millis() as u64;
     let total_elapsed = total_start_time.elapsed();
     println!("图片扫描时间: {:?}, 共找到 {} 张图片 (累计耗时: {:?})", 
              scan_time, all_image_paths.len(), total_elapsed);
     
+    // 可选的文件大小预过滤
+    let prefilter_start_time = Instant::now();
+    let mut file_sizes = if params.size_prefilter {
+        let sizes = apply_size_prefilter(&mut all_image_paths, &mut root_indices, params.algorithm);
+        println!("文件大小预过滤完成，剩余 {} 张图片 (累计耗时: {:?})", 
+                 all_image_paths.len(), total_start_time.elapsed());
+        
+        if all_image_paths.is_empty() {
+            return Ok(metrics.into_prefiltered_results(scan_errors, prefilter_start_time, total_start_time));
+        }
+        Some(sizes)
+    } else {
+        None
+    };
+    
+    // 可选的最小尺寸过滤
+    if let Some(min_dimension) = params.min_dimension {
+        apply_dimension_prefilter(&mut all_image_paths, &mut root_indices, file_sizes.as_mut(), min_dimension);
+        println!("最小尺寸过滤完成，剩余 {} 张图片 (累计耗时: {:?})", 
+                 all_image_paths.len(), total_start_time.elapsed());
+        
+        if all_image_paths.is_empty() {
+            return Ok(metrics.into_prefiltered_results(scan_errors, prefilter_start_time, total_start_time));
+        }
+    }
+    
+    metrics.timings.prefilter_ms = prefilter_start_time.elapsed().as_millis() as u64;
+    
     // 开始计算哈希值的计时
     let hash_start_time = Instant::now();
     
     // 2. 计算所有图像的哈希值
-    let image_hashes = compute_image_hashes(&all_image_paths, params.algorithm, total_start_time)?;
+    let hash_results = compute_image_hashes(
+        &all_image_paths,
+        params.algorithm,
+        &params.hash_options(),
+        &params.config,
+        progress,
+        total_start_time
+    );
+    
+    // 处理失败的图像不参与分组，记入错误列表；取消后跳过的图像直接丢弃，只对已完成的部分分组
+    let partial = hash_results.iter().any(Option::is_none);
+    let succeeded: Vec<bool> = hash_results.iter().map(|result| matches!(result, Some(Ok(_)))).collect();
+    let mut image_hashes = Vec::with_capacity(hash_results.len());
+    // 无法读取的目录排在前面，之后是处理失败的图像
+    let scan_error_count = scan_errors.len();
+    let mut errors = scan_errors;
+    
+    for (path, result) in all_image_paths.iter().zip(hash_results) {
+        match result {
+            Some(Ok(hash)) => image_hashes.push(hash),
+            Some(Err(error)) => errors.push(FileError::from_hash_error(path.to_string_lossy().into_owned(), error)),
+            None => {},
+        }
+    }
+    
+    if partial {
+        println!("检测已取消，只对已完成的 {} 张图片分组", image_hashes.len() + errors.len() - scan_error_count);
+    }
+    
+    retain_by_mask(&mut all_image_paths, &succeeded);
+    retain_by_mask(&mut root_indices, &succeeded);
+    if let Some(sizes) = file_sizes.as_mut() {
+        retain_by_mask(sizes, &succeeded);
+    }
     
     // 计算哈希计算时间
     let hash_time = hash_start_time.elapsed();
+    metrics.timings.hash_ms = hash_time.as_millis() as u64;
+    metrics.stats.images_hashed = image_hashes.len();
+    metrics.stats.images_failed = errors.len() - scan_error_count;
     let total_elapsed = total_start_time.elapsed();
-    println!("图片哈希计算时间: {:?}, 共处理 {} 张图片 (累计耗时: {:?})", 
-             hash_time, image_hashes.len(), total_elapsed);
+    println!("图片哈希计算时间: {:?}, 成功处理 {} 张图片，失败 {} 张 (累计耗时: {:?})", 
+             hash_time, image_hashes.len(), metrics.stats.images_failed, total_elapsed);
     
     // 开始计算相似度的计时
     let similarity_start_time = Instant::now();
     
-    // 3. 根据哈希值找出重复图像
-    let duplicate_groups = find_duplicate_groups(
+    // 3. 根据哈希值找出重复图像的连通分量
+    let components = find_duplicate_components(
         &all_image_paths,
+        &root_indices,
         &image_hashes,
-        params.algorithm,
-        params.threshold,
+        file_sizes.as_deref(),
+        params,
+        &mut metrics,
         total_start_time
     )?;
     
     // 计算相似度比较时间
     let similarity_time = similarity_start_time.elapsed();
     let total_elapsed = total_start_time.elapsed();
-    println!("图片相似度比较时间: {:?}, 共找到 {} 组重复图片 (累计耗时: {:?})", 
-             similarity_time, duplicate_groups.len(), total_elapsed);
+    println!("图片相似度比较时间: {:?}, 共找到 {} 个重复连通分量 (累计耗时: {:?})", 
+             similarity_time, components.len(), total_elapsed);
     
-    // 4. 按组大小排序，最大的组在最前面
-    let mut sorted_groups = duplicate_groups;
-    sorted_groups.sort_by(|a, b| b.images.len().cmp(&a.images.len()));
+    // 收集文件信息构建重复组，每完成一组立即报告给前端
+    // 限制组数时哪些组会被保留要等排序后才知道，这种情况下截断后再报告保留的组
+    let grouping_start_time = Instant::now();
+    let stream_groups = params.max_groups.is_none();
+    let metadata = FileMetadataSource {
+        paths: &all_image_paths,
+        root_indices: &root_indices,
+        hashes: &image_hashes,
+        params,
+    };
+    let group_progress: &dyn ProgressReporter = if stream_groups { progress } else { &NoProgress };
+    let mut sorted_groups = build_duplicate_groups(components, &metadata, params, group_progress);
+    let grouping_time = grouping_start_time.elapsed();
+    metrics.timings.grouping_ms = grouping_time.as_millis() as u64;
+    println!("图片分组时间: {:?}, 共形成 {} 组重复图片 (累计耗时: {:?})", 
+             grouping_time, sorted_groups.len(), total_start_time.elapsed());
+    
+    // 4. 排序并限制返回的组数
+    metrics.stats.total_groups = finalize_groups(&mut sorted_groups, params);
+    if !stream_groups {
+        for group in &sorted_groups {
+            progress.group_found(group);
+        }
+    }
     
     // 计算总耗时
     let total_time = total_start_time.elapsed();
     println!("总耗时: {:?}", total_time);
+    metrics.timings.total_ms = total_time.as_millis() as u64;
     
-    Ok(sorted_groups)
+    Ok(DetectionResults {
+        groups: sorted_groups,
+        errors,
+        stats: metrics.stats,
+        timings: metrics.timings,
+        partial,
+        ..Default::default()
+    })
+}
+
+/// 缩放副本模式允许的最大宽高比差异（较大与较小宽高比之比）
+///
+/// 缩小时宽高分别取整，边长不小于50像素的缩略图宽高比误差在2%以内。
+pub const SCALED_DUPLICATE_MAX_ASPECT_FACTOR: f32 = 1.02;
+
+/// 颜色预过滤允许的最大颜色签名距离(0-1)
+///
+/// 同一张图重新压缩或轻微调色后距离通常在0.05以内，主色调不同的同一图形（如红色版与蓝色版）约在0.35以上。
+pub const COLOR_PREFILTER_MAX_DISTANCE: f32 = 0.3;
+
+/// 两张图像的颜色签名是否足够接近，任一方没有颜色签名时不过滤
+fn colors_compatible(a: &HashResult, b: &HashResult) -> bool {
+    match (&a.color_signature, &b.color_signature) {
+        (Some(sig_a), Some(sig_b)) => {
+            image_utils::color_signature_distance(sig_a, sig_b) <= COLOR_PREFILTER_MAX_DISTANCE
+        },
+        _ => true,
+    }
+}
+
+/// 按文件内容把一组图像拆分为字节完全相同的子组，只返回包含多张图像的子组
+///
+/// 先按文件大小分桶，大小相同的再流式计算SHA-256，摘要也相同时才逐块比较内容确认；
+/// 每一步都只读取固定大小的块，大文件组成的组也不会整体读入内存。
+/// 无法读取的文件无法确认，直接丢弃。
+fn split_by_file_bytes(images: Vec<ImageInfo>) -> Vec<Vec<ImageInfo>> {
+    let mut confirmed = Vec::new();
+    
+    for same_size in bucket_by(images, |image| Some(image.size_bytes)) {
+        let same_digest = bucket_by(same_size, |image| hash_utils::compute_file_sha256(Path::new(&image.path)).ok());
+        for candidates in same_digest {
+            let mut identical: Vec<Vec<ImageInfo>> = Vec::new();
+            for image in candidates {
+                let matching = identical.iter_mut().find(|bucket| {
+                    hash_utils::files_identical(Path::new(&bucket[0].path), Path::new(&image.path)).unwrap_or(false)
+                });
+                match matching {
+                    Some(bucket) => bucket.push(image),
+                    None => identical.push(vec![image]),
+                }
+            }
+            confirmed.extend(identical.into_iter().filter(|bucket| bucket.len() > 1));
+        }
+    }
+    
+    confirmed
+}
+
+/// 按键把元素分桶，保持元素的原有顺序，只返回包含多个元素的桶
+///
+/// `key`返回None的元素被丢弃。
+fn bucket_by<T, K: PartialEq>(items: Vec<T>, key: impl Fn(&T) -> Option<K>) -> Vec<Vec<T>> {
+    let mut buckets: Vec<(K, Vec<T>)> = Vec::new();
+    
+    for item in items {
+        let Some(item_key) = key(&item) else {
+            continue;
+        };
+        
+        match buckets.iter_mut().find(|(bucket_key, _)| *bucket_key == item_key) {
+            Some((_, bucket)) => bucket.push(item),
+            None => buckets.push((item_key, vec![item])),
+        }
+    }
+    
+    buckets.into_iter()
+        .map(|(_, bucket)| bucket)
+        .filter(|bucket| bucket.len() > 1)
+        .collect()
+}
+
+/// 检测过程中收集的各阶段计数和耗时
+#[derive(Default)]
+struct RunMetrics {
+    stats: DetectionCounts,
+    timings: StageTimings,
+}
+
+impl RunMetrics {
+    /// 预过滤后没有剩余图像时提前结束，结果中仍保留已经完成的扫描计数和耗时
+    fn into_prefiltered_results(mut self, errors: Vec<FileError>, prefilter_start_time: Instant, total_start_time: Instant) -> DetectionResults {
+        self.timings.prefilter_ms = prefilter_start_time.elapsed().as_millis() as u64;
+        self.timings.total_ms = total_start_time.elapsed().as_millis() as u64;
+        DetectionResults {
+            errors,
+            stats: self.stats,
+            timings: self.timings,
+            ..Default::default()
+        }
+    }
+}
+
+/// 按掩码保留元素，`keep`与`items`一一对应
+fn retain_by_mask<T>(items: &mut Vec<T>, keep: &[bool]) {
+    let mut idx = 0;
+    items.retain(|_| {
+        let kept = keep[idx];
+        idx += 1;
+        kept
+    });
 }
 
 /// 并行计算所有图像的哈希值
+///
+/// 返回结果与`paths`一一对应，处理失败的图像对应其失败原因；
+/// `progress`报告取消后尚未开始处理的图像对应None。
+/// 即使所有图像都失败也正常返回，由调用方把每个失败记入错误列表。
 fn compute_image_hashes(
     paths: &[PathBuf],
     algorithm: HashAlgorithm,
+    options: &HashOptions,
+    config: &DetectionConfig,
+    progress: &dyn ProgressReporter,
     total_start_time: Instant
-) -> Result<Vec<HashResult>, String> {
+) -> Vec<Option<Result<HashResult, HashError>>> {
     if paths.is_empty() {
-        return Ok(Vec::new());
+        return Vec::new();
     }
     
     // 批量处理提高性能
-    const BATCH_SIZE: usize = 500;
+    let batch_size = config.hash_batch_size.max(1);
+    
+    // 限制同时解码的图像数，避免大量全分辨率图像同时驻留内存
+    // 使用与限制同样大小的独立线程池，而不是在rayon任务中阻塞等待许可：
+    // 哈希计算内部可能再启动rayon任务，等待其完成的线程会窃取其他图像，阻塞式信号量因此会死锁
+    let decode_pool = build_decode_pool(config.max_concurrent_decodes);
     
     // 创建固定大小的结果向量，初始化为None
     let results = Arc::new(Mutex::new(vec![None; paths.len()]));
     let error_count = Arc::new(Mutex::new(0));
     
+    // 每完成一张图像计数一次，经节流后报告进度
+    let processed = AtomicUsize::new(0);
+    let throttle = ProgressThrottle::new(paths.len());
+    
     // 记录批处理开始时间
     let batch_start_time = Instant::now();
-    let batch_count = (paths.len() + BATCH_SIZE - 1) / BATCH_SIZE; // 向上取整
+    let batch_count = paths.len().div_ceil(batch_size);
     
     let total_elapsed = total_start_time.elapsed();
     println!("开始计算图像哈希值，共 {} 张图片，分为 {} 批处理 (累计耗时: {:?})", 
              paths.len(), batch_count, total_elapsed);
     
     // 分批并行处理
-    paths.chunks(BATCH_SIZE).enumerate().par_bridge().for_each(|(batch_idx, batch)| {
+    let hash_batches = || paths.chunks(batch_size).enumerate().par_bridge().for_each(|(batch_idx, batch)| {
         let local_start_time = Instant::now();
         
-        let batch_results: Vec<(usize, Result<HashResult, String>)> = batch.par_iter().enumerate()
+        let batch_results: Vec<(usize, Option<Result<HashResult, HashError>>)> = batch.par_iter().enumerate()
             .map(|(local_idx, path)| {
                 // 计算哈希并记录原始索引
                 let global_idx = local_idx + 
                     (batch.as_ptr() as usize - paths.as_ptr() as usize) / std::mem::size_of::<PathBuf>();
                 
-                (global_idx, algorithms::calculate_hash(path, algorithm))
+                // 已取消时不再开始新的图像
+                if progress.is_cancelled() {
+                    return (global_idx, None);
+                }
+                
+                let result = algorithms::calculate_hash(path, algorithm, options);
+                
+                let done = processed.fetch_add(1, Ordering::Relaxed) + 1;
+                if throttle.should_emit(done) {
+                    progress.hash_progress(&HashProgress {
+                        processed: done,
+                        total: paths.len(),
+                        eta_ms: throttle.estimate_remaining_ms(done),
+                    });
+                }
+                
+                (global_idx, Some(result))
             })
             .collect();
         
@@ -131,15 +935,11 @@ fn compute_image_hashes(
         let mut error_lock = error_count.lock().unwrap();
         
         for (idx, result) in batch_results {
-            match result {
-                Ok(hash) => {
-                    results_lock[idx] = Some(hash);
-                },
-                Err(e) => {
-                    *error_lock += 1;
-                    eprintln!("处理图像失败 {}: {}", paths[idx].display(), e);
-                }
+            if let Some(Err(e)) = &result {
+                *error_lock += 1;
+                eprintln!("处理图像失败 {}: {}", paths[idx].display(), e);
             }
+            results_lock[idx] = result;
         }
         
         let local_elapsed = local_start_time.elapsed();
@@ -147,6 +947,10 @@ fn compute_image_hashes(
         println!("批次 {}/{} 处理完成，耗时: {:?}, 处理了 {} 张图片 (累计耗时: {:?})", 
                  batch_idx + 1, batch_count, local_elapsed, batch.len(), total_elapsed);
     });
+    match &decode_pool {
+        Some(pool) => pool.install(hash_batches),
+        None => hash_batches(),
+    }
     
     let batch_total_time = batch_start_time.elapsed();
     let total_elapsed = total_start_time.elapsed();
@@ -161,34 +965,48 @@ fn compute_image_hashes(
     
     let final_error_count = *error_count.lock().unwrap();
     
-    // 将Option<HashResult>转换为HashResult，对于None的情况使用空哈希值
-    let valid_hashes: Vec<HashResult> = final_results.into_iter()
-        .map(|opt_result| opt_result.unwrap_or_else(|| HashResult {
-            hash: String::new(),
-            width: 0,
-            height: 0,
-        }))
-        .collect();
+    // 未写入结果的索引是取消后跳过的图像
+    let hash_results: Vec<Option<Result<HashResult, HashError>>> = final_results;
     
     if final_error_count > 0 {
         eprintln!("注意: {} 个图像处理失败", final_error_count);
     }
     
-    if valid_hashes.is_empty() {
-        Err("所有图像处理均失败".to_string())
-    } else {
-        Ok(valid_hashes)
+    hash_results
+}
+
+/// 创建用于计算哈希的线程池，线程数即同时解码的最大图像数
+///
+/// `max_concurrent`为0或不小于当前线程池的线程数时不需要额外限制，返回None；
+/// 创建失败时同样返回None，退回当前线程池。
+fn build_decode_pool(max_concurrent: usize) -> Option<rayon::ThreadPool> {
+    if max_concurrent == 0 || max_concurrent >= rayon::current_num_threads() {
+        return None;
     }
+    
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(max_concurrent)
+        .build()
+        .map_err(|e| eprintln!("创建解码线程池失败，不限制同时解码数: {}", e))
+        .ok()
 }
 
-/// 寻找重复图像并分组
-fn find_duplicate_groups(
+/// 寻找重复图像，返回包含两张以上图像的连通分量（图像在`paths`中的索引）
+///
+/// `root_indices`与`paths`一一对应，记录每张图像所属根目录在`params.folders`中的索引；
+/// 提供`file_sizes`时，文件大小不可能重复的候选对会被丢弃
+fn find_duplicate_components(
     paths: &[PathBuf],
+    root_indices: &[usize],
     hashes: &[HashResult],
-    algorithm: HashAlgorithm,
-    threshold: f32,
+    file_sizes: Option<&[u64]>,
+    params: &DuplicateDetectionParams,
+    metrics: &mut RunMetrics,
     total_start_time: Instant
-) -> Result<Vec<DuplicateGroup>, String> {
+) -> Result<Vec<Vec<usize>>, String> {
+    let algorithm = params.algorithm;
+    let threshold = params.threshold;
+    
     if hashes.is_empty() {
         return Ok(Vec::new());
     }
@@ -200,13 +1018,67 @@ fn find_duplicate_groups(
     // 开始LSH候选对生成计时
     let lsh_start_time = Instant::now();
     
-    // 提取所有哈希字符串用于LSH算法
-    let hash_strings: Vec<String> = hashes.iter().map(|h| h.hash.clone()).collect();
+    // 64位二值哈希只转换一次为u64，候选对生成、预过滤和相似度计算都直接使用；
+    // 字符串只用于显示。ORB、精确哈希以及混有其他长度哈希（如视频）时仍使用字符串
+    let packed = pack_binary_hashes(hashes, algorithm);
+    let hash_strings: Vec<String> = if packed.is_some() {
+        Vec::new()
+    } else {
+        hashes.iter().map(|h| h.hash.clone()).collect()
+    };
     
     // 使用LSH算法快速找到可能的候选对
-    let candidate_pairs = compute_candidate_pairs(&hash_strings, algorithm);
+    let mut candidate_pairs = match &packed {
+        Some(packed) => compute_packed_candidate_pairs(packed, algorithm, params.config.candidate_batch_size, params.config.max_bucket_size),
+        None => compute_candidate_pairs(&hash_strings, algorithm, params.config.candidate_batch_size, params.config.max_bucket_size),
+    };
+    metrics.stats.candidate_pairs = candidate_pairs.len();
+    
+    // 仅跨文件夹模式：丢弃来自同一文件夹分组的候选对
+    if params.cross_folder_only {
+        let groups = params.image_root_groups(root_indices);
+        candidate_pairs.retain(|&(i, j)| groups[i] != groups[j]);
+    }
+    
+    // 文件大小预过滤：丢弃大小不可能重复的候选对
+    if let Some(sizes) = file_sizes {
+        candidate_pairs.retain(|&(i, j)| sizes_compatible(algorithm, sizes[i], sizes[j]));
+    }
+    
+    // 宽高比过滤：丢弃形状相差悬殊的候选对；缩放副本模式总是要求宽高比一致
+    let max_aspect_ratio_factor = match algorithm {
+        HashAlgorithm::ScaledDuplicate => Some(params.max_aspect_ratio_factor
+            .map_or(SCALED_DUPLICATE_MAX_ASPECT_FACTOR, |factor| factor.min(SCALED_DUPLICATE_MAX_ASPECT_FACTOR))),
+        _ => params.max_aspect_ratio_factor,
+    };
+    if let Some(max_factor) = max_aspect_ratio_factor {
+        candidate_pairs.retain(|&(i, j)| aspect_ratios_compatible(&hashes[i], &hashes[j], max_factor));
+    }
+    
+    // 颜色预过滤：丢弃颜色分布相差悬殊的候选对
+    if params.use_color_prefilter {
+        let before = candidate_pairs.len();
+        candidate_pairs.retain(|&(i, j)| colors_compatible(&hashes[i], &hashes[j]));
+        println!("按颜色预过滤，移除 {} 个候选对", before - candidate_pairs.len());
+    }
+    
+    // 1的个数预过滤：汉明距离不小于两个哈希中1的个数之差，差值已超过阈值允许范围的候选对不可能相似
+    let popcounts = match &packed {
+        Some(packed) => Some(packed.par_iter().map(|hash| Some(hash.count_ones())).collect()),
+        None => binary_popcounts(&hash_strings, algorithm),
+    };
+    if let Some(popcounts) = popcounts {
+        let bits = algorithm.bit_length().unwrap_or(0) as f32;
+        let before = candidate_pairs.len();
+        candidate_pairs.retain(|&(i, j)| match (popcounts[i], popcounts[j]) {
+            (Some(a), Some(b)) => 100.0 * (1.0 - a.abs_diff(b) as f32 / bits) >= threshold,
+            _ => true,
+        });
+        println!("按1的个数预过滤，移除 {} 个候选对", before - candidate_pairs.len());
+    }
     
     let lsh_time = lsh_start_time.elapsed();
+    metrics.timings.candidate_ms = lsh_time.as_millis() as u64;
     let total_elapsed = total_start_time.elapsed();
     println!("LSH候选对生成时间: {:?}, 生成了 {} 个候选对 (累计耗时: {:?})", 
              lsh_time, candidate_pairs.len(), total_elapsed);
@@ -214,90 +1086,392 @@ fn find_duplicate_groups(
     // 开始相似度计算计时
     let similarity_calc_start_time = Instant::now();
     
-    // 并行计算所有候选对的相似度
-    let similarity_results: Vec<((usize, usize), f32)> = candidate_pairs
-        .par_iter()
-        .map(|&(i, j)| {
-            let hash1 = &hash_strings[i];
-            let hash2 = &hash_strings[j];
-            let similarity = algorithms::calculate_similarity(hash1, hash2, algorithm);
-            ((i, j), similarity)
-        })
-        .filter(|(_, similarity)| *similarity >= threshold)
-        .collect();
+    // 并行计算一批候选对的相似度，只保留超过阈值的对
+    let evaluate = |pairs: &[(usize, usize)]| -> Vec<ScoredPair> {
+        pairs.par_iter()
+            .map(|&(i, j)| {
+                let similarity = match &packed {
+                    Some(packed) => packed_hash_similarity(packed[i], packed[j]),
+                    None => algorithms::calculate_similarity(&hash_strings[i], &hash_strings[j], algorithm),
+                };
+                ((i, j), similarity)
+            })
+            .filter(|(_, similarity)| *similarity >= threshold)
+            .collect()
+    };
+    
+    // 使用并查集算法构建连通分量（相似图像组）
+    let mut disjoint_set = DisjointSet::new(hashes.len());
+    
+    // 分组是传递的，两端已在同一集合中的候选对不会改变结果，可以跳过相似度计算。
+    // 按批处理：每批开始前用并查集剔除已连通的对，批内仍并行计算，算完立即合并。
+    // 需要二次确认时，确认可能否决已合并的对，此时必须计算全部候选对。
+    let similarity_results = if params.confirm_with.is_none() {
+        let mut similar = Vec::new();
+        let mut evaluated = 0;
+        
+        for chunk in candidate_pairs.chunks(GROUPING_BATCH_SIZE) {
+            let pending: Vec<(usize, usize)> = chunk.iter()
+                .copied()
+                .filter(|&(i, j)| disjoint_set.find(i) != disjoint_set.find(j))
+                .collect();
+            evaluated += pending.len();
+            
+            let batch = evaluate(&pending);
+            for ((i, j), _) in &batch {
+                disjoint_set.union(*i, *j);
+            }
+            similar.extend(batch);
+        }
+        
+        metrics.stats.pairs_evaluated = evaluated;
+        metrics.stats.pairs_skipped = candidate_pairs.len() - evaluated;
+        println!("跳过 {} 个两端已在同一组中的候选对", metrics.stats.pairs_skipped);
+        similar
+    } else {
+        metrics.stats.pairs_evaluated = candidate_pairs.len();
+        evaluate(&candidate_pairs)
+    };
     
     let similarity_calc_time = similarity_calc_start_time.elapsed();
     let total_elapsed = total_start_time.elapsed();
     println!("相似度计算时间: {:?}, 共有 {} 对图片相似度超过阈值 (累计耗时: {:?})", 
              similarity_calc_time, similarity_results.len(), total_elapsed);
     
-    // 开始分组计时
-    let grouping_start_time = Instant::now();
-    
-    // 使用并查集算法构建连通分量（相似图像组）
-    let mut disjoint_set = DisjointSet::new(hashes.len());
+    // 可选的第二种算法确认
+    let similarity_results = match params.confirm_with {
+        Some(confirm_algorithm) => {
+            let confirmed = confirm_pairs(similarity_results, paths, confirm_algorithm, params, total_start_time)?;
+            println!("{}确认完成，剩余 {} 对图片 (累计耗时: {:?})", 
+                     confirm_algorithm, confirmed.len(), total_start_time.elapsed());
+            confirmed
+        },
+        None => similarity_results,
+    };
+    if params.confirm_with.is_some() {
+        // 只合并通过确认的图像对
+        for ((i, j), _) in &similarity_results {
+            disjoint_set.union(*i, *j);
+        }
+    }
+    metrics.stats.similar_pairs = similarity_results.len();
     
-    // 合并相似的图像对
-    for ((i, j), _) in &similarity_results {
-        disjoint_set.union(*i, *j);
+    // 可选的分块哈希补充：合并精确哈希不同但大部分内容相同的文件
+    if let (HashAlgorithm::Exact, Some(block_threshold)) = (algorithm, params.block_match_threshold) {
+        let mut block_pairs = block_match_pairs(paths, block_threshold, params.config.max_bucket_size);
+        if params.cross_folder_only {
+            let groups = params.image_root_groups(root_indices);
+            block_pairs.retain(|&(i, j)| groups[i] != groups[j]);
+        }
+        for &(i, j) in &block_pairs {
+            disjoint_set.union(i, j);
+        }
+        metrics.stats.similar_pairs += block_pairs.len();
+        println!("分块哈希比较完成，{} 对文件的相同块比例超过阈值 (累计耗时: {:?})", 
+                 block_pairs.len(), total_start_time.elapsed());
     }
+    metrics.timings.similarity_ms = similarity_calc_start_time.elapsed().as_millis() as u64;
     
-    // 从并查集构建组
+    // 从并查集构建连通分量，只保留实际重复（包含多张图像）的分量
     let mut group_map: HashMap<usize, Vec<usize>> = HashMap::new();
     for i in 0..hashes.len() {
         let root = disjoint_set.find(i);
         group_map.entry(root).or_insert_with(Vec::new).push(i);
     }
     
-    // 过滤并构建最终的重复组
-    let mut groups = Vec::new();
+    Ok(group_map.into_values()
+        .filter(|indices| indices.len() > 1)
+        .collect())
+}
+
+/// 按文件分块哈希寻找相同块比例不低于`threshold`的文件对
+///
+/// 用块哈希建立倒排索引，只比较至少有一个相同块的文件；包含文件数超过`max_bucket_size`的块
+/// （例如全零填充块）不产生候选对。无法读取的文件不参与比较。
+fn block_match_pairs(paths: &[PathBuf], threshold: f32, max_bucket_size: Option<usize>) -> Vec<(usize, usize)> {
+    let blocks: Vec<Vec<u64>> = paths.par_iter()
+        .map(|path| hash_utils::compute_file_block_hashes(path, hash_utils::DEFAULT_FILE_BLOCK_SIZE).unwrap_or_default())
+        .collect();
+    
+    let mut index: HashMap<u64, Vec<usize>> = HashMap::new();
+    for (i, file_blocks) in blocks.iter().enumerate() {
+        // 同一文件内重复的块只记录一次
+        for block in file_blocks.iter().collect::<HashSet<_>>() {
+            index.entry(*block).or_default().push(i);
+        }
+    }
     
-    for (_, indices) in group_map.iter() {
-        // 只处理大于1的组（实际重复）
-        if indices.len() <= 1 {
+    let mut candidates: HashSet<(usize, usize)> = HashSet::new();
+    for files in index.values() {
+        if files.len() < 2 || max_bucket_size.is_some_and(|max| files.len() > max) {
             continue;
         }
+        for (k, &i) in files.iter().enumerate() {
+            candidates.extend(files[k + 1..].iter().map(|&j| (i, j)));
+        }
+    }
+    
+    let mut pairs: Vec<(usize, usize)> = candidates.into_par_iter()
+        .filter(|&(i, j)| hash_utils::block_hash_similarity(&blocks[i], &blocks[j]) >= threshold)
+        .collect();
+    pairs.sort_unstable();
+    pairs
+}
+
+/// 为分组提供图像信息，使分组逻辑不依赖文件系统
+pub trait ImageMetadataSource: Sync {
+    /// 返回第`index`张图像的信息，无法获取时返回`None`，该图像不会出现在结果中
+    fn image_info(&self, index: usize) -> Option<ImageInfo>;
+}
+
+/// 内存中的图像信息，按索引直接返回
+impl ImageMetadataSource for [ImageInfo] {
+    fn image_info(&self, index: usize) -> Option<ImageInfo> {
+        self.get(index).cloned()
+    }
+}
+
+/// 从文件系统读取元数据的图像信息来源，按需生成缩略图
+struct FileMetadataSource<'a> {
+    paths: &'a [PathBuf],
+    root_indices: &'a [usize],
+    hashes: &'a [HashResult],
+    params: &'a DuplicateDetectionParams,
+}
+
+impl ImageMetadataSource for FileMetadataSource<'_> {
+    fn image_info(&self, index: usize) -> Option<ImageInfo> {
+        let path = &self.paths[index];
+        let hash_result = &self.hashes[index];
+        let (size_bytes, created_at, modified_at) = get_file_metadata(path).ok()?;
+        let thumbnail_size = self.params.thumbnail_size.filter(|_| self.params.include_thumbnails);
         
-        // 收集组内所有图像信息
-        let images: Vec<ImageInfo> = indices.par_iter()
-            .filter_map(|&idx| {
-                let path = &paths[idx];
-                let hash_result = &hashes[idx];
-                
-                match get_file_metadata(path) {
-                    Ok((size_bytes, created_at, modified_at)) => {
-                        Some(ImageInfo {
-                            path: path.to_string_lossy().into_owned(),
-                            hash: hash_result.hash.clone(),
-                            width: hash_result.width,
-                            height: hash_result.height,
-                            size_bytes,
-                            created_at,
-                            modified_at,
-                        })
-                    },
-                    Err(_) => None
-                }
-            })
-            .collect();
-        
-        // 如果组内有多个有效图像，添加到结果中
-        if images.len() > 1 {
-            groups.push(DuplicateGroup {
-                images,
-                similarity_threshold: threshold,
-            });
+        Some(ImageInfo {
+            path: path.to_string_lossy().into_owned(),
+            hash: hash_result.hash.clone(),
+            algorithm: hash_result.algorithm,
+            width: hash_result.width,
+            height: hash_result.height,
+            size_bytes,
+            created_at,
+            modified_at,
+            source_root: self.params.folders[self.root_indices[index]].to_string_lossy().into_owned(),
+            thumbnail: thumbnail_size
+                .and_then(|size| image_utils::generate_thumbnail(path, size).ok()),
+        })
+    }
+}
+
+/// 对内存中的哈希值分组，返回与检测命令相同规则得到的重复组
+///
+/// `hashes`与`images`一一对应，结果中每张图像的`hash`和`algorithm`取自传入的哈希和`params.algorithm`，
+/// 其余图像信息直接取自`images`，不读取文件元数据：
+/// 宽高用于宽高比过滤，`source_root`与`params.folders`匹配后用于仅跨文件夹模式
+/// （不在其中的图像归为同一个额外的文件夹）。
+/// 只有二次确认（`confirm_with`）和逐字节确认（`verify_bytes`）仍会按`images`中的路径读取文件。
+/// 非空哈希的格式与`params.algorithm`不符（例如把ORB特征当作感知哈希传入）时返回错误。
+pub fn find_duplicate_groups(
+    hashes: &[String],
+    images: &[ImageInfo],
+    params: &DuplicateDetectionParams
+) -> Result<Vec<DuplicateGroup>, String> {
+    if hashes.len() != images.len() {
+        return Err(format!("哈希值({})与图像信息({})数量不匹配", hashes.len(), images.len()));
+    }
+
+    let owners: Vec<usize> = (0..hashes.len()).collect();
+    group_owned_hashes(hashes, &owners, images, params)
+}
+
+/// 对每张图像带有多个哈希的情况分组，规则与`find_duplicate_groups`相同
+///
+/// `hashes[i]`是第`i`张图像的全部哈希（例如旋转/翻转变体或视频的多个帧），
+/// 两张图像的任意一对哈希超过阈值即视为相似（相当于取所有交叉配对的最大相似度）。
+/// 同一张图像自身的哈希之间不做比较；
+/// 没有任何哈希的图像不会出现在结果中。
+pub fn find_duplicate_groups_multi(
+    hashes: &[Vec<String>],
+    images: &[ImageInfo],
+    params: &DuplicateDetectionParams
+) -> Result<Vec<DuplicateGroup>, String> {
+    if hashes.len() != images.len() {
+        return Err(format!("哈希组({})与图像信息({})数量不匹配", hashes.len(), images.len()));
+    }
+    
+    // 展开为每个哈希一项，记录所属图像
+    let owners: Vec<usize> = hashes.iter()
+        .enumerate()
+        .flat_map(|(owner, image_hashes)| std::iter::repeat(owner).take(image_hashes.len()))
+        .collect();
+    let flat_hashes: Vec<String> = hashes.iter().flatten().cloned().collect();
+    
+    group_owned_hashes(&flat_hashes, &owners, images, params)
+}
+
+/// 对展开后的哈希分组，`owners[k]`是第`k`个哈希所属图像在`images`中的索引
+///
+/// 先按哈希求连通分量，再把属于同一张图像的哈希所在的分量合并，得到图像级别的分组。
+/// 取所有交叉配对的最大相似度等价于只要有一对超过阈值就连边，因此连通性与逐图像比较一致。
+fn group_owned_hashes(
+    hashes: &[String],
+    owners: &[usize],
+    images: &[ImageInfo],
+    params: &DuplicateDetectionParams
+) -> Result<Vec<DuplicateGroup>, String> {
+    // 传入的哈希可能来自缓存或导出文件，格式与`params.algorithm`不符时明确报错，而不是得到无意义的相似度
+    for (hash, &owner) in hashes.iter().zip(owners) {
+        if !hash.is_empty() {
+            algorithms::validate_hash(hash, params.algorithm)
+                .map_err(|e| format!("{}: {}", images[owner].path, e))?;
         }
     }
     
-    let grouping_time = grouping_start_time.elapsed();
-    let total_elapsed = total_start_time.elapsed();
-    println!("图片分组时间: {:?}, 共形成 {} 组重复图片 (累计耗时: {:?})", 
-             grouping_time, groups.len(), total_elapsed);
+    let paths: Vec<PathBuf> = owners.iter().map(|&owner| PathBuf::from(&images[owner].path)).collect();
+    let root_indices: Vec<usize> = owners.iter()
+        .map(|&owner| params.folders.iter()
+            .position(|folder| folder.to_string_lossy() == images[owner].source_root)
+            .unwrap_or(params.folders.len()))
+        .collect();
+    let hash_results: Vec<HashResult> = hashes.iter().zip(owners)
+        .map(|(hash, &owner)| HashResult {
+            hash: hash.clone(),
+            algorithm: params.algorithm,
+            color_signature: None,
+            width: images[owner].width,
+            height: images[owner].height,
+        })
+        .collect();
+    
+    let mut metrics = RunMetrics::default();
+    let hash_components = find_duplicate_components(
+        &paths,
+        &root_indices,
+        &hash_results,
+        None,
+        params,
+        &mut metrics,
+        Instant::now()
+    )?;
+    
+    // 合并到图像级别：同一张图像的多个哈希可能落在不同分量中，这些分量属于同一组
+    let mut disjoint_set = DisjointSet::new(images.len());
+    for component in &hash_components {
+        for pair in component.windows(2) {
+            disjoint_set.union(owners[pair[0]], owners[pair[1]]);
+        }
+    }
+    let mut group_map: HashMap<usize, Vec<usize>> = HashMap::new();
+    for owner in hash_components.iter().flatten().map(|&k| owners[k]).collect::<HashSet<_>>() {
+        let root = disjoint_set.find(owner);
+        group_map.entry(root).or_insert_with(Vec::new).push(owner);
+    }
+    let components: Vec<Vec<usize>> = group_map.into_values()
+        .filter(|indices| indices.len() > 1)
+        .map(|mut indices| {
+            indices.sort_unstable();
+            indices
+        })
+        .collect();
+    
+    // 输出的图像信息带上传入的哈希；一张图像有多个哈希时取第一个（倒序覆盖，先出现的最后写入）
+    let mut hashed_images = images.to_vec();
+    for (hash, &owner) in hashes.iter().zip(owners).rev() {
+        hashed_images[owner].hash = hash.clone();
+        hashed_images[owner].algorithm = params.algorithm;
+    }
+    
+    let mut groups = build_duplicate_groups(components, hashed_images.as_slice(), params, &NoProgress);
+    finalize_groups(&mut groups, params);
     
     Ok(groups)
 }
 
+/// 按组大小排序（最大的组在最前面，大小相同时可释放空间大的在前），再按`max_groups`截断
+///
+/// 返回截断前的组数
+fn finalize_groups(groups: &mut Vec<DuplicateGroup>, params: &DuplicateDetectionParams) -> usize {
+    groups.sort_by(|a, b| b.images.len().cmp(&a.images.len())
+        .then(b.wasted_bytes.cmp(&a.wasted_bytes)));
+    
+    // 限制返回的组数，避免病态输入产生过大的结果
+    let total = groups.len();
+    if let Some(max_groups) = params.max_groups {
+        if total > max_groups {
+            println!("结果共 {} 组，只返回前 {} 组", total, max_groups);
+            groups.truncate(max_groups);
+        }
+    }
+    
+    total
+}
+
+/// 为每个连通分量收集图像信息并构建最终的重复组，每完成一组立即通过`progress.group_found`报告
+///
+/// 逐字节确认、可释放空间和文件名过滤都只涉及单个组，在这里逐组完成，
+/// 因此报告的组与最终返回的组一致，只是顺序不同（最终结果还会按大小排序）。
+/// 最终结果按`max_groups`截断时不一致，调用方此时应传入`NoProgress`，截断后再报告。
+/// 过滤必须在连通分量确定之后进行，否则会打断传递关系形成的组。
+fn build_duplicate_groups<M: ImageMetadataSource + ?Sized>(
+    components: Vec<Vec<usize>>,
+    metadata: &M,
+    params: &DuplicateDetectionParams,
+    progress: &dyn ProgressReporter
+) -> Vec<DuplicateGroup> {
+    // 可选的逐字节确认，只适用于精确哈希得到的组
+    let verify_bytes = params.verify_bytes
+        && (params.algorithm == HashAlgorithm::Exact || params.confirm_with == Some(HashAlgorithm::Exact));
+    let filtered_out = AtomicUsize::new(0);
+    
+    let groups: Vec<DuplicateGroup> = components.into_par_iter()
+        .flat_map_iter(|indices| {
+            // 收集组内所有图像信息（按需在同一个并行循环中生成缩略图）
+            let mut images: Vec<ImageInfo> = indices.par_iter()
+                .filter_map(|&idx| metadata.image_info(idx))
+                .collect();
+            
+            // 缩放副本模式下按像素数从大到小排列，第一张即分辨率最高的版本
+            if params.algorithm == HashAlgorithm::ScaledDuplicate {
+                images.sort_by_key(|image| std::cmp::Reverse(image.width as u64 * image.height as u64));
+            }
+            
+            // 组内只剩一张有效图像时不构成重复
+            let image_sets = if images.len() <= 1 {
+                Vec::new()
+            } else if verify_bytes {
+                split_by_file_bytes(images)
+            } else {
+                vec![images]
+            };
+            
+            let mut finished = Vec::with_capacity(image_sets.len());
+            for images in image_sets {
+                let group = DuplicateGroup::new(images, params.threshold);
+                let keep = group.wasted_bytes >= params.min_wasted_bytes
+                    && (!params.only_different_names || group.has_different_names());
+                if keep {
+                    progress.group_found(&group);
+                    finished.push(group);
+                } else {
+                    filtered_out.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+            finished
+        })
+        .collect();
+    
+    let filtered_out = filtered_out.into_inner();
+    if filtered_out > 0 {
+        println!("按可释放空间和文件名过滤，移除 {} 组", filtered_out);
+    }
+    
+    groups
+}
+
+/// 分组时每批处理的候选对数量
+///
+/// 批越小，越早发现两端已连通的候选对；批越大，并行度越高。
+const GROUPING_BATCH_SIZE: usize = 4096;
+
 /// 并查集数据结构，用于高效地构建连通分量
 struct DisjointSet {
     parent: Vec<usize>,
@@ -363,29 +1537,15 @@ pub fn are_images_duplicates(
     
     // 检查文件大小（可选的快速过滤）
     if let (Ok(metadata1), Ok(metadata2)) = (fs::metadata(img1_path), fs::metadata(img2_path)) {
-        // 如果文件大小差异超过50%，则不太可能是重复的（可选性能优化）
-        if metadata1.len() > 0 && metadata2.len() > 0 {
-            let size_ratio = if metadata1.len() > metadata2.len() {
-                metadata1.len() as f64 / metadata2.len() as f64
-            } else {
-                metadata2.len() as f64 / metadata1.len() as f64
-            };
-            
-            // 对于精确匹配算法，文件大小必须完全相同
-            if algorithm == HashAlgorithm::Exact && metadata1.len() != metadata2.len() {
-                return Ok(false);
-            }
-            
-            // 对于其他算法，如果大小差异过大，可能不是重复的
-            if algorithm != HashAlgorithm::Exact && size_ratio > 2.0 {
-                return Ok(false);
-            }
+        if !sizes_compatible(algorithm, metadata1.len(), metadata2.len()) {
+            return Ok(false);
         }
     }
     
     // 计算两张图片的哈希值
-    let hash1 = algorithms::calculate_hash(img1_path, algorithm)?;
-    let hash2 = algorithms::calculate_hash(img2_path, algorithm)?;
+    let options = HashOptions::default();
+    let hash1 = algorithms::calculate_hash(img1_path, algorithm, &options)?;
+    let hash2 = algorithms::calculate_hash(img2_path, algorithm, &options)?;
     
     // 计算相似度
     let similarity = algorithms::calculate_similarity(&hash1.hash, &hash2.hash, algorithm);
@@ -393,17 +1553,555 @@ pub fn are_images_duplicates(
     Ok(similarity >= threshold)
 }
 
+/// 图像对的索引及其相似度
+type ScoredPair = ((usize, usize), f32);
+
+/// 使用第二种算法确认候选对
+///
+/// 只为出现在`pairs`中的图像计算确认算法的哈希；无法计算哈希的图像所在的对无法确认，会被丢弃。
+/// 返回的对保留主算法的相似度。
+fn confirm_pairs(
+    pairs: Vec<ScoredPair>,
+    paths: &[PathBuf],
+    confirm_algorithm: HashAlgorithm,
+    params: &DuplicateDetectionParams,
+    total_start_time: Instant
+) -> Result<Vec<ScoredPair>, String> {
+    if pairs.is_empty() {
+        return Ok(pairs);
+    }
+    
+    let threshold = params.confirm_threshold.unwrap_or_else(|| confirm_algorithm.default_threshold());
+    
+    // 只对候选对中涉及的图像计算确认哈希
+    let involved: Vec<usize> = pairs.iter()
+        .flat_map(|&((i, j), _)| [i, j])
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    let involved_paths: Vec<PathBuf> = involved.iter().map(|&idx| paths[idx].clone()).collect();
+    
+    let confirm_hashes = compute_image_hashes(
+        &involved_paths,
+        confirm_algorithm,
+        &params.hash_options(),
+        &params.config,
+        &NoProgress,
+        total_start_time
+    );
+    
+    let hash_by_index: HashMap<usize, String> = involved.into_iter()
+        .zip(confirm_hashes)
+        .filter_map(|(idx, hash)| Some((idx, hash?.ok()?.hash)))
+        .collect();
+    
+    Ok(pairs.into_par_iter()
+        .filter(|&((i, j), _)| match (hash_by_index.get(&i), hash_by_index.get(&j)) {
+            (Some(hash1), Some(hash2)) => {
+                algorithms::calculate_similarity(hash1, hash2, confirm_algorithm) >= threshold
+            },
+            _ => false,
+        })
+        .collect())
+}
+
+/// 根据文件大小判断两张图像是否可能重复
+///
+/// 对于精确匹配算法，文件大小必须完全相同；对于其他算法，
+/// 大小相差超过一倍时认为不太可能是重复的；缩放副本的文件大小与分辨率有关，不做判断。
+/// 大小未知（为0）时不做判断。
+fn sizes_compatible(algorithm: HashAlgorithm, size1: u64, size2: u64) -> bool {
+    if size1 == 0 || size2 == 0 {
+        return true;
+    }
+    
+    match algorithm {
+        HashAlgorithm::Exact => return size1 == size2,
+        // 不同分辨率的版本文件大小可以相差几十倍
+        HashAlgorithm::ScaledDuplicate => return true,
+        _ => {},
+    }
+    
+    let (larger, smaller) = if size1 > size2 { (size1, size2) } else { (size2, size1) };
+    larger as f64 / smaller as f64 <= 2.0
+}
+
+/// 把64位二值哈希打包为`u64`，只适用于按比特比较的算法
+///
+/// 任一哈希无法打包（例如混有视频的关键帧哈希）时返回None，整批回退到字符串比较，
+/// 避免同一次检测中两种表示混用。
+fn pack_binary_hashes(hashes: &[HashResult], algorithm: HashAlgorithm) -> Option<Vec<u64>> {
+    if !matches!(algorithm, HashAlgorithm::Average | HashAlgorithm::Difference |
+                            HashAlgorithm::Perceptual | HashAlgorithm::EdgeHash |
+                            HashAlgorithm::ScaledDuplicate) {
+        return None;
+    }
+    
+    hashes.par_iter()
+        .map(|result| hash_utils::binary_to_u64(&result.hash))
+        .collect()
+}
+
+/// 预先计算每个二值哈希中1的个数，只适用于按比特比较的算法
+///
+/// 长度与算法比特数不符的哈希（例如视频的关键帧哈希）对应None，不参与预过滤。
+fn binary_popcounts(hashes: &[String], algorithm: HashAlgorithm) -> Option<Vec<Option<u32>>> {
+    if !matches!(algorithm, HashAlgorithm::Average | HashAlgorithm::Difference |
+                            HashAlgorithm::Perceptual | HashAlgorithm::EdgeHash |
+                            HashAlgorithm::ScaledDuplicate) {
+        return None;
+    }
+    
+    let bits = algorithm.bit_length()? as usize;
+    Some(hashes.par_iter()
+        .map(|hash| (hash.len() == bits).then(|| crate::core::utils::popcount(hash)))
+        .collect())
+}
+
+/// 根据原始宽高比判断两张图像是否可能重复
+///
+/// 两个宽高比中较大者与较小者之比不超过`max_factor`时视为兼容。尺寸未知（为0）时不做判断。
+fn aspect_ratios_compatible(a: &HashResult, b: &HashResult, max_factor: f32) -> bool {
+    if a.width == 0 || a.height == 0 || b.width == 0 || b.height == 0 {
+        return true;
+    }
+    
+    let ratio1 = a.width as f32 / a.height as f32;
+    let ratio2 = b.width as f32 / b.height as f32;
+    ratio1.max(ratio2) / ratio1.min(ratio2) <= max_factor
+}
+
+/// 按文件大小预过滤图像，返回保留下来的图像的文件大小
+///
+/// 只读取文件元数据，不解码图像。对于精确匹配算法，大小唯一的文件不可能与其他文件重复，
+/// 直接从`paths`和`root_indices`中移除；其他算法保留所有文件，返回的大小用于过滤候选对。
+fn apply_size_prefilter(
+    paths: &mut Vec<PathBuf>,
+    root_indices: &mut Vec<usize>,
+    algorithm: HashAlgorithm
+) -> Vec<u64> {
+    let sizes: Vec<u64> = paths.par_iter()
+        .map(|path| fs::metadata(path).map(|m| m.len()).unwrap_or(0))
+        .collect();
+    
+    if algorithm != HashAlgorithm::Exact {
+        return sizes;
+    }
+    
+    // 统计每个大小出现的次数
+    let mut size_counts: HashMap<u64, usize> = HashMap::new();
+    for &size in &sizes {
+        *size_counts.entry(size).or_insert(0) += 1;
+    }
+    
+    // 保留大小未知或与其他文件大小相同的图像
+    let keep: Vec<bool> = sizes.iter()
+        .map(|size| *size == 0 || size_counts[size] > 1)
+        .collect();
+    
+    let mut sizes = sizes;
+    retain_by_mask(&mut sizes, &keep);
+    retain_by_mask(paths, &keep);
+    retain_by_mask(root_indices, &keep);
+    sizes
+}
+
+/// 按图像尺寸预过滤，移除长边小于`min_dimension`的图像
+///
+/// 只读取文件头，不解码图像；无法读取尺寸的文件保留，由哈希阶段报告具体错误。
+fn apply_dimension_prefilter(
+    paths: &mut Vec<PathBuf>,
+    root_indices: &mut Vec<usize>,
+    file_sizes: Option<&mut Vec<u64>>,
+    min_dimension: u32
+) {
+    let keep: Vec<bool> = paths.par_iter()
+        .map(|path| match image_utils::read_dimensions(path) {
+            Ok((width, height)) => width.max(height) >= min_dimension,
+            Err(_) => true,
+        })
+        .collect();
+    
+    retain_by_mask(paths, &keep);
+    retain_by_mask(root_indices, &keep);
+    if let Some(sizes) = file_sizes {
+        retain_by_mask(sizes, &keep);
+    }
+}
+
+/// 扫描阶段每遍历多少个文件报告一次进度
+const SCAN_PROGRESS_INTERVAL: usize = 500;
+
 /// 获取所有文件夹中的图像路径
+///
+/// 各个根目录并行扫描，合并后按规范路径去重，
+/// 避免重叠的根目录导致同一文件被重复处理。
 pub fn get_all_image_paths(
     folders: &[PathBuf],
-    recursive: bool
+    options: &ScanOptions
 ) -> Result<Vec<PathBuf>, String> {
+    Ok(get_all_image_paths_with_roots(folders, options, &NoProgress)?
+        .0
+        .into_iter()
+        .map(|(_, path)| path)
+        .collect())
+}
+
+/// 扫描得到的(根目录索引, 图像路径)，以及扫描时无法读取的路径
+type ScannedPaths = (Vec<(usize, PathBuf)>, Vec<FileError>);
+
+/// 获取所有文件夹中的图像路径，并附带每个路径所属根目录在`folders`中的索引
+///
+/// 遍历过程中每处理`SCAN_PROGRESS_INTERVAL`个文件通过`progress`报告一次进度。
+/// 同时返回扫描中无法读取的目录，这些目录被跳过，扫描继续进行。
+pub fn get_all_image_paths_with_roots(
+    folders: &[PathBuf],
+    options: &ScanOptions,
+    progress: &dyn ProgressReporter
+) -> Result<ScannedPaths, String> {
+    let roots = dedup_scan_roots(folders, options);
+    
+    // 各个根目录并行扫描，共享计数
+    let scanned_files = AtomicUsize::new(0);
+    let image_files = AtomicUsize::new(0);
+    let on_file = |is_image: bool| {
+        if is_image {
+            image_files.fetch_add(1, Ordering::Relaxed);
+        }
+        let scanned = scanned_files.fetch_add(1, Ordering::Relaxed) + 1;
+        if scanned.is_multiple_of(SCAN_PROGRESS_INTERVAL) {
+            progress.scan_progress(&ScanProgress {
+                scanned_files: scanned,
+                image_files: image_files.load(Ordering::Relaxed),
+                finished: false,
+            });
+        }
+    };
+    let scan_errors = Mutex::new(Vec::new());
+    let on_error = |error: FileError| {
+        println!("扫描时跳过无法读取的路径 {}: {}", error.path, error.reason);
+        if let Ok(mut errors) = scan_errors.lock() {
+            errors.push(error);
+        }
+    };
+    
+    // 并行扫描每个文件夹，同时计算规范路径用于去重
+    // 通过符号链接访问到的文件会被解析为其真实路径
+    let per_folder: Vec<Vec<(usize, PathBuf, PathBuf)>> = roots
+        .par_iter()
+        .map(|&root_idx| {
+            let paths = get_image_paths_with_progress(&folders[root_idx], options, &on_file, &on_error)?;
+            Ok(paths.into_iter()
+                .map(|path| {
+                    let canonical = path.canonicalize().unwrap_or_else(|_| path.clone());
+                    (root_idx, canonical, path)
+                })
+                .collect())
+        })
+        .collect::<Result<_, String>>()?;
+    
+    // 按文件夹顺序合并，保留第一次出现的路径
+    let mut seen = HashSet::new();
     let mut all_paths = Vec::new();
+    let mut duplicate_count = 0;
+    let mut excluded_count = 0;
+    
+    for (root_idx, canonical, path) in per_folder.into_iter().flatten() {
+        if options.excluded_files.contains(&canonical) {
+            excluded_count += 1;
+        } else if seen.insert(canonical) {
+            all_paths.push((root_idx, path));
+        } else {
+            duplicate_count += 1;
+        }
+    }
+    
+    println!("扫描 {} 个文件夹完成，共 {} 张图片 (去除重复路径 {} 个，明确排除 {} 个)", 
+             roots.len(), all_paths.len(), duplicate_count, excluded_count);
+    
+    progress.scan_progress(&ScanProgress {
+        scanned_files: scanned_files.load(Ordering::Relaxed),
+        image_files: all_paths.len(),
+        finished: true,
+    });
+    
+    let scan_errors = scan_errors.into_inner().unwrap_or_default();
+    Ok((all_paths, scan_errors))
+}
+
+/// 去除重叠的扫描根目录，返回需要扫描的根目录索引
+///
+/// 按规范路径比较：相同的根目录只保留一个；递归扫描时，
+/// 位于另一个根目录之下的根目录会被整体跳过（例如同时选择了`/photos`和`/photos/2023`）。
+/// 外层根目录的隐藏项或排除规则会跳过内层根目录时，内层根目录仍单独扫描，
+/// 用户明确选择的文件夹不会因此消失。
+fn dedup_scan_roots(folders: &[PathBuf], options: &ScanOptions) -> Vec<usize> {
+    let canonical_roots: Vec<PathBuf> = folders.iter()
+        .map(|folder| folder.canonicalize().unwrap_or_else(|_| folder.clone()))
+        .collect();
+    
+    let mut roots = Vec::with_capacity(folders.len());
+    let mut seen = HashSet::new();
+    
+    for (i, folder) in folders.iter().enumerate() {
+        let canonical = &canonical_roots[i];
+        
+        if !seen.insert(canonical.clone()) {
+            continue;
+        }
+        
+        let covered = options.recursive && canonical_roots.iter()
+            .any(|other| other != canonical && canonical.starts_with(other)
+                && reaches_nested_root(other, canonical, options));
+        
+        if covered {
+            println!("跳过重叠的文件夹: {}", folder.display());
+            continue;
+        }
+        
+        roots.push(i);
+    }
+    
+    roots
+}
+
+/// 检查从外层根目录递归扫描时是否会进入内层根目录
+///
+/// 遍历时被跳过的目录不会再向下展开，因此两者之间的每一级目录都不能被跳过。
+fn reaches_nested_root(outer: &Path, nested: &Path, options: &ScanOptions) -> bool {
+    nested.ancestors()
+        .take_while(|dir| *dir != outer)
+        .all(|dir| !options.is_excluded(outer, dir))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use image::{DynamicImage, ImageFormat, RgbImage};
+    
+    /// 在系统临时目录下创建一个本测试独占的空目录
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("delo-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+    
+    #[test]
+    fn reports_every_failure_when_no_image_decodes() {
+        let dir = scratch_dir("all-failed");
+        fs::write(dir.join("empty.png"), b"").unwrap();
+        
+        let mut jpeg = Vec::new();
+        DynamicImage::ImageRgb8(RgbImage::new(64, 64))
+            .write_to(&mut Cursor::new(&mut jpeg), ImageFormat::Jpeg)
+            .unwrap();
+        fs::write(dir.join("truncated.jpg"), &jpeg[..20]).unwrap();
+        
+        let params = DuplicateDetectionParams::new(vec![dir.clone()], HashAlgorithm::Perceptual, 90.0, false);
+        let results = detect_duplicates(&params).expect("全部失败时仍应返回结果");
+        fs::remove_dir_all(&dir).unwrap();
+        
+        assert!(results.groups.is_empty());
+        assert_eq!(results.stats.images_failed, 2);
+        let mut failed: Vec<String> = results.errors.iter()
+            .map(|error| Path::new(&error.path).file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        failed.sort();
+        assert_eq!(failed, ["empty.png", "truncated.jpg"]);
+        assert!(results.errors.iter().all(|error| error.decode_error.is_some()));
+    }
+    
+    #[test]
+    fn block_matching_groups_files_differing_in_one_block() {
+        let dir = scratch_dir("block-match");
+        // 512x512的24位BMP为768KB像素加文件头（13块），像素随机，各块内容互不相同
+        let mut rng = fastrand::Rng::with_seed(7);
+        let img = RgbImage::from_fn(512, 512, |_, _| image::Rgb([rng.u8(..), rng.u8(..), rng.u8(..)]));
+        let mut original = Vec::new();
+        DynamicImage::ImageRgb8(img)
+            .write_to(&mut Cursor::new(&mut original), ImageFormat::Bmp)
+            .unwrap();
+        let block_size = hash_utils::DEFAULT_FILE_BLOCK_SIZE;
+        assert_eq!(original.len().div_ceil(block_size), 13);
+        
+        // 只改写第6个64KB块（位于像素数据中），精确哈希随之不同
+        let mut edited = original.clone();
+        for byte in &mut edited[5 * block_size..6 * block_size] {
+            *byte = !*byte;
+        }
+        fs::write(dir.join("original.bmp"), &original).unwrap();
+        fs::write(dir.join("edited.bmp"), &edited).unwrap();
+        
+        let mut params = DuplicateDetectionParams::new(vec![dir.clone()], HashAlgorithm::Exact, 100.0, false);
+        let exact_only = detect_duplicates(&params).unwrap();
+        params.block_match_threshold = Some(90.0);
+        let with_blocks = detect_duplicates(&params).unwrap();
+        params.block_match_threshold = Some(95.0);
+        let strict_blocks = detect_duplicates(&params).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        
+        assert!(exact_only.groups.is_empty());
+        // 13块中12块相同（约92.3%）
+        assert_eq!(with_blocks.groups.len(), 1);
+        assert_eq!(with_blocks.groups[0].images.len(), 2);
+        assert!(strict_blocks.groups.is_empty());
+    }
+    
+    #[test]
+    fn hash_folder_lists_failed_files_alongside_hashes() {
+        let dir = scratch_dir("hash-folder");
+        DynamicImage::ImageRgb8(RgbImage::from_pixel(32, 32, image::Rgb([200, 30, 30])))
+            .save(dir.join("valid.png"))
+            .unwrap();
+        fs::write(dir.join("broken.png"), b"not a png").unwrap();
+        
+        let params = DuplicateDetectionParams::new(vec![dir.clone()], HashAlgorithm::Difference, 90.0, false);
+        let results = hash_folder(&params).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        
+        assert_eq!(results.images.len(), 1);
+        assert!(results.images[0].path.ends_with("valid.png"));
+        assert_eq!((results.images[0].width, results.images[0].height), (32, 32));
+        assert_eq!(results.errors.len(), 1);
+        assert!(results.errors[0].path.ends_with("broken.png"));
+        assert!(results.errors[0].decode_error.is_some());
+    }
     
-    for folder in folders {
-        let mut paths = get_image_paths(folder, recursive)?;
-        all_paths.append(&mut paths);
+    /// 只填写分组需要的字段，哈希留空，由`find_duplicate_groups`按传入的哈希填写
+    fn image_info(path: &str, size_bytes: u64) -> ImageInfo {
+        ImageInfo {
+            path: path.to_string(),
+            hash: String::new(),
+            algorithm: HashAlgorithm::Exact,
+            width: 100,
+            height: 100,
+            size_bytes,
+            created_at: String::new(),
+            modified_at: String::new(),
+            source_root: String::new(),
+            thumbnail: None,
+        }
+    }
+    
+    #[test]
+    fn find_duplicate_groups_fills_hashes_from_input() {
+        let near = "0".repeat(64);
+        let mut near_variant = near.clone();
+        near_variant.replace_range(..1, "1");
+        let far = "01".repeat(32);
+        let hashes = vec![near.clone(), far, near_variant.clone()];
+        let images = vec![image_info("/a.png", 300), image_info("/b.png", 200), image_info("/c.png", 100)];
+        
+        let params = DuplicateDetectionParams::new(Vec::new(), HashAlgorithm::Difference, 90.0, false);
+        let groups = find_duplicate_groups(&hashes, &images, &params).unwrap();
+        
+        assert_eq!(groups.len(), 1);
+        let mut grouped: Vec<(&str, &str, HashAlgorithm)> = groups[0].images.iter()
+            .map(|image| (image.path.as_str(), image.hash.as_str(), image.algorithm))
+            .collect();
+        grouped.sort_by_key(|(path, _, _)| *path);
+        assert_eq!(grouped, [
+            ("/a.png", near.as_str(), HashAlgorithm::Difference),
+            ("/c.png", near_variant.as_str(), HashAlgorithm::Difference),
+        ]);
+    }
+    
+    #[test]
+    fn split_by_file_bytes_streams_and_confirms_contents() {
+        let dir = scratch_dir("split-bytes");
+        // 大于一块的内容，只在最后一块不同的文件也必须被区分
+        let mut rng = fastrand::Rng::with_seed(386);
+        let content: Vec<u8> = (0..hash_utils::DEFAULT_FILE_BLOCK_SIZE * 3 + 17).map(|_| rng.u8(..)).collect();
+        let mut tail_changed = content.clone();
+        *tail_changed.last_mut().unwrap() ^= 0xff;
+        
+        let files: [(&str, &[u8]); 5] = [
+            ("a.bin", &content),
+            ("b.bin", &content),
+            ("c.bin", &tail_changed),
+            ("d.bin", &tail_changed),
+            ("e.bin", &content[..content.len() - 1]),
+        ];
+        let mut images = Vec::new();
+        for (name, data) in files {
+            let path = dir.join(name);
+            fs::write(&path, data).unwrap();
+            images.push(image_info(&path.to_string_lossy(), data.len() as u64));
+        }
+        // 大小相同但无法读取的文件被丢弃
+        images.push(image_info(&dir.join("missing.bin").to_string_lossy(), content.len() as u64));
+        
+        let groups = split_by_file_bytes(images);
+        // 摘要不同的文件不会进入逐块比较，这里单独确认最后一块的差异能被发现
+        assert!(hash_utils::files_identical(&dir.join("a.bin"), &dir.join("b.bin")).unwrap());
+        assert!(!hash_utils::files_identical(&dir.join("a.bin"), &dir.join("c.bin")).unwrap());
+        assert!(!hash_utils::files_identical(&dir.join("a.bin"), &dir.join("e.bin")).unwrap());
+        fs::remove_dir_all(&dir).unwrap();
+        
+        let names: Vec<Vec<String>> = groups.iter()
+            .map(|group| group.iter()
+                .map(|image| Path::new(&image.path).file_name().unwrap().to_string_lossy().into_owned())
+                .collect())
+            .collect();
+        assert_eq!(names, [["a.bin", "b.bin"], ["c.bin", "d.bin"]]);
     }
     
-    Ok(all_paths)
-}
\ No newline at end of file
+    #[test]
+    fn prefiltered_run_keeps_scan_metrics() {
+        let dir = scratch_dir("prefiltered");
+        for name in ["a.png", "b.png"] {
+            DynamicImage::ImageRgb8(RgbImage::new(16, 16)).save(dir.join(name)).unwrap();
+        }
+        
+        let mut params = DuplicateDetectionParams::new(vec![dir.clone()], HashAlgorithm::Average, 90.0, false);
+        params.min_dimension = Some(64);
+        let results = detect_duplicates(&params).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        
+        assert!(results.groups.is_empty());
+        assert_eq!(results.stats.images_scanned, 2);
+        assert_eq!(results.stats.images_hashed, 0);
+    }
+    
+    #[test]
+    fn find_duplicate_groups_rejects_hashes_from_another_algorithm() {
+        let phash = "01".repeat(32);
+        let images = [image_info("/a.png", 100), image_info("/b.png", 100)];
+        let params = DuplicateDetectionParams::new(Vec::new(), HashAlgorithm::Perceptual, 90.0, false);
+        
+        // SHA-256不是64位二值哈希
+        let sha = "ab".repeat(32);
+        let error = find_duplicate_groups(&[phash.clone(), sha], &images, &params).unwrap_err();
+        assert!(error.starts_with("/b.png: "), "{}", error);
+        
+        assert_eq!(find_duplicate_groups(&[phash.clone(), phash], &images, &params).unwrap().len(), 1);
+    }
+    
+    #[test]
+    fn nested_roots_survive_the_outer_roots_filters() {
+        let dir = scratch_dir("nested-roots");
+        let subfolders = [".hidden", "skipped", "kept"];
+        for name in subfolders {
+            fs::create_dir(dir.join(name)).unwrap();
+            DynamicImage::ImageRgb8(RgbImage::new(8, 8)).save(dir.join(name).join("a.png")).unwrap();
+        }
+        
+        let mut folders = vec![dir.clone()];
+        folders.extend(subfolders.iter().map(|name| dir.join(name)));
+        let options = ScanOptions {
+            recursive: true,
+            exclude: build_glob_set(&["skipped".to_string()]).unwrap(),
+            skip_hidden: true,
+            ..Default::default()
+        };
+        let roots = dedup_scan_roots(&folders, &options);
+        let paths = get_all_image_paths(&folders, &options).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        
+        // 外层根目录会跳过隐藏和排除的子文件夹，只有"kept"被外层覆盖
+        assert_eq!(roots, [0, 1, 2]);
+        assert_eq!(paths.len(), 3);
+    }
+}
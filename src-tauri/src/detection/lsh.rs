@@ -1,7 +1,8 @@
 use std::collections::{HashMap, HashSet};
 use rayon::prelude::*;
+use crate::algorithms::orb;
 use crate::core::types::HashAlgorithm;
-use crate::core::utils::hash_utils::split_hash_for_lsh;
+use crate::core::utils::hash_utils::{split_hash_for_lsh, splitmix64};
 
 /// LSH (局部敏感哈希) 索引
 /// 使用多个桶来存储哈希值，相似的哈希值会被分配到相同的桶中
@@ -14,7 +15,12 @@ pub struct LSHIndex {
     /// 算法类型
     algorithm: HashAlgorithm,
     /// 每个桶的最大索引数量，防止热点桶
+    ///
+    /// 桶满后按索引的伪随机优先级保留样本（见`sample_priority`），
+    /// 保留哪些图像与插入顺序无关，召回率不依赖扫描顺序。
     max_bucket_size: usize,
+    /// 已添加的不同索引，使`len`无需遍历所有桶
+    indices: HashSet<usize>,
 }
 
 impl LSHIndex {
@@ -27,6 +33,8 @@ impl LSHIndex {
             HashAlgorithm::Average => (4, 2000),   // 均值哈希使用中等大小
             HashAlgorithm::Difference => (4, 2000), // 差值哈希使用中等大小
             HashAlgorithm::Perceptual => (6, 2000), // 感知哈希使用较多的段
+            HashAlgorithm::EdgeHash => (4, 2000),   // 边缘哈希使用中等大小
+            HashAlgorithm::ScaledDuplicate => (6, 2000), // 与感知哈希相同
         };
         
         Self {
@@ -34,40 +42,41 @@ impl LSHIndex {
             bands,
             algorithm,
             max_bucket_size,
+            indices: HashSet::new(),
         }
     }
     
+    /// 设置每个桶的最大索引数量，代替算法的默认值
+    pub fn with_max_bucket_size(mut self, max_bucket_size: usize) -> Self {
+        self.max_bucket_size = max_bucket_size.max(1);
+        self
+    }
+    
     /// 添加哈希值到索引中
     pub fn add(&mut self, hash: &str, index: usize) {
         if hash.is_empty() {
             return; // 跳过空哈希值
         }
         
-        let bands = match self.algorithm {
-            // 对于ORB算法的特征字符串，通常会很长，使用特殊处理策略
-            HashAlgorithm::ORB => {
-                // 提取固定数量的字符以创建签名
-                let signature_len = if hash.len() > 256 { 256 } else { hash.len() };
-                let signature = &hash[0..signature_len];
-                
-                // 对于ORB，简单地将所有bands个字符块作为签名
-                let band_size = signature_len / self.bands;
-                if band_size > 0 {
-                    split_hash_for_lsh(signature, self.bands)
-                } else {
-                    // 如果哈希太短，则使用整个哈希值
-                    vec![signature.to_string()]
-                }
-            },
-            // 对于其他哈希算法，采用标准分段方式
-            _ => split_hash_for_lsh(hash, self.bands),
-        };
+        let bands = self.get_hash_bands(hash);
+        if !bands.is_empty() {
+            self.indices.insert(index);
+        }
         
         // 限制添加到每个桶的索引数量，避免某些热点桶过大
+        // 桶满时新索引与优先级最低（值最大）的索引比较，保留优先级更高的一个
+        // 同一哈希的多个段可能相同（如纯色图像的全0哈希），同一桶中只记录一次
         for band in bands {
             let bucket = self.buckets.entry(band).or_insert_with(Vec::new);
+            if bucket.contains(&index) {
+                continue;
+            }
             if bucket.len() < self.max_bucket_size {
                 bucket.push(index);
+            } else if let Some(lowest) = bucket.iter_mut().max_by_key(|i| sample_priority(**i)) {
+                if sample_priority(index) < sample_priority(*lowest) {
+                    *lowest = index;
+                }
             }
         }
     }
@@ -98,18 +107,12 @@ impl LSHIndex {
         }
         
         match self.algorithm {
-            HashAlgorithm::ORB => {
-                // 对ORB特征使用特殊的分段策略
-                let signature_len = if hash.len() > 256 { 256 } else { hash.len() };
-                let signature = &hash[0..signature_len];
-                
-                let band_size = signature_len / self.bands;
-                if band_size > 0 {
-                    split_hash_for_lsh(signature, self.bands)
-                } else {
-                    vec![signature.to_string()]
-                }
+            // ORB特征是Base64编码的描述子集合，先折叠为64位SimHash签名再按二值哈希分段
+            HashAlgorithm::ORB => match orb::orb_signature(hash) {
+                Some(signature) => split_hash_for_lsh(&format!("{:064b}", signature), self.bands),
+                None => Vec::new(),
             },
+            // 对于其他哈希算法，采用标准分段方式
             _ => split_hash_for_lsh(hash, self.bands),
         }
     }
@@ -133,11 +136,15 @@ impl LSHIndex {
             .enumerate()
             .map(|(batch_idx, batch_hashes)| {
                 let mut local_buckets = HashMap::with_capacity(batch_hashes.len() / 2);
+                let mut local_indices = Vec::with_capacity(batch_hashes.len());
                 
                 for (i, hash) in batch_hashes.iter().enumerate() {
                     if !hash.is_empty() {
                         let idx = start_index + batch_idx * batch_size + i;
                         let bands = self.get_hash_bands(hash);
+                        if !bands.is_empty() {
+                            local_indices.push(idx);
+                        }
                         
                         for band in bands {
                             local_buckets.entry(band)
@@ -147,7 +154,7 @@ impl LSHIndex {
                     }
                 }
                 
-                local_buckets
+                (local_buckets, local_indices)
             })
             .collect();
 
@@ -160,7 +167,8 @@ impl LSHIndex {
         }
         
         // 合并新的批次结果
-        for local_buckets in partial_indices {
+        for (local_buckets, local_indices) in partial_indices {
+            self.indices.extend(local_indices);
             for (band, mut indices) in local_buckets {
                 let bucket = new_buckets.entry(band).or_insert_with(Vec::new);
                 bucket.append(&mut indices);
@@ -170,9 +178,8 @@ impl LSHIndex {
                     bucket.sort_unstable();
                     bucket.dedup();
                     if bucket.len() > self.max_bucket_size {
-                        // 保留最新的索引
-                        let start = bucket.len() - self.max_bucket_size;
-                        bucket.copy_within(start.., 0);
+                        // 保留优先级最高的索引，相当于与插入顺序无关的随机样本
+                        bucket.select_nth_unstable_by_key(self.max_bucket_size, |&i| sample_priority(i));
                         bucket.truncate(self.max_bucket_size);
                     }
                 }
@@ -182,138 +189,243 @@ impl LSHIndex {
         self.buckets = new_buckets;
     }
     
+    /// 从索引中移除哈希值
+    ///
+    /// `hash`必须与添加时使用的哈希相同，这样才能重新计算出相同的段；
+    /// 移除后变为空的桶会被删除。
+    pub fn remove(&mut self, hash: &str, index: usize) {
+        if hash.is_empty() {
+            return;
+        }
+
+        self.indices.remove(&index);
+        for band in self.get_hash_bands(hash) {
+            if let Some(bucket) = self.buckets.get_mut(&band) {
+                bucket.retain(|&i| i != index);
+                if bucket.is_empty() {
+                    self.buckets.remove(&band);
+                }
+            }
+        }
+    }
+
     /// 清空索引
     pub fn clear(&mut self) {
         self.buckets.clear();
+        self.indices.clear();
     }
     
     /// 获取索引中的哈希数量（去重）
     pub fn len(&self) -> usize {
-        // 计算所有索引的并集大小，防止重复计数
-        let mut all_indices = HashSet::<usize>::new();
-        for indices in self.buckets.values() {
-            all_indices.extend(indices);
-        }
-        all_indices.len()
+        self.indices.len()
     }
     
     /// 检查索引是否为空
     pub fn is_empty(&self) -> bool {
-        self.buckets.is_empty()
+        self.indices.is_empty()
     }
 }
 
+/// 桶满时决定保留哪些索引的优先级，值越小越优先
+///
+/// 对索引做伪随机混合，溢出的桶保留的是一个均匀样本，而不是最先或最后插入的那部分。
+fn sample_priority(index: usize) -> u64 {
+    splitmix64(index as u64)
+}
+
 /// 并行计算候选匹配对
 /// 对于大量哈希值，使用LSH并行计算可能的相似对
-pub fn compute_candidate_pairs(hashes: &[String], algorithm: HashAlgorithm) -> Vec<(usize, usize)> {
+///
+/// 所有哈希构建一个全局索引，每个哈希只查询一次并只保留`j > i`的对，因此结果不含重复。
+/// 返回的候选对按`(i, j)`升序排列。
+/// 查询按`batch_size`分批进行，限制并行查询产生的中间结果的内存占用。
+/// `max_bucket_size`为None时使用算法默认的桶大小。
+pub fn compute_candidate_pairs(
+    hashes: &[String],
+    algorithm: HashAlgorithm,
+    batch_size: usize,
+    max_bucket_size: Option<usize>
+) -> Vec<(usize, usize)> {
     if hashes.len() <= 1 {
         return Vec::new();
     }
     
-    // 使用更有效的分批处理方式
-    const BATCH_SIZE: usize = 10000;
+    // 构建全局LSH索引（批量添加内部已并行）
+    let mut lsh = LSHIndex::new(algorithm);
+    if let Some(max_bucket_size) = max_bucket_size {
+        lsh = lsh.with_max_bucket_size(max_bucket_size);
+    }
+    lsh.batch_add(hashes, 0);
     
-    if hashes.len() > BATCH_SIZE {
-        // 对于超大规模输入，分批处理以降低内存占用
-        let batch_count = (hashes.len() + BATCH_SIZE - 1) / BATCH_SIZE;
-        let mut all_pairs = Vec::new();
+    let mut pairs = Vec::new();
+    for (batch_idx, batch) in hashes.chunks(batch_size.max(1)).enumerate() {
+        let start = batch_idx * batch_size.max(1);
         
-        // 处理批次内部的匹配
-        for batch_idx in 0..batch_count {
-            let start = batch_idx * BATCH_SIZE;
-            let end = (start + BATCH_SIZE).min(hashes.len());
-            let batch = &hashes[start..end];
-            
-            // 计算批次内部的匹配对
-            let mut lsh = LSHIndex::new(algorithm);
-            for (i, hash) in batch.iter().enumerate() {
-                lsh.add(hash, i);
-            }
-            
-            // 并行查询每个哈希值
-            let batch_pairs: Vec<(usize, usize)> = batch.par_iter()
-                .enumerate()
-                .flat_map(|(i, hash)| {
-                    let candidate_indices = lsh.query(hash);
-                    candidate_indices.into_iter()
-                        .filter_map(move |j| {
-                            // 避免自匹配和重复匹配
-                            if j > i {
-                                Some((i + start, j + start))
-                            } else {
-                                None
-                            }
-                        })
-                        .collect::<Vec<_>>()
-                })
-                .collect();
-            
-            all_pairs.extend(batch_pairs);
-            
-            // 处理不同批次之间的匹配
-            if batch_idx > 0 {
-                for prev_batch_idx in 0..batch_idx {
-                    let prev_start = prev_batch_idx * BATCH_SIZE;
-                    let prev_end = (prev_start + BATCH_SIZE).min(hashes.len());
-                    let prev_batch = &hashes[prev_start..prev_end];
-                    
-                    // 创建新的LSH索引用于跨批次匹配
-                    let mut cross_lsh = LSHIndex::new(algorithm);
-                    for (i, hash) in prev_batch.iter().enumerate() {
-                        cross_lsh.add(hash, i);
-                    }
-                    
-                    // 当前批次的每个哈希查询前面批次的索引
-                    let cross_pairs: Vec<(usize, usize)> = batch.par_iter()
-                        .enumerate()
-                        .flat_map(|(i, hash)| {
-                            let prev_indices = cross_lsh.query(hash);
-                            prev_indices.into_iter()
-                                .map(move |j| (i + start, j + prev_start))
-                                .collect::<Vec<_>>()
-                        })
-                        .collect();
-                    
-                    all_pairs.extend(cross_pairs);
+        // 并行查询每个哈希值，避免自匹配和重复匹配
+        let batch_pairs: Vec<(usize, usize)> = batch.par_iter()
+            .enumerate()
+            .flat_map_iter(|(offset, hash)| {
+                let i = start + offset;
+                lsh.query(hash).into_iter()
+                    .filter(move |&j| j > i)
+                    .map(move |j| (i, j))
+            })
+            .collect();
+        
+        pairs.extend(batch_pairs);
+    }
+    
+    // 查询结果来自HashSet，顺序不确定；排序后每次运行得到相同的候选对序列，
+    // 分批计算相似度和并查集合并的过程也随之可复现
+    pairs.sort_unstable();
+    pairs
+}
+
+/// 并行计算打包为`u64`的64位二值哈希的候选匹配对
+///
+/// 分段方式、桶的上限和抽样规则与`LSHIndex`处理对应的"0"/"1"字符串完全相同
+/// （段按其比特值分桶，不区分所在位置），因此得到的候选对与`compute_candidate_pairs`一致，
+/// 只是不需要为每个段分配字符串。返回的候选对按`(i, j)`升序排列。
+pub fn compute_packed_candidate_pairs(
+    hashes: &[u64],
+    algorithm: HashAlgorithm,
+    batch_size: usize,
+    max_bucket_size: Option<usize>
+) -> Vec<(usize, usize)> {
+    if hashes.len() <= 1 {
+        return Vec::new();
+    }
+    
+    // 段数和默认桶大小与字符串索引相同
+    let defaults = LSHIndex::new(algorithm);
+    let bands = defaults.bands;
+    let max_bucket_size = max_bucket_size.map_or(defaults.max_bucket_size, |size| size.max(1));
+    let band_size = 64 / bands as u32;
+    let mask = if band_size >= 64 { u64::MAX } else { (1u64 << band_size) - 1 };
+    let band_keys = |hash: u64| (1..=bands as u32).map(move |band| (hash >> (64 - band * band_size)) & mask);
+    
+    // 构建桶：按段值排序后相邻的项即同一个桶
+    let mut entries: Vec<(u64, usize)> = hashes.par_iter()
+        .enumerate()
+        .flat_map_iter(|(index, &hash)| band_keys(hash).map(move |key| (key, index)))
+        .collect();
+    entries.par_sort_unstable();
+    entries.dedup();
+    
+    let mut buckets: HashMap<u64, Vec<usize>> = HashMap::new();
+    for chunk in entries.chunk_by(|a, b| a.0 == b.0) {
+        let mut bucket: Vec<usize> = chunk.iter().map(|&(_, index)| index).collect();
+        if bucket.len() > max_bucket_size {
+            // 保留优先级最高的索引，与`LSHIndex::batch_add`的抽样一致
+            bucket.select_nth_unstable_by_key(max_bucket_size, |&i| sample_priority(i));
+            bucket.truncate(max_bucket_size);
+        }
+        buckets.insert(chunk[0].0, bucket);
+    }
+    
+    let mut pairs = Vec::new();
+    for (batch_idx, batch) in hashes.chunks(batch_size.max(1)).enumerate() {
+        let start = batch_idx * batch_size.max(1);
+        
+        let batch_pairs: Vec<(usize, usize)> = batch.par_iter()
+            .enumerate()
+            .flat_map_iter(|(offset, &hash)| {
+                let i = start + offset;
+                let mut candidates: Vec<usize> = band_keys(hash)
+                    .filter_map(|key| buckets.get(&key))
+                    .flatten()
+                    .copied()
+                    .filter(|&j| j > i)
+                    .collect();
+                candidates.sort_unstable();
+                candidates.dedup();
+                candidates.into_iter().map(move |j| (i, j))
+            })
+            .collect();
+        
+        pairs.extend(batch_pairs);
+    }
+    
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::utils::hash_utils::binary_to_u64;
+    
+    /// 由随机基准哈希各派生若干变体（随机翻转0-3位），与分组基准的数据分布相同
+    fn clustered_hashes(clusters: usize, variants: usize, seed: u64) -> Vec<String> {
+        let mut rng = fastrand::Rng::with_seed(seed);
+        let mut hashes = Vec::with_capacity(clusters * variants);
+        for _ in 0..clusters {
+            let base = rng.u64(..);
+            for _ in 0..variants {
+                let mut variant = base;
+                for _ in 0..rng.usize(..=3) {
+                    variant ^= 1 << rng.u32(..64);
                 }
+                hashes.push(format!("{:064b}", variant));
             }
         }
+        rng.shuffle(&mut hashes);
+        hashes
+    }
+    
+    #[test]
+    fn packed_banding_matches_string_banding() {
+        let hashes = clustered_hashes(200, 5, 1);
+        let packed: Vec<u64> = hashes.iter().map(|hash| binary_to_u64(hash).unwrap()).collect();
         
-        // 去重
-        let mut unique_pairs = HashSet::with_capacity(all_pairs.len());
-        for pair in all_pairs {
-            unique_pairs.insert(pair);
+        for algorithm in [HashAlgorithm::Average, HashAlgorithm::Difference, HashAlgorithm::Perceptual] {
+            // 小批次和小桶分别覆盖跨批查询和桶满后的抽样
+            for (batch_size, max_bucket_size) in [(10_000, None), (37, None), (10_000, Some(3))] {
+                let from_strings = compute_candidate_pairs(&hashes, algorithm, batch_size, max_bucket_size);
+                let from_packed = compute_packed_candidate_pairs(&packed, algorithm, batch_size, max_bucket_size);
+                assert!(!from_strings.is_empty());
+                assert_eq!(from_packed, from_strings, "{} batch={} max_bucket={:?}", algorithm, batch_size, max_bucket_size);
+            }
         }
+    }
+    
+    #[test]
+    fn removed_index_is_no_longer_returned() {
+        let hashes = clustered_hashes(1, 4, 2);
+        let mut index = LSHIndex::new(HashAlgorithm::Average);
+        for (i, hash) in hashes.iter().enumerate() {
+            index.add(hash, i);
+        }
+        assert_eq!(index.len(), 4);
         
-        unique_pairs.into_iter().collect()
-    } else {
-        // 对于小规模数据，使用原始方法
-        // 创建LSH索引
-        let mut lsh = LSHIndex::new(algorithm);
+        index.remove(&hashes[1], 1);
+        assert_eq!(index.len(), 3);
+        for hash in &hashes {
+            let candidates = index.query(hash);
+            assert!(!candidates.contains(&1), "已移除的索引仍出现在查询结果中: {:?}", candidates);
+        }
+        // 同一簇的其余索引不受影响
+        let mut remaining = index.query(&hashes[0]);
+        remaining.sort_unstable();
+        assert_eq!(remaining, vec![0, 2, 3]);
         
-        // 添加所有哈希值到索引
+        // 重新添加后可以再次查到
+        index.add(&hashes[1], 1);
+        assert!(index.query(&hashes[1]).contains(&1));
+        
+        // 全部移除后不留下空桶
         for (i, hash) in hashes.iter().enumerate() {
-            lsh.add(hash, i);
+            index.remove(hash, i);
         }
+        assert!(index.is_empty());
+        assert!(index.buckets.is_empty());
+        assert!(hashes.iter().all(|hash| index.query(hash).is_empty()));
         
-        // 并行查询所有候选对
-        let pairs: HashSet<(usize, usize)> = hashes.par_iter()
-            .enumerate()
-            .flat_map(|(i, hash)| {
-                let candidate_indices = lsh.query(hash);
-                candidate_indices.into_iter()
-                    .filter_map(move |j| {
-                        // 避免自匹配和重复匹配
-                        if j > i {
-                            Some((i, j))
-                        } else {
-                            None
-                        }
-                    })
-                    .collect::<Vec<_>>()
-            })
-            .collect();
-        
-        pairs.into_iter().collect()
+        // clear与逐个移除的结果相同
+        for (i, hash) in hashes.iter().enumerate() {
+            index.add(hash, i);
+        }
+        index.clear();
+        assert!(index.is_empty());
+        assert!(index.buckets.is_empty());
     }
-}
\ No newline at end of file
+}
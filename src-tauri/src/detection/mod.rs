@@ -1,6 +1,12 @@
 pub mod duplicate;
 pub mod lsh;
+pub mod progress;
+pub mod report;
+pub mod resolve;
 
 // 重新导出公共接口
 pub use duplicate::*;
-pub use lsh::*;
\ No newline at end of file
+pub use lsh::*;
+pub use progress::*;
+pub use report::*;
+pub use resolve::*;
\ No newline at end of file
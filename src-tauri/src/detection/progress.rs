@@ -0,0 +1,117 @@
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::Instant;
+use serde::{Serialize, Deserialize};
+use crate::core::types::DuplicateGroup;
+
+/// 两次进度报告之间的最短间隔（毫秒），除非进度又推进了至少1%
+pub const PROGRESS_MIN_INTERVAL_MS: u64 = 100;
+
+/// 扫描阶段的进度
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScanProgress {
+    /// 已遍历的文件数
+    pub scanned_files: usize,
+    /// 其中的图像文件数
+    pub image_files: usize,
+    /// 扫描是否已完成
+    pub finished: bool,
+}
+
+/// 哈希阶段的进度
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HashProgress {
+    /// 已处理（成功或失败）的图像数
+    pub processed: usize,
+    /// 需要处理的图像总数
+    pub total: usize,
+    /// 按目前的平均吞吐量线性外推的剩余时间（毫秒），尚未处理任何图像时为None
+    #[serde(default)]
+    pub eta_ms: Option<u64>,
+}
+
+/// 检测过程的进度回调
+///
+/// 检测流程本身不依赖Tauri，由API层实现该trait，把进度转发为前端事件
+pub trait ProgressReporter: Sync {
+    /// 报告扫描阶段的进度
+    fn scan_progress(&self, _progress: &ScanProgress) {}
+    
+    /// 报告哈希阶段的进度，调用频率已由`ProgressThrottle`限制
+    fn hash_progress(&self, _progress: &HashProgress) {}
+    
+    /// 报告一个已经确定的重复组，组按完成顺序报告，不按大小排序
+    ///
+    /// 报告的组与最终结果一致；设置了`max_groups`时，截断后才按结果顺序报告保留的组
+    fn group_found(&self, _group: &DuplicateGroup) {}
+    
+    /// 用户是否已请求取消
+    ///
+    /// 哈希阶段在开始处理每张图像前检查，取消后只对已完成的图像分组并返回部分结果
+    fn is_cancelled(&self) -> bool {
+        false
+    }
+}
+
+/// 不报告任何进度
+pub struct NoProgress;
+
+impl ProgressReporter for NoProgress {}
+
+/// 进度报告的节流器，避免逐张图像发送事件淹没前端
+///
+/// 距上次报告超过`PROGRESS_MIN_INTERVAL_MS`毫秒，或进度推进了至少1%时才允许报告，
+/// 以先满足者为准；全部完成时总是报告。可以在多个线程中同时调用。
+pub struct ProgressThrottle {
+    start: Instant,
+    total: usize,
+    step: usize,
+    last_emit_ms: AtomicU64,
+    last_emit_count: AtomicUsize,
+}
+
+impl ProgressThrottle {
+    /// 为总数为`total`的任务创建节流器
+    pub fn new(total: usize) -> Self {
+        Self {
+            start: Instant::now(),
+            total,
+            step: (total / 100).max(1),
+            last_emit_ms: AtomicU64::new(0),
+            last_emit_count: AtomicUsize::new(0),
+        }
+    }
+    
+    /// 完成数为`done`时是否应该报告进度
+    pub fn should_emit(&self, done: usize) -> bool {
+        if done >= self.total {
+            return true;
+        }
+        
+        let now = self.start.elapsed().as_millis() as u64;
+        let last_ms = self.last_emit_ms.load(Ordering::Relaxed);
+        let last_count = self.last_emit_count.load(Ordering::Relaxed);
+        if now.saturating_sub(last_ms) < PROGRESS_MIN_INTERVAL_MS && done.saturating_sub(last_count) < self.step {
+            return false;
+        }
+        
+        // 多个线程同时满足条件时只有一个报告
+        if self.last_emit_ms.compare_exchange(last_ms, now, Ordering::Relaxed, Ordering::Relaxed).is_err() {
+            return false;
+        }
+        self.last_emit_count.fetch_max(done, Ordering::Relaxed);
+        true
+    }
+    
+    /// 按开始以来的平均吞吐量估算完成剩余`total - done`项还需要的毫秒数
+    ///
+    /// 只是线性外推，解码耗时差异较大的图库中会有波动；`done`为0时无法估算。
+    pub fn estimate_remaining_ms(&self, done: usize) -> Option<u64> {
+        if done == 0 {
+            return None;
+        }
+        
+        let remaining = self.total.saturating_sub(done) as u128;
+        let elapsed = self.start.elapsed().as_millis();
+        Some((elapsed * remaining / done as u128) as u64)
+    }
+}
@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::Path;
+use rayon::prelude::*;
+use crate::core::types::DetectionResults;
+use crate::core::utils::{hash_utils, image_utils};
+
+/// 报告中缩略图的默认最大边长
+pub const REPORT_THUMBNAIL_SIZE: u32 = 160;
+
+/// 报告的内联样式，保证离线打开时无需任何外部资源
+const REPORT_STYLE: &str = "\
+body{font-family:sans-serif;margin:24px;color:#222;background:#fafafa}\
+h1{font-size:22px}\
+.summary{margin-bottom:24px;color:#555}\
+.group{background:#fff;border:1px solid #ddd;border-radius:6px;padding:12px;margin-bottom:16px}\
+.group h2{font-size:16px;margin:0 0 8px}\
+.images{display:flex;flex-wrap:wrap;gap:12px}\
+.image{width:180px;font-size:12px;word-break:break-all}\
+.image img{max-width:160px;max-height:160px;display:block;margin-bottom:4px}\
+.missing{width:160px;height:100px;background:#eee;color:#999;display:flex;align-items:center;justify-content:center;margin-bottom:4px}";
+
+/// 将检测结果渲染为自包含的HTML报告
+///
+/// 缩略图以Base64的data URI内嵌，报告可以脱离原始图片在任何浏览器中离线查看。
+/// 无法生成缩略图的图像显示占位块。
+pub fn render_html_report(results: &DetectionResults, thumbnail_size: u32) -> String {
+    // 并行生成所有缩略图，同一图像只生成一次
+    let paths: Vec<&str> = results.groups.iter()
+        .flat_map(|group| group.images.iter().map(|img| img.path.as_str()))
+        .collect::<std::collections::HashSet<_>>()
+        .into_iter()
+        .collect();
+    let thumbnails: HashMap<&str, String> = paths.par_iter()
+        .filter_map(|&path| {
+            image_utils::generate_thumbnail(Path::new(path), thumbnail_size)
+                .ok()
+                .map(|thumbnail| (path, thumbnail))
+        })
+        .collect();
+
+    let image_count: usize = results.groups.iter().map(|g| g.images.len()).sum();
+    let total_wasted: u64 = results.groups.iter().map(|g| g.wasted_bytes).sum();
+
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html lang=\"zh-CN\">\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str("<title>重复图片报告</title>\n");
+    let _ = writeln!(html, "<style>{}</style>\n</head>\n<body>", REPORT_STYLE);
+    html.push_str("<h1>重复图片报告</h1>\n");
+    let _ = writeln!(
+        html,
+        "<div class=\"summary\">共 {} 组重复图片，涉及 {} 张图片，可释放 {}{}</div>",
+        results.groups.len(),
+        image_count,
+        format_bytes(total_wasted),
+        if results.partial { "（检测被取消，结果不完整）" } else { "" }
+    );
+
+    for (index, group) in results.groups.iter().enumerate() {
+        let _ = writeln!(
+            html,
+            "<div class=\"group\">\n<h2>第 {} 组：{} 张图片，可释放 {}</h2>\n<div class=\"images\">",
+            index + 1,
+            group.images.len(),
+            format_bytes(group.wasted_bytes)
+        );
+
+        for image in &group.images {
+            html.push_str("<div class=\"image\">");
+            match thumbnails.get(image.path.as_str()) {
+                Some(thumbnail) => {
+                    let _ = write!(html, "<img src=\"data:image/png;base64,{}\" alt=\"\">", thumbnail);
+                }
+                None => html.push_str("<div class=\"missing\">无预览</div>"),
+            }
+            let _ = writeln!(
+                html,
+                "<div>{}</div><div>{}×{} · {}</div></div>",
+                escape_html(&image.path),
+                image.width,
+                image.height,
+                format_bytes(image.size_bytes)
+            );
+        }
+
+        html.push_str("</div>\n</div>\n");
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+/// 将检测结果序列化为JSON报告
+///
+/// `hex_hashes`为true时，二值哈希（均值、差值、感知、边缘哈希）转换为十六进制表示，
+/// 64位哈希从64个字符缩短为16个字符，可用`hash_utils::hex_to_binary`还原；
+/// 精确哈希和ORB特征保持原样。只影响导出的内容，检测内部仍使用二进制字符串。
+pub fn render_json_report(results: &DetectionResults, hex_hashes: bool) -> Result<String, String> {
+    let serialize = |results: &DetectionResults| {
+        serde_json::to_string_pretty(results)
+            .map_err(|e| format!("无法序列化检测结果: {}", e))
+    };
+
+    if !hex_hashes {
+        return serialize(results);
+    }
+
+    let mut exported = results.clone();
+    for image in exported.groups.iter_mut().flat_map(|group| group.images.iter_mut()) {
+        if let Some(hex) = hash_utils::binary_to_hex(&image.hash) {
+            image.hash = hex;
+        }
+    }
+    serialize(&exported)
+}
+
+/// 转义HTML特殊字符
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// 将字节数格式化为便于阅读的大小
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
@@ -0,0 +1,113 @@
+use std::path::Path;
+use crate::core::types::{
+    DuplicateGroup, FileResolution, GroupPlan, GroupResolution, ImageInfo, KeepRule, ResolveAction, ResolveReport,
+};
+use crate::core::utils::file_utils::{move_to_trash, replace_with_hardlink};
+
+/// 按保留规则为每个重复组生成处理计划
+///
+/// 每组保留一张图像，其余图像列入待处理列表；少于两张图像的组没有可处理的文件，不生成计划。
+pub fn plan_deletions(groups: &[DuplicateGroup], keep_rule: KeepRule) -> Vec<GroupPlan> {
+    groups.iter()
+        .filter(|group| group.images.len() > 1)
+        .map(|group| {
+            let keep_index = select_keep(&group.images, keep_rule);
+            let keep = group.images[keep_index].path.clone();
+            let remove = group.images.iter()
+                .enumerate()
+                .filter(|&(idx, image)| idx != keep_index && !is_same_file(&image.path, &keep))
+                .map(|(_, image)| image.clone())
+                .collect();
+            
+            GroupPlan { keep, remove }
+        })
+        .collect()
+}
+
+/// 按保留规则处理重复组，返回每个文件的处理结果
+///
+/// 单个文件失败不影响其他文件。保留的文件已不存在时跳过整组，避免删除最后一份副本。
+/// `DryRun`只生成计划，不对文件系统做任何修改。
+pub fn resolve_duplicates(groups: &[DuplicateGroup], keep_rule: KeepRule, action: ResolveAction) -> ResolveReport {
+    let mut report = ResolveReport {
+        action,
+        groups: Vec::new(),
+        succeeded: 0,
+        failed: 0,
+        freed_bytes: 0,
+    };
+    
+    for plan in plan_deletions(groups, keep_rule) {
+        let keep_exists = action == ResolveAction::DryRun || Path::new(&plan.keep).is_file();
+        
+        let files = plan.remove.iter()
+            .map(|image| {
+                let path = Path::new(&image.path);
+                let result = if !keep_exists {
+                    Err(format!("保留的文件不存在，跳过: {}", plan.keep))
+                } else {
+                    match action {
+                        ResolveAction::KeepOnly => move_to_trash(path),
+                        ResolveAction::Hardlink => replace_with_hardlink(path, Path::new(&plan.keep)),
+                        ResolveAction::DryRun => Ok(()),
+                    }
+                };
+                
+                match result {
+                    Ok(()) => {
+                        report.succeeded += 1;
+                        report.freed_bytes += image.size_bytes;
+                        FileResolution { path: image.path.clone(), success: true, error: None }
+                    },
+                    Err(e) => {
+                        report.failed += 1;
+                        FileResolution { path: image.path.clone(), success: false, error: Some(e) }
+                    },
+                }
+            })
+            .collect();
+        
+        report.groups.push(GroupResolution { keep: plan.keep, files });
+    }
+    
+    println!("重复组处理完成({:?})，成功 {} 个文件，失败 {} 个，释放 {} 字节", 
+             action, report.succeeded, report.failed, report.freed_bytes);
+    report
+}
+
+/// 按规则选出组内保留的图像，规则相同时取路径字典序最小的
+fn select_keep(images: &[ImageInfo], keep_rule: KeepRule) -> usize {
+    let by_path = |a: &ImageInfo, b: &ImageInfo| a.path.cmp(&b.path);
+    let pixels = |image: &ImageInfo| image.width as u64 * image.height as u64;
+    // 没有修改时间（空字符串）的图像在新旧规则中都排在最后
+    fn modified(image: &ImageInfo) -> Option<&str> {
+        Some(image.modified_at.as_str()).filter(|time| !time.is_empty())
+    }
+    fn oldest_key(image: &ImageInfo) -> (bool, Option<&str>) {
+        (modified(image).is_none(), modified(image))
+    }
+    
+    let indexed = images.iter().enumerate();
+    let best = match keep_rule {
+        KeepRule::Largest => indexed.min_by(|(_, a), (_, b)| b.size_bytes.cmp(&a.size_bytes).then_with(|| by_path(a, b))),
+        KeepRule::Smallest => indexed.min_by(|(_, a), (_, b)| a.size_bytes.cmp(&b.size_bytes).then_with(|| by_path(a, b))),
+        KeepRule::Newest => indexed.min_by(|(_, a), (_, b)| {
+            modified(b).cmp(&modified(a)).then_with(|| by_path(a, b))
+        }),
+        KeepRule::Oldest => indexed.min_by(|(_, a), (_, b)| {
+            oldest_key(a).cmp(&oldest_key(b)).then_with(|| by_path(a, b))
+        }),
+        KeepRule::HighestResolution => indexed.min_by(|(_, a), (_, b)| pixels(b).cmp(&pixels(a)).then_with(|| by_path(a, b))),
+        KeepRule::ShortestPath => indexed.min_by(|(_, a), (_, b)| a.path.len().cmp(&b.path.len()).then_with(|| by_path(a, b))),
+    };
+    
+    best.map(|(idx, _)| idx).unwrap_or(0)
+}
+
+/// 两个路径是否指向同一个文件（例如符号链接），这样的文件不能当作副本处理
+fn is_same_file(path: &str, other: &str) -> bool {
+    match (Path::new(path).canonicalize(), Path::new(other).canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => path == other,
+    }
+}
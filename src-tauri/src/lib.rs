@@ -7,16 +7,39 @@ use tauri::command;
 use std::path::PathBuf;
 
 // 重新导出API函数
-pub use api::{get_image_paths, find_duplicates, get_supported_algorithms, get_detection_stats, get_folder_stats};
-pub use core::types::{HashAlgorithm, DuplicateGroup, DuplicateDetectionRequest};
+pub use api::{get_image_paths, find_duplicates, cancel_detection, find_similar_to, compare_hex_hashes, find_similar_to_imagehash, find_matches, hash_folder, compare_files_by_blocks, suggest_threshold, similarity_histogram, benchmark_detection, get_keypoints, explain_similarity, get_image_info, get_result_summary, get_result_page, export_results_json, export_report_html, resolve_duplicates, ResultStore, CancelFlag, get_supported_algorithms, get_detection_stats, get_folder_stats};
+pub use core::types::{HashAlgorithm, AlgorithmParams, DuplicateGroup, DuplicateDetectionRequest, ImageInfo, ImageFileInfo, HashOptions, ResizeFilter};
+pub use algorithms::{calculate_hash_from_image, calculate_similarity};
+pub use detection::{find_duplicate_groups, find_duplicate_groups_multi, DuplicateDetectionParams, compute_candidate_pairs, compute_packed_candidate_pairs};
+pub use core::utils::{binary_to_u64, hash_similarity, packed_hash_similarity, gray_image_to_matrix, matrix_to_gray_image};
 
 /// 应用入口函数
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
+        .manage(ResultStore::default())
+        .manage(CancelFlag::default())
         .invoke_handler(tauri::generate_handler![
             get_image_paths,
             find_duplicates,
+            cancel_detection,
+            get_result_summary,
+            get_result_page,
+            export_results_json,
+            export_report_html,
+            resolve_duplicates,
+            find_similar_to,
+            compare_hex_hashes,
+            find_similar_to_imagehash,
+            find_matches,
+            hash_folder,
+            compare_files_by_blocks,
+            suggest_threshold,
+            similarity_histogram,
+            benchmark_detection,
+            get_keypoints,
+            explain_similarity,
+            get_image_info,
             get_supported_algorithms,
             get_detection_stats,
             get_folder_stats
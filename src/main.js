@@ -9,7 +9,7 @@ import "./assets/css/main.css";
 
 // 创建全局状态存储
 const globalState = {
-  duplicateGroups: [],
+  groupCount: 0,
   selectedFolders: [],
   algorithm: '',
   similarityThreshold: 0,
@@ -0,0 +1,19 @@
+/**
+ * 检测结果分页获取
+ * 后端只在 find_duplicates 中返回摘要，重复组需要通过 get_result_page 分页获取
+ */
+
+/** 每次请求获取的重复组数量 */
+export const PAGE_SIZE = 500;
+
+/**
+ * 获取最近一次检测结果中的一页重复组
+ * 结果页在用户滚动到底部时按需加载，不会一次取回全部结果
+ * @param {Function} invoke - Tauri 的 invoke 函数
+ * @param {number} offset - 起始重复组序号
+ * @param {number} [limit=PAGE_SIZE] - 本页最多返回的重复组数量
+ * @returns {Promise<Array>} 本页的重复组，超出结果范围时为空数组
+ */
+export async function fetchGroupPage(invoke, offset, limit = PAGE_SIZE) {
+  return (await invoke("get_result_page", { offset, limit })) || [];
+}